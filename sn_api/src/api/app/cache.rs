@@ -0,0 +1,472 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::Safe;
+use crate::{Error, Result};
+use futures::lock::Mutex;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use xor_name::XorName;
+
+// Default number of seconds the latest version of mutable content is kept in the cache
+const DEFAULT_CACHE_TTL_SECS: u64 = 5;
+
+// Maximum number of bytes of content kept in memory, oldest items are evicted first
+const MAX_MEMORY_CACHE_SIZE: usize = 64 * 1024 * 1024;
+
+// Folders within the on-disk cache for each type of item
+const CACHE_BLOBS_DIRNAME: &str = "blobs";
+const CACHE_SEQUENCE_ENTRIES_DIRNAME: &str = "sequence_entries";
+const CACHE_SEQUENCE_LAST_ENTRIES_DIRNAME: &str = "sequence_last_entries";
+
+/// Statistics about the content and usage of the resolution cache
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheStats {
+    /// Number of lookups served from the cache
+    pub hits: u64,
+    /// Number of lookups which had to be sent to the network
+    pub misses: u64,
+    /// Number of items currently kept in memory
+    pub memory_entries: usize,
+    /// Number of bytes of content currently kept in memory
+    pub memory_size: usize,
+    /// Location of the on-disk cache, if enabled
+    pub disk_dir: Option<PathBuf>,
+    /// Number of items currently stored in the on-disk cache
+    pub disk_entries: usize,
+    /// Number of bytes currently stored in the on-disk cache
+    pub disk_size: u64,
+}
+
+// Each of the type of items we keep in the cache. Blobs and specific entries of
+// a Sequence are immutable, thus they never expire, whilst the last entry of a
+// Sequence (i.e. the latest version of a FilesContainer or NrsMapContainer) is
+// only kept for the configured TTL.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum CacheKey {
+    Blob(XorName),
+    SequenceEntry {
+        name: XorName,
+        tag: u64,
+        private: bool,
+        index: u64,
+    },
+    SequenceLastEntry {
+        name: XorName,
+        tag: u64,
+        private: bool,
+    },
+}
+
+impl CacheKey {
+    fn expires(&self) -> bool {
+        matches!(self, CacheKey::SequenceLastEntry { .. })
+    }
+
+    // Content of private Sequences is never persisted on disk
+    fn is_private(&self) -> bool {
+        match self {
+            CacheKey::Blob(_) => false,
+            CacheKey::SequenceEntry { private, .. }
+            | CacheKey::SequenceLastEntry { private, .. } => *private,
+        }
+    }
+
+    fn disk_path(&self, dir: &Path) -> PathBuf {
+        let visibility = |private: &bool| if *private { "private" } else { "public" };
+        match self {
+            CacheKey::Blob(name) => dir.join(CACHE_BLOBS_DIRNAME).join(hex::encode(name.0)),
+            CacheKey::SequenceEntry {
+                name,
+                tag,
+                private,
+                index,
+            } => dir.join(CACHE_SEQUENCE_ENTRIES_DIRNAME).join(format!(
+                "{}_{}_{}_{}",
+                hex::encode(name.0),
+                tag,
+                visibility(private),
+                index
+            )),
+            CacheKey::SequenceLastEntry { name, tag, private } => {
+                dir.join(CACHE_SEQUENCE_LAST_ENTRIES_DIRNAME).join(format!(
+                    "{}_{}_{}",
+                    hex::encode(name.0),
+                    tag,
+                    visibility(private)
+                ))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedItem {
+    version: u64,
+    data: Vec<u8>,
+    // Seconds since UNIX epoch when the item was stored
+    cached_at: u64,
+}
+
+#[derive(Debug)]
+struct CacheState {
+    items: BTreeMap<CacheKey, CachedItem>,
+    insertion_order: VecDeque<CacheKey>,
+    memory_size: usize,
+    ttl: Duration,
+    disk_dir: Option<PathBuf>,
+    hits: u64,
+    misses: u64,
+}
+
+// In-process cache of content fetched from the network, optionally persisted on disk
+#[derive(Debug, Clone)]
+pub(crate) struct ResolutionCache {
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl Default for ResolutionCache {
+    fn default() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CacheState {
+                items: BTreeMap::new(),
+                insertion_order: VecDeque::new(),
+                memory_size: 0,
+                ttl: Duration::from_secs(DEFAULT_CACHE_TTL_SECS),
+                disk_dir: None,
+                hits: 0,
+                misses: 0,
+            })),
+        }
+    }
+}
+
+impl ResolutionCache {
+    pub async fn get(&self, key: &CacheKey) -> Option<(u64, Vec<u8>)> {
+        let mut state = self.state.lock().await;
+        let ttl = state.ttl;
+        let is_fresh = |item: &CachedItem| !key.expires() || secs_since(item.cached_at) < ttl;
+
+        let item = match state.items.get(key).cloned() {
+            Some(item) if is_fresh(&item) => Some(item),
+            Some(_) => {
+                state.remove_from_memory(key);
+                None
+            }
+            None => None,
+        };
+
+        let item = match item {
+            Some(item) => Some(item),
+            None => match state.read_from_disk(key) {
+                Some(item) if is_fresh(&item) => {
+                    state.insert_in_memory(key.clone(), item.clone());
+                    Some(item)
+                }
+                _ => None,
+            },
+        };
+
+        match item {
+            Some(item) => {
+                debug!("Cache hit for {:?}", key);
+                state.hits += 1;
+                Some((item.version, item.data))
+            }
+            None => {
+                state.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub async fn put(&self, key: CacheKey, version: u64, data: &[u8]) {
+        let item = CachedItem {
+            version,
+            data: data.to_vec(),
+            cached_at: secs_since_epoch(),
+        };
+
+        let mut state = self.state.lock().await;
+        state.write_to_disk(&key, &item);
+        state.insert_in_memory(key, item);
+    }
+
+    pub async fn remove(&self, key: &CacheKey) {
+        let mut state = self.state.lock().await;
+        state.remove_from_memory(key);
+        if let Some(dir) = &state.disk_dir {
+            let _ = fs::remove_file(key.disk_path(dir));
+        }
+    }
+
+    pub async fn set_ttl(&self, ttl: Duration) {
+        self.state.lock().await.ttl = ttl;
+    }
+
+    pub async fn set_disk_dir(&self, dir: Option<&Path>) -> Result<()> {
+        if let Some(path) = dir {
+            for subdir in &[
+                CACHE_BLOBS_DIRNAME,
+                CACHE_SEQUENCE_ENTRIES_DIRNAME,
+                CACHE_SEQUENCE_LAST_ENTRIES_DIRNAME,
+            ] {
+                fs::create_dir_all(path.join(subdir)).map_err(|err| {
+                    Error::FileSystemError(format!(
+                        "Failed to create cache folder at '{}': {}",
+                        path.display(),
+                        err
+                    ))
+                })?;
+            }
+        }
+
+        self.state.lock().await.disk_dir = dir.map(|path| path.to_path_buf());
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> CacheStats {
+        let state = self.state.lock().await;
+        let (disk_entries, disk_size) = match &state.disk_dir {
+            Some(dir) => disk_usage(dir),
+            None => (0, 0),
+        };
+
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            memory_entries: state.items.len(),
+            memory_size: state.memory_size,
+            disk_dir: state.disk_dir.clone(),
+            disk_entries,
+            disk_size,
+        }
+    }
+
+    pub async fn clear(&self) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.items.clear();
+        state.insertion_order.clear();
+        state.memory_size = 0;
+        state.hits = 0;
+        state.misses = 0;
+
+        if let Some(dir) = &state.disk_dir {
+            for subdir in &[
+                CACHE_BLOBS_DIRNAME,
+                CACHE_SEQUENCE_ENTRIES_DIRNAME,
+                CACHE_SEQUENCE_LAST_ENTRIES_DIRNAME,
+            ] {
+                let path = dir.join(subdir);
+                if path.exists() {
+                    fs::remove_dir_all(&path)
+                        .and_then(|_| fs::create_dir_all(&path))
+                        .map_err(|err| {
+                            Error::FileSystemError(format!(
+                                "Failed to clear cache folder at '{}': {}",
+                                path.display(),
+                                err
+                            ))
+                        })?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CacheState {
+    fn insert_in_memory(&mut self, key: CacheKey, item: CachedItem) {
+        if item.data.len() > MAX_MEMORY_CACHE_SIZE {
+            return;
+        }
+
+        self.remove_from_memory(&key);
+        while self.memory_size + item.data.len() > MAX_MEMORY_CACHE_SIZE {
+            match self.insertion_order.pop_front() {
+                Some(oldest) => {
+                    if let Some(evicted) = self.items.remove(&oldest) {
+                        self.memory_size -= evicted.data.len();
+                    }
+                }
+                None => break,
+            }
+        }
+
+        self.memory_size += item.data.len();
+        self.insertion_order.push_back(key.clone());
+        self.items.insert(key, item);
+    }
+
+    fn remove_from_memory(&mut self, key: &CacheKey) {
+        if let Some(item) = self.items.remove(key) {
+            self.memory_size -= item.data.len();
+            self.insertion_order.retain(|k| k != key);
+        }
+    }
+
+    fn read_from_disk(&self, key: &CacheKey) -> Option<CachedItem> {
+        let path = key.disk_path(self.disk_dir.as_ref()?);
+        let bytes = fs::read(&path).ok()?;
+        match bincode::deserialize(&bytes) {
+            Ok(item) => Some(item),
+            Err(err) => {
+                warn!(
+                    "Ignoring corrupted cache file '{}': {}",
+                    path.display(),
+                    err
+                );
+                let _ = fs::remove_file(&path);
+                None
+            }
+        }
+    }
+
+    fn write_to_disk(&self, key: &CacheKey, item: &CachedItem) {
+        if key.is_private() {
+            return;
+        }
+
+        if let Some(dir) = &self.disk_dir {
+            let path = key.disk_path(dir);
+            let result = bincode::serialize(item)
+                .map_err(|err| err.to_string())
+                .and_then(|bytes| fs::write(&path, bytes).map_err(|err| err.to_string()));
+            if let Err(err) = result {
+                warn!("Failed to write cache file '{}': {}", path.display(), err);
+            }
+        }
+    }
+}
+
+fn secs_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn secs_since(timestamp: u64) -> Duration {
+    Duration::from_secs(secs_since_epoch().saturating_sub(timestamp))
+}
+
+fn disk_usage(dir: &Path) -> (usize, u64) {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .fold((0, 0), |(count, size), metadata| {
+            (count + 1, size + metadata.len())
+        })
+}
+
+impl Safe {
+    /// # Set how long the latest version of mutable content is cached for
+    /// Blobs and specific versions of Sequences are immutable, therefore they are
+    /// cached without expiration. Looking up the latest version of a Sequence
+    /// (e.g. the current NrsMap or FilesMap) is only cached for this amount of time.
+    pub async fn set_cache_ttl(&mut self, ttl: Duration) {
+        self.safe_client.cache.set_ttl(ttl).await
+    }
+
+    /// # Enable (or disable if `None`) the on-disk cache at the given folder
+    /// Content cached on disk can be shared across different instances and processes.
+    pub async fn set_cache_dir(&mut self, dir: Option<&Path>) -> Result<()> {
+        self.safe_client.cache.set_disk_dir(dir).await
+    }
+
+    /// # Retrieve statistics about the resolution cache
+    pub async fn cache_stats(&self) -> CacheStats {
+        self.safe_client.cache.stats().await
+    }
+
+    /// # Remove all content from the resolution cache, including the on-disk cache if enabled
+    pub async fn cache_clear(&self) -> Result<()> {
+        self.safe_client.cache.clear().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn test_cache_last_entry_expires() -> Result<()> {
+        let cache = ResolutionCache::default();
+        let name: XorName = rand::random();
+        let blob_key = CacheKey::Blob(name);
+        let last_entry_key = CacheKey::SequenceLastEntry {
+            name,
+            tag: 1_100,
+            private: false,
+        };
+
+        cache.put(blob_key.clone(), 0, b"blob").await;
+        cache.put(last_entry_key.clone(), 3, b"entry").await;
+        assert_eq!(cache.get(&blob_key).await, Some((0, b"blob".to_vec())));
+        assert_eq!(
+            cache.get(&last_entry_key).await,
+            Some((3, b"entry".to_vec()))
+        );
+
+        cache.set_ttl(Duration::from_secs(0)).await;
+        assert_eq!(cache.get(&blob_key).await, Some((0, b"blob".to_vec())));
+        assert_eq!(cache.get(&last_entry_key).await, None);
+
+        let stats = cache.stats().await;
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.memory_entries, 1);
+        assert_eq!(stats.memory_size, 4);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_on_disk() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!(
+            "sn_api_cache_{}",
+            hex::encode(rand::random::<XorName>().0)
+        ));
+        let key = CacheKey::SequenceEntry {
+            name: rand::random(),
+            tag: 1_100,
+            private: false,
+            index: 2,
+        };
+
+        let cache = ResolutionCache::default();
+        cache.set_disk_dir(Some(&dir)).await?;
+        cache.put(key.clone(), 2, b"entry").await;
+
+        // a different cache instance using the same folder finds it
+        let other_cache = ResolutionCache::default();
+        other_cache.set_disk_dir(Some(&dir)).await?;
+        assert_eq!(other_cache.get(&key).await, Some((2, b"entry".to_vec())));
+        assert_eq!(other_cache.stats().await.disk_entries, 1);
+
+        other_cache.clear().await?;
+        assert_eq!(other_cache.get(&key).await, None);
+        let stats = other_cache.stats().await;
+        assert_eq!(stats.disk_entries, 0);
+        assert_eq!(stats.memory_entries, 0);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@
 
 use super::{
     files::{FileItem, FileMeta, FilesMap, RealPath},
+    map::MapEntry,
     nrs::NrsMap,
     Safe, XorName,
 };
@@ -19,7 +20,10 @@ pub use super::{
 use crate::{Error, Result};
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
 
 pub type Range = Option<(Option<u64>, Option<u64>)>;
 
@@ -84,6 +88,51 @@ pub enum SafeData {
         data: Vec<u8>,
         resolved_from: String,
     },
+    Map {
+        xorurl: String,
+        xorname: XorName,
+        type_tag: u64,
+        entries: Vec<MapEntry>,
+        resolved_from: String,
+    },
+}
+
+/// Rule applied to a URL to find the next one to resolve in the resolution chain
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub enum ResolutionRule {
+    /// The URL's subnames were looked up in an NRS Map Container
+    NrsSubNames {
+        sub_names: Vec<String>,
+        target: String,
+    },
+    /// The URL has no subnames, thus the default link of an NRS Map Container was used
+    NrsDefault { target: String },
+    /// The URL's path was looked up in a FilesContainer, following any symlinks found,
+    /// the target is only set when the path resolved to a file
+    FilesContainerPath {
+        path: String,
+        realpath: String,
+        symlinks_followed: usize,
+        target: Option<String>,
+    },
+}
+
+/// Trace of one of the steps of the resolution of a URL
+#[derive(Debug, PartialEq, Deserialize, Serialize, Clone)]
+pub struct ResolutionStep {
+    /// The URL resolved in this step
+    pub url: String,
+    /// The rule applied to find the next URL, if any
+    pub rule: Option<ResolutionRule>,
+    /// Number of calls made to the network, i.e. not served from the cache
+    pub network_calls: u64,
+    /// Number of bytes of content retrieved from the network
+    pub bytes_transferred: u64,
+    /// Time taken by this step
+    pub elapsed: Duration,
+    /// The error the resolution failed with in this step, in which case it's the last one
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 impl SafeData {
@@ -96,7 +145,8 @@ impl SafeData {
             | PublicBlob { xorurl, .. }
             | NrsMapContainer { xorurl, .. }
             | PublicSequence { xorurl, .. }
-            | PrivateSequence { xorurl, .. } => xorurl.clone(),
+            | PrivateSequence { xorurl, .. }
+            | Map { xorurl, .. } => xorurl.clone(),
         }
     }
 
@@ -109,7 +159,8 @@ impl SafeData {
             | PublicBlob { resolved_from, .. }
             | NrsMapContainer { resolved_from, .. }
             | PrivateSequence { resolved_from, .. }
-            | PublicSequence { resolved_from, .. } => resolved_from.clone(),
+            | PublicSequence { resolved_from, .. }
+            | Map { resolved_from, .. } => resolved_from.clone(),
         }
     }
 }
@@ -146,7 +197,7 @@ impl Safe {
     /// # });
     /// ```
     pub async fn fetch(&self, url: &str, range: Range) -> Result<SafeData> {
-        let mut resolution_chain = self.retrieve_from_url(url, true, range, true).await?;
+        let mut resolution_chain = self.retrieve_from_url(url, true, range, true, None).await?;
         // Construct return data using the last and first items from the resolution chain
         resolution_chain
             .pop()
@@ -194,18 +245,57 @@ impl Safe {
     /// # });
     /// ```
     pub async fn inspect(&mut self, url: &str) -> Result<Vec<SafeData>> {
-        self.retrieve_from_url(url, false, None, true).await
+        self.retrieve_from_url(url, false, None, true, None).await
+    }
+
+    /// # Inspect a safe:// URL as 'inspect' does, also tracing each of the resolution steps
+    /// # For each step it's reported the rule applied to find the next URL to resolve, e.g.
+    /// # an NRS subname lookup, and the network calls made, bytes retrieved and time taken.
+    /// # The network usage is measured on the client shared by all clones of this instance,
+    /// # thus it also accounts for any operation made concurrently on such clones.
+    /// # The trace is returned even if the resolution fails, its last step being the one
+    /// # which failed, along with the error.
+    ///
+    /// ## Example
+    ///
+    /// ```rust
+    /// # use sn_api::{Safe, fetch::ResolutionRule};
+    /// # let mut safe = Safe::default();
+    /// # async_std::task::block_on(async {
+    /// #   safe.connect("", Some("fake-credentials")).await.unwrap();
+    ///     let (container_xorurl, _, _) = safe.files_container_create(Some("../testdata/"), None, true, false, false).await.unwrap();
+    ///
+    ///     let (inspected_content, trace) = safe.inspect_with_trace( &format!( "{}/test.md", &container_xorurl.replace("?v=0", "") ) ).await;
+    ///     assert_eq!(inspected_content.unwrap().len(), trace.len());
+    ///     match &trace[0].rule {
+    ///         Some(ResolutionRule::FilesContainerPath { path, .. }) => assert_eq!(path, "/test.md"),
+    ///         other => panic!("Unexpected resolution rule: {:?}", other),
+    ///     };
+    /// # });
+    /// ```
+    pub async fn inspect_with_trace(
+        &self,
+        url: &str,
+    ) -> (Result<Vec<SafeData>>, Vec<ResolutionStep>) {
+        let mut trace = vec![];
+        let resolution_chain = self
+            .retrieve_from_url(url, false, None, true, Some(&mut trace))
+            .await;
+        (resolution_chain, trace)
     }
 
     // Retrieves all pieces of data that resulted from resolving the given URL.
     // An optional 'while_is' argment can be set as a filter to stop reslution process
     // upon the first non-matching content type.
+    // If a 'trace' is provided, a ResolutionStep is recorded in it for each indirection,
+    // including the one which failed if the resolution fails.
     pub(crate) async fn retrieve_from_url(
         &self,
         url: &str,
         retrieve_data: bool,
         range: Range,
         resolve_path: bool,
+        mut trace: Option<&mut Vec<ResolutionStep>>,
     ) -> Result<Vec<SafeData>> {
         let current_safe_url = Safe::parse_url(url)?;
         info!("URL parsed successfully, fetching: {}", current_safe_url);
@@ -224,7 +314,11 @@ impl Safe {
                 return Err(Error::ContentError(format!("The maximum number of indirections ({}) was reached when trying to resolve the URL provided", INDIRECTION_LIMIT)));
             }
 
-            let (step, next) = self
+            let step_url = next_safe_url.to_string();
+            let started = Instant::now();
+            let (calls_before, bytes_before) = self.safe_client.net_stats.snapshot();
+
+            let result = self
                 .resolve_one_indirection(
                     next_safe_url,
                     metadata,
@@ -232,7 +326,24 @@ impl Safe {
                     range,
                     resolve_path,
                 )
-                .await?;
+                .await;
+
+            if let Some(trace) = trace.as_mut() {
+                let (calls, bytes) = self.safe_client.net_stats.snapshot();
+                let (rule, error) = match &result {
+                    Ok((_, _, rule)) => (rule.clone(), None),
+                    Err(err) => (None, Some(err.to_string())),
+                };
+                trace.push(ResolutionStep {
+                    url: step_url,
+                    rule,
+                    network_calls: calls - calls_before,
+                    bytes_transferred: bytes - bytes_before,
+                    elapsed: started.elapsed(),
+                    error,
+                });
+            }
+            let (step, next, _) = result?;
 
             resolution_chain.push(step);
             next_to_resolve = next;
@@ -249,7 +360,7 @@ impl Safe {
         retrieve_data: bool,
         range: Range,
         resolve_path: bool,
-    ) -> Result<(SafeData, Option<NextStepInfo>)> {
+    ) -> Result<IndirectionInfo> {
         let url = the_xor.to_string();
         let xorurl = the_xor.to_xorurl_string();
         debug!("Going into a new step in the URL resolution for {}", xorurl);
@@ -273,10 +384,10 @@ impl Safe {
                 );
 
                 let path = the_xor.path_decoded()?;
-                let (files_map, next) = if resolve_path && path != "/" && !path.is_empty() {
+                let (files_map, next, rule) = if resolve_path && path != "/" && !path.is_empty() {
                     // TODO: Move this logic (path resolver) to the FilesMap struct
-                    let realpath = files_map.realpath(&path)?;
-                    match &files_map.get(&realpath) {
+                    let (realpath, symlinks_followed) = files_map.realpath_with_links(&path)?;
+                    let (files_map, next) = match &files_map.get(&realpath) {
                         Some(file_item) => match file_item.get("type") {
                             Some(file_type) => {
                                 if FileMeta::filetype_is_file(&file_type) {
@@ -314,9 +425,17 @@ impl Safe {
                             }
                         },
                         None => (gen_filtered_filesmap(&realpath, &files_map, &xorurl)?, None),
-                    }
+                    };
+
+                    let rule = ResolutionRule::FilesContainerPath {
+                        path,
+                        realpath,
+                        symlinks_followed,
+                        target: next.as_ref().map(|(target, _)| target.to_string()),
+                    };
+                    (files_map, next, Some(rule))
                 } else {
-                    (files_map, None)
+                    (files_map, None, None)
                 };
 
                 // We don't want the path just the FilesContainer XOR-URL and version
@@ -331,7 +450,7 @@ impl Safe {
                     resolved_from: url,
                 };
 
-                Ok((safe_data, next))
+                Ok((safe_data, next, rule))
             }
             SafeContentType::NrsMapContainer => {
                 let (version, nrs_map) = self
@@ -364,6 +483,13 @@ impl Safe {
                 }
 
                 debug!("Resolving target from resolvable map: {}", target_safe_url);
+                let sub_names = the_xor.sub_names_vec().to_vec();
+                let target = target_safe_url.to_string();
+                let rule = if sub_names.is_empty() {
+                    ResolutionRule::NrsDefault { target }
+                } else {
+                    ResolutionRule::NrsSubNames { sub_names, target }
+                };
 
                 // We don't want the path or subnames, just the FilesContainer XOR-URL and version
                 the_xor.set_path("");
@@ -383,7 +509,7 @@ impl Safe {
                     resolved_from: url,
                 };
 
-                Ok((nrs_map_container, Some((target_safe_url, None))))
+                Ok((nrs_map_container, Some((target_safe_url, None)), Some(rule)))
             }
             SafeContentType::Raw => {
                 if !the_xor.sub_names_vec().is_empty() {
@@ -402,7 +528,7 @@ impl Safe {
                             xorname: the_xor.xorname(),
                             resolved_from: url,
                         };
-                        Ok((safe_data, None))
+                        Ok((safe_data, None, None))
                     }
                     SafeDataType::PublicBlob => {
                         self.retrieve_blob(&the_xor, retrieve_data, None, &metadata, range)
@@ -422,7 +548,7 @@ impl Safe {
                             resolved_from: url.to_string(),
                         };
 
-                        Ok((safe_data, None))
+                        Ok((safe_data, None, None))
                     }
                     SafeDataType::PrivateSequence => {
                         // TODO: fetch only if 'retrieve_data' is set,
@@ -438,7 +564,24 @@ impl Safe {
                             resolved_from: url.to_string(),
                         };
 
-                        Ok((safe_data, None))
+                        Ok((safe_data, None, None))
+                    }
+                    SafeDataType::SeqMap => {
+                        let entries = if retrieve_data {
+                            self.fetch_map_entries(&the_xor).await?
+                        } else {
+                            vec![]
+                        };
+                        debug!("Map found with {} entries at: {}", entries.len(), xorurl);
+                        let safe_data = SafeData::Map {
+                            xorurl,
+                            xorname: the_xor.xorname(),
+                            type_tag: the_xor.type_tag(),
+                            entries,
+                            resolved_from: url,
+                        };
+
+                        Ok((safe_data, None, None))
                     }
                     other => Err(Error::ContentError(format!(
                         "Data type '{:?}' not supported yet",
@@ -500,7 +643,7 @@ impl Safe {
                     resolved_from: url,
                 };
 
-                Ok((safe_data, None))
+                Ok((safe_data, None, None))
             }
         }
     }
@@ -512,7 +655,7 @@ impl Safe {
         media_type: Option<String>,
         metadata: &Option<FileItem>,
         range: Range,
-    ) -> Result<(SafeData, Option<NextStepInfo>)> {
+    ) -> Result<IndirectionInfo> {
         if !the_xor.path().is_empty() {
             return Err(Error::ContentError(format!(
                 "Cannot get relative path of Immutable Data {:?}",
@@ -537,7 +680,7 @@ impl Safe {
             resolved_from: the_xor.to_string(),
         };
 
-        Ok((safe_data, None))
+        Ok((safe_data, None, None))
     }
 }
 
@@ -569,6 +712,10 @@ fn gen_filtered_filesmap(urlpath: &str, files_map: &FilesMap, xorurl: &str) -> R
 // // in each iteration of the resolution process
 type NextStepInfo = (SafeUrl, Option<FileItem>);
 
+// Outcome of resolving one indirection: the data found, the next step to be made,
+// and the rule applied to find it
+type IndirectionInfo = (SafeData, Option<NextStepInfo>, Option<ResolutionRule>);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -630,6 +777,46 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_fetch_map() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.map_create(None, 30_000).await?;
+        let _ = retry_loop!(safe.map_list(&xorurl));
+        safe.map_insert(&xorurl, b"key", b"value").await?;
+
+        let safe_url = SafeUrl::from_url(&xorurl)?;
+        let content = safe.fetch(&xorurl, None).await?;
+        assert!(
+            content
+                == SafeData::Map {
+                    xorurl: xorurl.clone(),
+                    xorname: safe_url.xorname(),
+                    type_tag: 30_000,
+                    entries: vec![MapEntry {
+                        key: b"key".to_vec(),
+                        value: b"value".to_vec(),
+                        version: 0,
+                    }],
+                    resolved_from: xorurl.clone(),
+                }
+        );
+
+        // the entries are not retrieved when inspecting the URL
+        let inspected_content = safe.inspect(&xorurl).await?;
+        assert_eq!(inspected_content.len(), 1);
+        assert!(
+            inspected_content[0]
+                == SafeData::Map {
+                    xorurl: xorurl.clone(),
+                    xorname: safe_url.xorname(),
+                    type_tag: 30_000,
+                    entries: vec![],
+                    resolved_from: xorurl,
+                }
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_fetch_files_container() -> Result<()> {
         let mut safe = new_safe_instance().await?;
@@ -759,6 +946,78 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_inspect_with_trace() -> Result<()> {
+        let site_name: String = thread_rng().sample_iter(&Alphanumeric).take(15).collect();
+
+        let mut safe = new_safe_instance().await?;
+        let (xorurl, _, _the_files_map) = safe
+            .files_container_create(Some("../testdata/"), None, true, false, false)
+            .await?;
+        let _ = retry_loop!(safe.fetch(&xorurl, None));
+
+        let mut safe_url = SafeUrl::from_url(&xorurl)?;
+        safe_url.set_content_version(Some(0));
+        let files_container_url = safe_url.to_string();
+        let _ = safe
+            .nrs_map_container_create(&site_name, &files_container_url, true, true, false)
+            .await?;
+
+        let nrs_url = format!("safe://{}/test.md", site_name);
+        let _ = retry_loop!(safe.fetch(&nrs_url, None));
+        let (inspected_content, trace) = safe.inspect_with_trace(&nrs_url).await;
+        let inspected_content = inspected_content?;
+        assert_eq!(inspected_content.len(), 3);
+        assert_eq!(trace.len(), 3);
+        assert_eq!(trace[0].url, nrs_url);
+
+        // the NRS name has no subnames thus its default link is followed
+        safe_url.set_path("/test.md");
+        let files_container_path_url = safe_url.to_string();
+        assert_eq!(
+            trace[0].rule,
+            Some(ResolutionRule::NrsDefault {
+                target: files_container_path_url.clone()
+            })
+        );
+
+        assert_eq!(trace[1].url, files_container_path_url);
+        match &trace[1].rule {
+            Some(ResolutionRule::FilesContainerPath {
+                path,
+                realpath,
+                symlinks_followed,
+                target,
+            }) => {
+                assert_eq!(path, "/test.md");
+                assert_eq!(realpath, "/test.md");
+                assert_eq!(*symlinks_followed, 0);
+                assert_eq!(*target, Some(inspected_content[2].xorurl()));
+            }
+            other => bail!("Unexpected resolution rule: {:?}", other),
+        }
+
+        // the Blob is the target content thus there is no rule applied to it,
+        // and since it's only inspected its content is not retrieved
+        assert_eq!(trace[2].rule, None);
+        assert_eq!(trace[2].bytes_transferred, 0);
+        assert!(trace.iter().all(|step| step.error.is_none()));
+
+        // the trace is kept when the resolution fails, the failing step being the last one
+        let missing_url = format!("safe://{}/missing.md", site_name);
+        let (inspected_content, trace) = safe.inspect_with_trace(&missing_url).await;
+        assert!(inspected_content.is_err());
+        assert_eq!(trace.len(), 2);
+        assert!(matches!(
+            trace[0].rule,
+            Some(ResolutionRule::NrsDefault { .. })
+        ));
+        assert_eq!(trace[0].error, None);
+        assert_eq!(trace[1].rule, None);
+        assert!(trace[1].error.is_some());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_fetch_public_blob() -> Result<()> {
         let mut safe = new_safe_instance().await?;
@@ -25,6 +25,7 @@ use file_system::{file_system_dir_walk, file_system_single_file, normalise_path_
 use files_map::add_or_update_file_item;
 use log::{debug, info, warn};
 use relative_path::RelativePath;
+use sn_data_types::PublicKey;
 use std::{collections::BTreeMap, fs, path::Path};
 
 pub(crate) use metadata::FileMeta;
@@ -61,6 +62,31 @@ impl Safe {
         recursive: bool,
         follow_links: bool,
         dry_run: bool,
+    ) -> Result<(XorUrl, ProcessedFiles, FilesMap)> {
+        self.files_container_create_with_writers(
+            location,
+            dest,
+            recursive,
+            follow_links,
+            dry_run,
+            &[],
+        )
+        .await
+    }
+
+    /// # Create a FilesContainer which other users can also update.
+    ///
+    /// Apart from the owner, each of the writers is granted permission to append new
+    /// versions to the FilesContainer's Sequence. As with any Sequence, permissions can only
+    /// be set when the FilesContainer is created, they cannot be changed afterwards.
+    pub async fn files_container_create_with_writers(
+        &mut self,
+        location: Option<&str>,
+        dest: Option<&str>,
+        recursive: bool,
+        follow_links: bool,
+        dry_run: bool,
+        writers: &[PublicKey],
     ) -> Result<(XorUrl, ProcessedFiles, FilesMap)> {
         // TODO: Enable source for funds / ownership
         // Warn about ownership?
@@ -102,7 +128,7 @@ impl Safe {
                     files_map_xorurl.as_bytes(),
                     None,
                     FILES_CONTAINER_TYPE_TAG,
-                    None,
+                    writers,
                     false,
                 )
                 .await?;
@@ -246,6 +272,10 @@ impl Safe {
         // the version from it so we can fetch latest version of it for sync-ing
         safe_url.set_content_version(None);
 
+        // Make sure we get the latest version rather than the one we may have in the cache
+        self.safe_client
+            .forget_sequence_last_entry(safe_url.xorname(), safe_url.type_tag(), false)
+            .await;
         let (current_version, current_files_map): (u64, FilesMap) =
             self.fetch_files_container(&safe_url).await?;
 
@@ -455,6 +485,10 @@ impl Safe {
         // the version from it so we can fetch latest version of it
         safe_url.set_content_version(None);
 
+        // Make sure we get the latest version rather than the one we may have in the cache
+        self.safe_client
+            .forget_sequence_last_entry(safe_url.xorname(), safe_url.type_tag(), false)
+            .await;
         let (current_version, files_map): (u64, FilesMap) =
             self.fetch_files_container(&safe_url).await?;
 
@@ -512,7 +546,7 @@ impl Safe {
                 safe_url.set_content_version(Some(new_version));
                 let new_link_for_nrs = safe_url.to_string();
                 let _ = self
-                    .nrs_map_container_add(url, &new_link_for_nrs, false, true, false)
+                    .nrs_map_container_add(url, &new_link_for_nrs, false, true, None, false)
                     .await?;
             }
 
@@ -644,6 +678,10 @@ async fn validate_files_add_params(
     // the version from it so we can fetch latest version of it for sync-ing
     safe_url.set_content_version(None);
 
+    // Make sure we get the latest version rather than the one we may have in the cache
+    safe.safe_client
+        .forget_sequence_last_entry(safe_url.xorname(), safe_url.type_tag(), false)
+        .await;
     let (current_version, current_files_map): (u64, FilesMap) =
         safe.fetch_files_container(&safe_url).await?;
 
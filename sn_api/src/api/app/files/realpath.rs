@@ -14,15 +14,16 @@ use super::{
 use crate::{Error, Result};
 
 pub(crate) trait RealPath {
-    fn realpath(&self, fpath: &str) -> Result<String>;
+    fn realpath_with_links(&self, fpath: &str) -> Result<(String, usize)>;
 }
 
 impl RealPath for FilesMap {
     // Realpath returns the real path of a given file in the filecontainer
-    // after resolving instances of "../", "./", and any relative symlinks.
+    // after resolving instances of "../", "./", and any relative symlinks,
+    // along with the number of symlinks that were followed to find it.
     //
     // Note: fpath must be an absolute path within the FileContainer.
-    fn realpath(&self, fpath: &str) -> Result<String> {
+    fn realpath_with_links(&self, fpath: &str) -> Result<(String, usize)> {
         if fpath.is_empty() {
             let msg = "Path cannot be empty".to_string();
             return Err(Error::InvalidInput(msg));
@@ -120,6 +121,6 @@ impl RealPath for FilesMap {
                 }
             }
         }
-        Ok(newpath.join("/"))
+        Ok((newpath.join("/"), nlinks))
     }
 }
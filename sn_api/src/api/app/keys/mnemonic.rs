@@ -0,0 +1,251 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+// Deterministic SafeKeys derived from a BIP39 mnemonic phrase.
+//
+// The 64 bytes seed is obtained from the mnemonic as per BIP39 (with an empty passphrase),
+// and the Ed25519 secret keys are derived from it as per SLIP-0010, which only supports
+// hardened derivation for Ed25519 keys, using the path m/44'/<SAFE coin type>'/0'/<index>'.
+
+use crate::{Error, Result, Safe};
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+use hmac::{Hmac, Mac, NewMac};
+use log::debug;
+use sha2::Sha512;
+use sn_data_types::Keypair;
+
+// Offset applied to indexes to make them hardened indexes
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+// HMAC key used to obtain the master key from the seed for Ed25519 keys
+const SLIP10_ED25519_CURVE: &[u8] = b"ed25519 seed";
+// BIP44 purpose
+const DERIVATION_PURPOSE: u32 = 44;
+// Coin type used in the derivation path for SafeKeys
+const SAFE_COIN_TYPE: u32 = 0x5afe;
+// Account used in the derivation path for SafeKeys
+const DERIVATION_ACCOUNT: u32 = 0;
+
+/// Default number of consecutive unused SafeKeys to derive from a mnemonic before
+/// considering there are no more used SafeKeys derived from it
+pub const MNEMONIC_DEFAULT_GAP_LIMIT: u32 = 5;
+
+impl Safe {
+    /// # Generate a new random mnemonic phrase
+    ///
+    /// The phrase is made of 24 words from the BIP39 English word list, and it
+    /// can be used to derive any number of SafeKeys deterministically.
+    pub fn keys_generate_mnemonic(&self) -> String {
+        let mnemonic = Mnemonic::new(MnemonicType::Words24, Language::English);
+        mnemonic.phrase().to_string()
+    }
+
+    /// # Derive the key pair with the given index from a mnemonic phrase
+    ///
+    /// The same key pair is always derived from the same mnemonic and index,
+    /// thus SafeKeys can be recovered by just knowing the mnemonic phrase.
+    pub fn keys_derive_from_mnemonic(&self, mnemonic: &str, index: u32) -> Result<Keypair> {
+        let seed = mnemonic_to_seed(mnemonic)?;
+        derive_ed_keypair(&seed, index)
+    }
+
+    // Find the SafeKeys derived from a mnemonic which have been used, i.e. those which have
+    // a balance or any transfers recorded. Key pairs are derived with increasing indexes until
+    // 'gap_limit' consecutive unused ones are found. The key pairs are returned with their indexes.
+    pub async fn keys_find_used_from_mnemonic(
+        &self,
+        mnemonic: &str,
+        gap_limit: u32,
+    ) -> Result<Vec<(u32, Keypair)>> {
+        if gap_limit == 0 {
+            return Err(Error::InvalidInput(
+                "The gap limit must be greater than zero".to_string(),
+            ));
+        }
+
+        let seed = mnemonic_to_seed(mnemonic)?;
+        let mut used_keys = vec![];
+        let mut unused_count = 0;
+        let mut index = 0;
+        while unused_count < gap_limit && index < HARDENED_OFFSET {
+            let keypair = derive_ed_keypair(&seed, index)?;
+            let balance = self
+                .safe_client
+                .read_balance_from_keypair(keypair.clone())
+                .await?;
+            let history = self
+                .safe_client
+                .read_history_from_keypair(keypair.clone())
+                .await?;
+
+            if balance.as_nano() > 0 || !history.credits.is_empty() || !history.debits.is_empty() {
+                debug!("SafeKey derived from mnemonic with index {} is used", index);
+                used_keys.push((index, keypair));
+                unused_count = 0;
+            } else {
+                unused_count += 1;
+            }
+            index += 1;
+        }
+
+        Ok(used_keys)
+    }
+}
+
+// Validate the mnemonic phrase and obtain the seed from it
+fn mnemonic_to_seed(phrase: &str) -> Result<Vec<u8>> {
+    let normalised = phrase.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let mnemonic = Mnemonic::from_phrase(&normalised.to_lowercase(), Language::English)
+        .map_err(|err| Error::InvalidInput(format!("Invalid mnemonic phrase: {}", err)))?;
+
+    Ok(Seed::new(&mnemonic, "").as_bytes().to_vec())
+}
+
+// Derive the Ed25519 key pair with the given index from the seed
+fn derive_ed_keypair(seed: &[u8], index: u32) -> Result<Keypair> {
+    if index >= HARDENED_OFFSET {
+        return Err(Error::InvalidInput(format!(
+            "The derivation index must be lower than {}",
+            HARDENED_OFFSET
+        )));
+    }
+
+    let path = [
+        DERIVATION_PURPOSE,
+        SAFE_COIN_TYPE,
+        DERIVATION_ACCOUNT,
+        index,
+    ];
+    let sk_bytes = derive_ed_sk_bytes(seed, &path)?;
+    let sk = ed25519_dalek::SecretKey::from_bytes(&sk_bytes).map_err(|err| {
+        Error::InvalidInput(format!("Failed to derive Ed25519 secret key: {}", err))
+    })?;
+
+    Ok(Keypair::from(sk))
+}
+
+// SLIP-0010 derivation of an Ed25519 secret key, all indexes in the path are hardened
+fn derive_ed_sk_bytes(seed: &[u8], path: &[u32]) -> Result<[u8; 32]> {
+    let (mut key, mut chain_code) = hmac_sha512_split(SLIP10_ED25519_CURVE, &[seed])?;
+    for index in path {
+        let hardened_index = (index | HARDENED_OFFSET).to_be_bytes();
+        let (child_key, child_chain_code) =
+            hmac_sha512_split(&chain_code, &[&[0], &key, &hardened_index])?;
+        key = child_key;
+        chain_code = child_chain_code;
+    }
+
+    Ok(key)
+}
+
+// Compute HMAC-SHA512 and split the result into its left and right halves
+fn hmac_sha512_split(key: &[u8], data: &[&[u8]]) -> Result<([u8; 32], [u8; 32])> {
+    let mut mac = Hmac::<Sha512>::new_varkey(key)
+        .map_err(|_| Error::InvalidInput("Failed to derive key from seed".to_string()))?;
+    for chunk in data {
+        mac.update(chunk);
+    }
+    let result = mac.finalize().into_bytes();
+
+    let mut left = [0; 32];
+    let mut right = [0; 32];
+    left.copy_from_slice(&result[..32]);
+    right.copy_from_slice(&result[32..]);
+    Ok((left, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::common::sk_to_hex;
+    use anyhow::{anyhow, Result};
+
+    #[test]
+    fn test_keys_slip10_ed25519_derivation() -> Result<()> {
+        // test vector 1 from SLIP-0010 specification
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f")?;
+
+        let master = derive_ed_sk_bytes(&seed, &[])?;
+        assert_eq!(
+            hex::encode(master),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+
+        let child = derive_ed_sk_bytes(&seed, &[0])?;
+        assert_eq!(
+            hex::encode(child),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+
+        let grandchild = derive_ed_sk_bytes(&seed, &[0, 1])?;
+        assert_eq!(
+            hex::encode(grandchild),
+            "b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_derive_from_mnemonic() -> Result<()> {
+        let safe = Safe::default();
+        let mnemonic = safe.keys_generate_mnemonic();
+        assert_eq!(mnemonic.split(' ').count(), 24);
+
+        let keypair0 = safe.keys_derive_from_mnemonic(&mnemonic, 0)?;
+        let keypair1 = safe.keys_derive_from_mnemonic(&mnemonic, 1)?;
+        assert_ne!(keypair0.public_key(), keypair1.public_key());
+
+        // the same key pair is derived every time, regardless of whitespaces and case
+        let messy_mnemonic = format!("  {}\n", mnemonic.to_uppercase().replace(' ', "   "));
+        let keypair0_again = safe.keys_derive_from_mnemonic(&messy_mnemonic, 0)?;
+        assert_eq!(
+            sk_to_hex(keypair0.secret_key().map_err(|err| anyhow!("{:?}", err))?),
+            sk_to_hex(
+                keypair0_again
+                    .secret_key()
+                    .map_err(|err| anyhow!("{:?}", err))?
+            )
+        );
+
+        // a different mnemonic derives different key pairs
+        let other_mnemonic = safe.keys_generate_mnemonic();
+        let other_keypair0 = safe.keys_derive_from_mnemonic(&other_mnemonic, 0)?;
+        assert_ne!(keypair0.public_key(), other_keypair0.public_key());
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_derive_from_invalid_mnemonic() -> Result<()> {
+        let safe = Safe::default();
+        let mnemonic = safe.keys_generate_mnemonic();
+
+        // an invalid checksum, the valid one would end with 'art'
+        let invalid_checksum = vec!["abandon"; 24].join(" ");
+        assert!(matches!(
+            safe.keys_derive_from_mnemonic(&invalid_checksum, 0),
+            Err(Error::InvalidInput(_))
+        ));
+        let valid_checksum = format!("{} art", vec!["abandon"; 23].join(" "));
+        assert!(safe.keys_derive_from_mnemonic(&valid_checksum, 0).is_ok());
+
+        // a word not in the list
+        let first_word = mnemonic.split(' ').next().unwrap_or_default();
+        let unknown_word = mnemonic.replacen(first_word, "safenetwork", 1);
+        assert!(matches!(
+            safe.keys_derive_from_mnemonic(&unknown_word, 0),
+            Err(Error::InvalidInput(_))
+        ));
+
+        // an index out of range
+        assert!(matches!(
+            safe.keys_derive_from_mnemonic(&mnemonic, HARDENED_OFFSET),
+            Err(Error::InvalidInput(_))
+        ));
+        Ok(())
+    }
+}
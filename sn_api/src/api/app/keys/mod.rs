@@ -10,15 +10,45 @@
 use super::{
     common::ed_sk_from_hex,
     helpers::{parse_coins_amount, pk_from_hex},
-    safeurl::{SafeContentType, SafeDataType, SafeUrl},
+    safeurl::{SafeContentType, SafeDataType, SafeUrl, XorUrlBase},
     Safe,
 };
 use crate::{Error, Result};
 use hex::encode;
 use rand::rngs::OsRng;
-use sn_data_types::{Keypair, SecretKey, Token};
+use serde::{Deserialize, Serialize};
+use sn_data_types::{ActorHistory, Keypair, SecretKey, Token};
 use xor_name::XorName;
 
+mod formats;
+mod mnemonic;
+mod signing;
+
+pub use formats::KeyFormat;
+pub use mnemonic::MNEMONIC_DEFAULT_GAP_LIMIT;
+pub use signing::DataSignature;
+
+/// Direction of a transfer with respect to the SafeKey whose history it is part of
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TransferDirection {
+    Incoming,
+    Outgoing,
+}
+
+/// A transfer found in the history of a SafeKey
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TransferHistoryEntry {
+    /// Friendly name of the Wallet's spendable balance the transfer relates to, if any
+    pub balance_name: Option<String>,
+    pub direction: TransferDirection,
+    pub amount: String,
+    /// SafeKey URL of the recipient of outgoing transfers, the network doesn't
+    /// report the sender of incoming transfers
+    pub counterparty: Option<String>,
+    /// Transfer id, which for outgoing transfers matches the TX_ID reported at the moment of the transfer
+    pub tx_id: String,
+}
+
 impl Safe {
     // Generate a key pair
     pub fn generate_random_ed_keypair(&self) -> Keypair {
@@ -29,18 +59,11 @@ impl Safe {
     // Create a SafeKey allocating token from current client's key onto it,
     // and return the SafeKey's XOR-URL
     pub async fn keys_create_and_preload(&self, preload_amount: &str) -> Result<(String, Keypair)> {
-        let amount = parse_coins_amount(preload_amount)?;
         let new_keypair = self.generate_random_ed_keypair();
-
-        // let's make the transfer
-        let _ = self
-            .safe_client
-            .safecoin_transfer_to_pk(None, new_keypair.public_key(), amount)
+        let xorurl = self
+            .keys_preload(&new_keypair, None, preload_amount)
             .await?;
 
-        let xorname = XorName::from(new_keypair.public_key());
-        let xorurl = SafeUrl::encode_safekey(xorname, self.xorurl_base)?;
-
         Ok((xorurl, new_keypair))
     }
 
@@ -50,27 +73,44 @@ impl Safe {
         from: &str,
         preload_amount: &str,
     ) -> Result<(String, Keypair)> {
-        let from_keypair = match ed_sk_from_hex(&from) {
-            Ok(sk) => Keypair::from(sk),
-            Err(_) => return Err(Error::InvalidInput(
-                "The source of funds needs to be an Ed25519 secret key. The secret key provided is invalid"
-                    .to_string(),
-            )),
+        let new_keypair = self.generate_random_ed_keypair();
+        let xorurl = self
+            .keys_preload(&new_keypair, Some(from), preload_amount)
+            .await?;
+
+        Ok((xorurl, new_keypair))
+    }
+
+    // Preload the SafeKey of an existing key pair, e.g. one derived from a mnemonic,
+    // allocating token from another key, or from current client's key if no source
+    // secret key is provided, and return the SafeKey's XOR-URL
+    pub async fn keys_preload(
+        &self,
+        keypair: &Keypair,
+        from: Option<&str>,
+        preload_amount: &str,
+    ) -> Result<String> {
+        let from_keypair = match from {
+            Some(from) => match ed_sk_from_hex(&from) {
+                Ok(sk) => Some(Keypair::from(sk)),
+                Err(_) => return Err(Error::InvalidInput(
+                    "The source of funds needs to be an Ed25519 secret key. The secret key provided is invalid"
+                        .to_string(),
+                )),
+            },
+            None => None,
         };
 
         let amount = parse_coins_amount(&preload_amount)?;
-        let new_keypair = self.generate_random_ed_keypair();
 
         // let's make the transfer
         let _ = self
             .safe_client
-            .safecoin_transfer_to_pk(Some(from_keypair), new_keypair.public_key(), amount)
+            .safecoin_transfer_to_pk(from_keypair, keypair.public_key(), amount)
             .await?;
 
-        let xorname = XorName::from(new_keypair.public_key());
-        let xorurl = SafeUrl::encode_safekey(xorname, self.xorurl_base)?;
-
-        Ok((xorurl, new_keypair))
+        let xorname = XorName::from(keypair.public_key());
+        SafeUrl::encode_safekey(xorname, self.xorurl_base)
     }
 
     #[cfg(feature = "simulated-payouts")]
@@ -79,40 +119,46 @@ impl Safe {
         &self,
         preload_amount: &str,
     ) -> Result<(String, Keypair)> {
-        let amount = parse_coins_amount(preload_amount)?;
         let keypair = self.generate_random_ed_keypair();
+        let xorurl = self
+            .keys_preload_test_coins(&keypair, preload_amount)
+            .await?;
+
+        Ok((xorurl, keypair))
+    }
+
+    #[cfg(feature = "simulated-payouts")]
+    // Allocate testcoins onto the SafeKey of an existing key pair, and return the SafeKey's XOR-URL
+    pub async fn keys_preload_test_coins(
+        &self,
+        keypair: &Keypair,
+        preload_amount: &str,
+    ) -> Result<String> {
+        let amount = parse_coins_amount(preload_amount)?;
         self.safe_client
             .trigger_simulated_farming_payout(amount, Some(keypair.clone()))
             .await?;
 
         let xorname = XorName::from(keypair.public_key());
-        let xorurl = SafeUrl::encode_safekey(xorname, self.xorurl_base)?;
-
-        Ok((xorurl, keypair))
+        SafeUrl::encode_safekey(xorname, self.xorurl_base)
     }
 
     // Check SafeKey's balance from the network from a given SecretKey string
     pub async fn keys_balance_from_sk(&self, secret_key: &SecretKey) -> Result<String> {
-        let keypair = match secret_key {
-            SecretKey::Ed25519(sk) => {
-                let bytes = sk.to_bytes();
-                let secret_key = ed25519_dalek::SecretKey::from_bytes(&bytes).map_err(|err| {
-                    Error::InvalidInput(format!("Error parsing SecretKey bytes: {}", err))
-                })?;
-                Keypair::from(secret_key)
-            }
-            SecretKey::BlsShare(_) => {
-                return Err(Error::InvalidInput(
-                    "Cannot convert from BlsShare key to a Keypair".to_string(),
-                ))
-            }
-        };
-
+        let keypair = keypair_from_sk(secret_key)?;
         let balance = self.safe_client.read_balance_from_keypair(keypair).await?;
 
         Ok(balance.to_string())
     }
 
+    // Retrieve the incoming and outgoing transfers of a SafeKey from the network
+    pub async fn keys_history(&self, secret_key: &SecretKey) -> Result<Vec<TransferHistoryEntry>> {
+        let keypair = keypair_from_sk(secret_key)?;
+        let history = self.safe_client.read_history_from_keypair(keypair).await?;
+
+        gen_history_entries(history, None, self.xorurl_base)
+    }
+
     // Check SafeKey's balance from the network from a given XOR/NRS-URL and secret key string.
     // The difference between this and 'keys_balance_from_sk' function is that this will additionally
     // check that the XOR/NRS-URL corresponds to the public key derived from the provided secret key
@@ -252,6 +298,58 @@ impl Safe {
     }
 }
 
+// Private helper to obtain a Keypair from a SecretKey
+fn keypair_from_sk(secret_key: &SecretKey) -> Result<Keypair> {
+    match secret_key {
+        SecretKey::Ed25519(sk) => {
+            let bytes = sk.to_bytes();
+            let secret_key = ed25519_dalek::SecretKey::from_bytes(&bytes).map_err(|err| {
+                Error::InvalidInput(format!("Error parsing SecretKey bytes: {}", err))
+            })?;
+            Ok(Keypair::from(secret_key))
+        }
+        SecretKey::BlsShare(_) => Err(Error::InvalidInput(
+            "Cannot convert from BlsShare key to a Keypair".to_string(),
+        )),
+    }
+}
+
+// Convert the history of transfers of a SafeKey, as retrieved from the network, into a list of
+// entries, with the outgoing transfers in the order they were made followed by the incoming ones.
+// The network doesn't report the time the transfers were made, nor the sender of incoming transfers.
+pub(crate) fn gen_history_entries(
+    history: ActorHistory,
+    balance_name: Option<&str>,
+    xorurl_base: XorUrlBase,
+) -> Result<Vec<TransferHistoryEntry>> {
+    let mut debits = history.debits;
+    debits.sort_by_key(|proof| proof.id().counter);
+
+    let mut entries = vec![];
+    for proof in debits.iter() {
+        let recipient = XorName::from(proof.recipient());
+        entries.push(TransferHistoryEntry {
+            balance_name: balance_name.map(|name| name.to_string()),
+            direction: TransferDirection::Outgoing,
+            amount: proof.amount().to_string(),
+            counterparty: Some(SafeUrl::encode_safekey(recipient, xorurl_base)?),
+            tx_id: proof.id().counter.to_string(),
+        });
+    }
+
+    for proof in history.credits.iter() {
+        entries.push(TransferHistoryEntry {
+            balance_name: balance_name.map(|name| name.to_string()),
+            direction: TransferDirection::Incoming,
+            amount: proof.amount().to_string(),
+            counterparty: None,
+            tx_id: encode(proof.id()),
+        });
+    }
+
+    Ok(entries)
+}
+
 #[cfg(all(test, feature = "simulated-payouts"))]
 mod tests {
     use super::*;
@@ -702,4 +800,38 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_keys_history() -> Result<()> {
+        let safe = new_safe_instance().await?;
+
+        let (_, keypair1) = safe.keys_create_preload_test_coins("12.5").await?;
+        let from_sk1_hex = sk_to_hex(keypair1.secret_key()?);
+        let (key2_xorurl, keypair2) = safe.keys_create_preload_test_coins("0").await?;
+
+        let tx_id = safe
+            .keys_transfer("2.3", Some(&from_sk1_hex), &key2_xorurl)
+            .await?;
+
+        let from_history = safe.keys_history(&keypair1.secret_key()?).await?;
+        let outgoing: Vec<&TransferHistoryEntry> = from_history
+            .iter()
+            .filter(|entry| entry.direction == TransferDirection::Outgoing)
+            .collect();
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].amount, "2.300000000");
+        assert_eq!(outgoing[0].tx_id, tx_id.to_string());
+        assert_eq!(outgoing[0].counterparty, Some(key2_xorurl));
+        assert_eq!(outgoing[0].balance_name, None);
+
+        let to_history = safe.keys_history(&keypair2.secret_key()?).await?;
+        assert!(to_history.iter().any(|entry| {
+            entry.direction == TransferDirection::Incoming && entry.amount == "2.300000000"
+        }));
+        assert!(to_history
+            .iter()
+            .all(|entry| entry.direction == TransferDirection::Incoming));
+
+        Ok(())
+    }
 }
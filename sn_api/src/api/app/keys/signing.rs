@@ -0,0 +1,179 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+// Detached signatures of arbitrary data made with the Ed25519 key pair of a SafeKey,
+// e.g. to sign files uploaded to the network so their integrity and origin can be checked.
+
+use crate::{
+    api::{app::safeurl::SafeUrl, common::ed_sk_from_hex},
+    Error, Result, Safe,
+};
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use sn_data_types::PublicKey as SafePublicKey;
+use std::convert::TryFrom;
+use xor_name::XorName;
+
+// Prefix of the message actually signed, so a SafeKey signing arbitrary data provided by
+// a user can never be used to produce a valid signature of anything other than a digest
+const SIGNED_DATA_DOMAIN: &[u8] = b"safe-signed-data-v1";
+
+/// Detached signature of some data, along with the SafeKey it was signed with
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DataSignature {
+    /// Hex-encoded Ed25519 public key the signature can be verified with
+    pub public_key: String,
+    /// XOR-URL of the SafeKey the data was signed with
+    pub xorurl: String,
+    /// Hex-encoded Ed25519 signature of the data
+    pub signature: String,
+}
+
+impl Safe {
+    /// # Sign some data with the secret key of a SafeKey
+    ///
+    /// The detached signature returned contains the public key and the SafeKey's XOR-URL,
+    /// so anyone can verify it with 'Safe::verify_data' without connecting to the network.
+    pub fn sign_data(&self, sk: &str, data: &[u8]) -> Result<DataSignature> {
+        let secret = ed_sk_from_hex(sk)?;
+        let public = PublicKey::from(&secret);
+        let keypair = Keypair { secret, public };
+        let signature = keypair.sign(&signed_message(data));
+
+        let xorname = XorName::from(SafePublicKey::from(public));
+        Ok(DataSignature {
+            public_key: hex::encode(public.to_bytes()),
+            xorurl: SafeUrl::encode_safekey(xorname, self.xorurl_base)?,
+            signature: hex::encode(signature.to_bytes()),
+        })
+    }
+
+    /// # Verify a detached signature of some data
+    ///
+    /// Apart from the signature itself, the SafeKey XOR-URL it contains is checked to match
+    /// its public key. Checking the signer is the expected one is up to the caller.
+    pub fn verify_data(&self, data: &[u8], signature: &DataSignature) -> Result<()> {
+        let pk_bytes = hex::decode(&signature.public_key).map_err(|err| {
+            Error::InvalidInput(format!(
+                "Invalid public key '{}' in signature: {}",
+                signature.public_key, err
+            ))
+        })?;
+        let public = PublicKey::from_bytes(&pk_bytes).map_err(|err| {
+            Error::InvalidInput(format!(
+                "Invalid Ed25519 public key '{}' in signature: {}",
+                signature.public_key, err
+            ))
+        })?;
+
+        let safeurl = SafeUrl::from_url(&signature.xorurl)?;
+        if safeurl.xorname() != XorName::from(SafePublicKey::from(public)) {
+            return Err(Error::InvalidSignature(format!(
+                "The SafeKey URL '{}' doesn't correspond to the public key of the signature",
+                signature.xorurl
+            )));
+        }
+
+        let sig_bytes = hex::decode(&signature.signature).map_err(|err| {
+            Error::InvalidInput(format!("Invalid hex-encoded signature: {}", err))
+        })?;
+        let sig = Signature::try_from(sig_bytes.as_slice())
+            .map_err(|err| Error::InvalidInput(format!("Invalid Ed25519 signature: {}", err)))?;
+
+        public
+            .verify_strict(&signed_message(data), &sig)
+            .map_err(|_| {
+                Error::InvalidSignature(format!(
+                    "The signature doesn't match the data and the public key {}",
+                    signature.public_key
+                ))
+            })
+    }
+}
+
+// The message signed is the SHA3-256 digest of the data, prefixed with a domain separator
+fn signed_message(data: &[u8]) -> Vec<u8> {
+    let mut message = SIGNED_DATA_DOMAIN.to_vec();
+    message.extend_from_slice(&Sha3_256::digest(data));
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::common::sk_to_hex;
+    use anyhow::{anyhow, Result};
+
+    #[test]
+    fn test_keys_sign_and_verify_data() -> Result<()> {
+        let safe = Safe::default();
+        let keypair = safe.generate_random_ed_keypair();
+        let sk = sk_to_hex(keypair.secret_key().map_err(|err| anyhow!("{:?}", err))?);
+        let data = b"release artifact";
+
+        let signature = safe.sign_data(&sk, data)?;
+        assert_eq!(
+            signature.xorurl,
+            SafeUrl::encode_safekey(XorName::from(keypair.public_key()), safe.xorurl_base)?
+        );
+        safe.verify_data(data, &signature)?;
+
+        // the same signature is always obtained for the same data
+        assert_eq!(safe.sign_data(&sk, data)?, signature);
+
+        // the raw data is not what's signed
+        let secret = ed_sk_from_hex(&sk)?;
+        let public = PublicKey::from(&secret);
+        let sig_bytes = hex::decode(&signature.signature)?;
+        let sig = Signature::try_from(sig_bytes.as_slice())?;
+        assert!(public.verify_strict(data, &sig).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_verify_data_tampered() -> Result<()> {
+        let safe = Safe::default();
+        let keypair = safe.generate_random_ed_keypair();
+        let sk = sk_to_hex(keypair.secret_key().map_err(|err| anyhow!("{:?}", err))?);
+        let signature = safe.sign_data(&sk, b"release artifact")?;
+
+        // different data
+        assert!(matches!(
+            safe.verify_data(b"release artifact!", &signature),
+            Err(Error::InvalidSignature(_))
+        ));
+
+        // a public key other than the signer's
+        let other = safe.sign_data(
+            &sk_to_hex(
+                safe.generate_random_ed_keypair()
+                    .secret_key()
+                    .map_err(|err| anyhow!("{:?}", err))?,
+            ),
+            b"release artifact",
+        )?;
+        let mut forged = signature.clone();
+        forged.public_key = other.public_key.clone();
+        forged.xorurl = other.xorurl;
+        assert!(matches!(
+            safe.verify_data(b"release artifact", &forged),
+            Err(Error::InvalidSignature(_))
+        ));
+
+        // a SafeKey URL which doesn't match the public key
+        let mut mismatched = signature;
+        mismatched.public_key = other.public_key;
+        assert!(matches!(
+            safe.verify_data(b"release artifact", &mismatched),
+            Err(Error::InvalidSignature(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,485 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{safeurl::XorUrl, Safe};
+use crate::{Error, KvConflict, Result};
+use jsonschema::JSONSchema;
+use log::debug;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, sync::Arc};
+
+// Key of the Map entry where the store's JSON Schema is kept, it cannot be used for documents
+const SCHEMA_KEY: &str = "$kv-schema";
+
+/// A store of JSON documents on top of a Map, where each document is kept at its own key
+///
+/// Every write to a document increments its version, and writes can be made conditional on
+/// the version the caller expects the document to currently have, in which case a
+/// `KvConflict` error is returned if it was modified in the meantime.
+#[derive(Clone)]
+pub struct KvStore {
+    safe: Safe,
+    xorurl: XorUrl,
+    schema: Option<Arc<StoreSchema>>,
+}
+
+// JSON Schema of the store, compiled once when the store is opened or the schema is set
+struct StoreSchema {
+    schema: Value,
+    compiled: JSONSchema,
+}
+
+impl StoreSchema {
+    fn compile(schema: Value) -> Result<Self> {
+        let compiled = JSONSchema::compile(&schema)
+            .map_err(|err| Error::InvalidInput(format!("Invalid JSON Schema: {}", err)))?;
+        Ok(Self { schema, compiled })
+    }
+}
+
+impl Safe {
+    /// Open a store of JSON documents on an existing Map, e.g. one created with `map_create`
+    ///
+    /// ## Example
+    /// ```
+    /// # use sn_api::Safe;
+    /// # let mut safe = Safe::default();
+    /// # async_std::task::block_on(async {
+    /// #   safe.connect("", Some("fake-credentials")).await.unwrap();
+    ///     let xorurl = safe.map_create(None, 30_000).await.unwrap();
+    ///     let mut store = safe.kv_open(&xorurl).await.unwrap();
+    ///     let version = store.put("counter", &1, None).await.unwrap();
+    ///     store.put("counter", &2, Some(version)).await.unwrap();
+    ///     let counter: Option<(u64, u32)> = store.get("counter").await.unwrap();
+    ///     assert_eq!(counter, Some((1, 2)));
+    /// # });
+    /// ```
+    pub async fn kv_open(&self, url: &str) -> Result<KvStore> {
+        let safe_url = self.resolve_map_url(url).await?;
+        let xorurl = safe_url.to_xorurl_string();
+        debug!("Document store opened on Map at: {}", safe_url);
+
+        // the JSON Schema set on the store, if any, is kept along with the documents
+        let schema = match self.map_get(&xorurl, SCHEMA_KEY.as_bytes()).await {
+            Ok((_, data)) => Some(Arc::new(StoreSchema::compile(parse_document(
+                SCHEMA_KEY, &data,
+            )?)?)),
+            Err(Error::EntryNotFound(_)) => None,
+            Err(err) => return Err(err),
+        };
+
+        Ok(KvStore {
+            safe: self.clone(),
+            xorurl,
+            schema,
+        })
+    }
+}
+
+impl KvStore {
+    /// Validate all the documents written to the store against a JSON Schema
+    ///
+    /// The schema is stored in a reserved entry of the Map, replacing any previous one, so
+    /// it's enforced by every handle opened on the store afterwards. Documents which are
+    /// already stored are not validated.
+    pub async fn set_schema(&mut self, schema: Value) -> Result<()> {
+        let schema = StoreSchema::compile(schema)?;
+        let current_version = self
+            .get_value(SCHEMA_KEY)
+            .await?
+            .map(|(version, _)| version);
+        let _ = self
+            .write(SCHEMA_KEY, &schema.schema, current_version)
+            .await?;
+        self.schema = Some(Arc::new(schema));
+        Ok(())
+    }
+
+    /// The JSON Schema documents written to the store are validated against, if any
+    pub fn schema(&self) -> Option<&Value> {
+        self.schema.as_ref().map(|schema| &schema.schema)
+    }
+
+    /// The XOR-URL of the Map the documents are stored on
+    pub fn xorurl(&self) -> &str {
+        &self.xorurl
+    }
+
+    /// Get a document along with its current version, or `None` if there is no such key
+    pub async fn get<T: DeserializeOwned>(&mut self, key: &str) -> Result<Option<(u64, T)>> {
+        check_key(key)?;
+        match self.get_value(key).await? {
+            Some((version, value)) => Ok(Some((version, from_value(key, value)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Write a document, returning its new version
+    ///
+    /// If `expected_version` is `None` the key must not exist yet, otherwise it must match the
+    /// document's current version. A `KvConflict` error is returned if it doesn't.
+    pub async fn put<T: Serialize>(
+        &mut self,
+        key: &str,
+        document: &T,
+        expected_version: Option<u64>,
+    ) -> Result<u64> {
+        check_key(key)?;
+        let value = serde_json::to_value(document).map_err(|err| {
+            Error::Serialisation(format!("Couldn't serialise document '{}': {}", key, err))
+        })?;
+        self.validate(key, &value)?;
+
+        let current_version = self.get_value(key).await?.map(|(version, _)| version);
+        if current_version != expected_version {
+            return Err(version_conflict(key, expected_version, current_version));
+        }
+
+        self.write(key, &value, current_version).await
+    }
+
+    /// Replace a document only if its current content is equal to `current`, returning its
+    /// new version. If `current` is `None` the key must not exist yet.
+    ///
+    /// A `KvConflict` error is returned if the document's content is a different one,
+    /// or if it's modified by someone else before the new content is written.
+    pub async fn cas<T: Serialize>(
+        &mut self,
+        key: &str,
+        current: Option<&T>,
+        new: &T,
+    ) -> Result<u64> {
+        check_key(key)?;
+        let expected = match current {
+            Some(document) => Some(serde_json::to_value(document).map_err(|err| {
+                Error::Serialisation(format!("Couldn't serialise document '{}': {}", key, err))
+            })?),
+            None => None,
+        };
+        let value = serde_json::to_value(new).map_err(|err| {
+            Error::Serialisation(format!("Couldn't serialise document '{}': {}", key, err))
+        })?;
+        self.validate(key, &value)?;
+
+        let (current_version, current_value) = match self.get_value(key).await? {
+            Some((version, value)) => (Some(version), Some(value)),
+            None => (None, None),
+        };
+        if current_value != expected {
+            return Err(Error::KvConflict(KvConflict::Content {
+                key: key.to_string(),
+                current: current_version,
+            }));
+        }
+
+        self.write(key, &value, current_version).await
+    }
+
+    /// Delete a document
+    ///
+    /// If `expected_version` is provided it must match the document's current version,
+    /// otherwise a `KvConflict` error is returned.
+    pub async fn delete(&mut self, key: &str, expected_version: Option<u64>) -> Result<()> {
+        check_key(key)?;
+        let current_version = self.get_value(key).await?.map(|(version, _)| version);
+        match (expected_version, current_version) {
+            (_, None) => Err(Error::EntryNotFound(format!(
+                "No document found with key '{}'",
+                key
+            ))),
+            (Some(expected), Some(current)) if expected != current => {
+                Err(version_conflict(key, expected_version, current_version))
+            }
+            (_, Some(current)) => {
+                let result = self
+                    .safe
+                    .map_delete(&self.xorurl, key.as_bytes(), Some(current))
+                    .await;
+                match result {
+                    Err(Error::VersionConflict(_)) => {
+                        Err(self.modified_meanwhile(key, current_version).await)
+                    }
+                    other => other,
+                }
+            }
+        }
+    }
+
+    /// List all the documents in the store, along with their current versions
+    pub async fn list<T: DeserializeOwned>(&mut self) -> Result<BTreeMap<String, (u64, T)>> {
+        let entries = self.safe.map_list(&self.xorurl).await?;
+        let mut documents = BTreeMap::new();
+        for entry in entries {
+            let key = String::from_utf8(entry.key).map_err(|_| {
+                Error::ContentError("Found a key in the store which is not valid UTF-8".to_string())
+            })?;
+            if key == SCHEMA_KEY {
+                continue;
+            }
+            let value = parse_document(&key, &entry.value)?;
+            let document = from_value(&key, value)?;
+            documents.insert(key, (entry.version, document));
+        }
+
+        Ok(documents)
+    }
+
+    async fn get_value(&mut self, key: &str) -> Result<Option<(u64, Value)>> {
+        match self.safe.map_get(&self.xorurl, key.as_bytes()).await {
+            Ok((version, data)) => Ok(Some((version, parse_document(key, &data)?))),
+            Err(Error::EntryNotFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    // Write a document whose current version has already been checked, the network
+    // rejects the write if the document was modified in the meantime
+    async fn write(&mut self, key: &str, value: &Value, current: Option<u64>) -> Result<u64> {
+        let data = serde_json::to_vec(value).map_err(|err| {
+            Error::Serialisation(format!("Couldn't serialise document '{}': {}", key, err))
+        })?;
+
+        let result = match current {
+            None => self
+                .safe
+                .map_insert(&self.xorurl, key.as_bytes(), &data)
+                .await
+                .map(|()| 0),
+            Some(version) => {
+                self.safe
+                    .map_update(&self.xorurl, key.as_bytes(), &data, Some(version))
+                    .await
+            }
+        };
+
+        match result {
+            Err(Error::EntryExists(_)) | Err(Error::VersionConflict(_)) => {
+                Err(self.modified_meanwhile(key, current).await)
+            }
+            other => other,
+        }
+    }
+
+    // Conflict error for a document found to be modified by someone else after its version
+    // was checked, reporting the version it's been modified to
+    async fn modified_meanwhile(&mut self, key: &str, expected: Option<u64>) -> Error {
+        match self.get_value(key).await {
+            Ok(current) => version_conflict(key, expected, current.map(|(version, _)| version)),
+            Err(err) => err,
+        }
+    }
+
+    fn validate(&self, key: &str, value: &Value) -> Result<()> {
+        let schema = match &self.schema {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+
+        if let Err(errors) = schema.compiled.validate(value) {
+            let errors: Vec<String> = errors.map(|err| err.to_string()).collect();
+            return Err(Error::InvalidInput(format!(
+                "Document '{}' doesn't conform to the store's JSON Schema: {}",
+                key,
+                errors.join("; ")
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_document(key: &str, data: &[u8]) -> Result<Value> {
+    serde_json::from_slice(data).map_err(|err| {
+        Error::ContentError(format!(
+            "Document '{}' is not a valid JSON document: {}",
+            key, err
+        ))
+    })
+}
+
+fn from_value<T: DeserializeOwned>(key: &str, value: Value) -> Result<T> {
+    serde_json::from_value(value).map_err(|err| {
+        Error::Serialisation(format!("Couldn't deserialise document '{}': {}", key, err))
+    })
+}
+
+fn check_key(key: &str) -> Result<()> {
+    if key == SCHEMA_KEY {
+        return Err(Error::InvalidInput(format!(
+            "The key '{}' is reserved for the store's JSON Schema",
+            key
+        )));
+    }
+    Ok(())
+}
+
+fn version_conflict(key: &str, expected: Option<u64>, current: Option<u64>) -> Error {
+    Error::KvConflict(KvConflict::Version {
+        key: key.to_string(),
+        expected,
+        current,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::app::test_helpers::new_safe_instance, retry_loop};
+    use anyhow::{bail, Result};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Profile {
+        name: String,
+        age: u32,
+    }
+
+    #[tokio::test]
+    async fn test_kv_put_get_and_list() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.map_create(None, 30_000).await?;
+        let _ = retry_loop!(safe.map_list(&xorurl));
+        let mut store = safe.kv_open(&xorurl).await?;
+
+        let alice = Profile {
+            name: "Alice".to_string(),
+            age: 30,
+        };
+        assert_eq!(store.put("alice", &alice, None).await?, 0);
+        assert_eq!(store.get::<Profile>("alice").await?, Some((0, alice)));
+        assert_eq!(store.get::<Profile>("bob").await?, None);
+
+        let alice = Profile {
+            name: "Alice".to_string(),
+            age: 31,
+        };
+        assert_eq!(store.put("alice", &alice, Some(0)).await?, 1);
+
+        match store.put("alice", &alice, Some(0)).await {
+            Err(Error::KvConflict(conflict)) => {
+                assert_eq!(
+                    conflict.to_string(),
+                    "Document 'alice' is at version 1 but version 0 was expected"
+                );
+                assert_eq!(
+                    conflict,
+                    KvConflict::Version {
+                        key: "alice".to_string(),
+                        expected: Some(0),
+                        current: Some(1)
+                    }
+                );
+            }
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+        match store.put("alice", &alice, None).await {
+            Err(Error::KvConflict(KvConflict::Version {
+                expected: None,
+                current: Some(1),
+                ..
+            })) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+
+        let documents = store.list::<Profile>().await?;
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents.get("alice"), Some(&(1, alice)));
+
+        match store.delete("alice", Some(0)).await {
+            Err(Error::KvConflict(KvConflict::Version { .. })) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+        store.delete("alice", Some(1)).await?;
+        assert_eq!(store.get::<Profile>("alice").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_kv_cas() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.map_create(None, 30_000).await?;
+        let _ = retry_loop!(safe.map_list(&xorurl));
+        let mut store = safe.kv_open(&xorurl).await?;
+
+        assert_eq!(store.cas("counter", None, &1).await?, 0);
+        match store.cas("counter", None, &1).await {
+            Err(Error::KvConflict(KvConflict::Content {
+                current: Some(0), ..
+            })) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+
+        assert_eq!(store.cas("counter", Some(&1), &2).await?, 1);
+        match store.cas("counter", Some(&1), &3).await {
+            Err(Error::KvConflict(KvConflict::Content {
+                current: Some(1), ..
+            })) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+        assert_eq!(store.get::<u32>("counter").await?, Some((1, 2)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_kv_schema_validation() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.map_create(None, 30_000).await?;
+        let _ = retry_loop!(safe.map_list(&xorurl));
+
+        let mut store = safe.kv_open(&xorurl).await?;
+        assert!(store
+            .set_schema(json!({ "type": "unknown-type" }))
+            .await
+            .is_err());
+        assert_eq!(store.schema(), None);
+
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "age": { "type": "integer", "minimum": 0 }
+            },
+            "required": ["name", "age"]
+        });
+        store.set_schema(schema.clone()).await?;
+
+        let bob = Profile {
+            name: "Bob".to_string(),
+            age: 25,
+        };
+        store.put("bob", &bob, None).await?;
+
+        match store.put("carol", &json!({ "name": "Carol" }), None).await {
+            Err(Error::InvalidInput(_)) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+        assert_eq!(store.get::<Value>("carol").await?, None);
+
+        // the schema is kept in the Map, thus enforced by any other handle of the store,
+        // and it's not listed as a document
+        let mut other_store = safe.kv_open(&xorurl).await?;
+        assert_eq!(other_store.schema(), Some(&schema));
+        match other_store
+            .put("carol", &json!({ "name": "Carol" }), None)
+            .await
+        {
+            Err(Error::InvalidInput(_)) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+        let documents = other_store.list::<Profile>().await?;
+        assert_eq!(documents.len(), 1);
+        match other_store.put(SCHEMA_KEY, &bob, None).await {
+            Err(Error::InvalidInput(_)) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    safeurl::{SafeContentType, SafeDataType, SafeUrl, XorUrl},
+    Safe,
+};
+use crate::{Error, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sn_data_types::MapValue;
+use xor_name::XorName;
+
+/// An entry of a Map, along with its current version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MapEntry {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub version: u64,
+}
+
+impl Safe {
+    /// Create an empty Map on the network
+    ///
+    /// ## Example
+    /// ```
+    /// # use sn_api::Safe;
+    /// # let mut safe = Safe::default();
+    /// # async_std::task::block_on(async {
+    /// #   safe.connect("", Some("fake-credentials")).await.unwrap();
+    ///     let xorurl = safe.map_create(None, 30_000).await.unwrap();
+    ///     safe.map_insert(&xorurl, b"key", b"value").await.unwrap();
+    ///     let received = safe.map_get(&xorurl, b"key").await.unwrap();
+    ///     assert_eq!(received, (0, b"value".to_vec()));
+    /// # });
+    /// ```
+    pub async fn map_create(&mut self, name: Option<XorName>, type_tag: u64) -> Result<XorUrl> {
+        let xorname = self
+            .safe_client
+            .store_map(name, type_tag, None, None)
+            .await?;
+
+        SafeUrl::encode_mutable_data(xorname, type_tag, SafeContentType::Raw, self.xorurl_base)
+    }
+
+    /// Insert a new entry into a Map, failing if the key already exists
+    pub async fn map_insert(&mut self, url: &str, key: &[u8], value: &[u8]) -> Result<()> {
+        let safe_url = self.resolve_map_url(url).await?;
+        self.safe_client
+            .map_insert(safe_url.xorname(), safe_url.type_tag(), key, value)
+            .await
+    }
+
+    /// Get the value of an entry of a Map, along with its current version
+    pub async fn map_get(&mut self, url: &str, key: &[u8]) -> Result<(u64, Vec<u8>)> {
+        debug!("Getting entry from Map at: {:?}", url);
+        let safe_url = self.resolve_map_url(url).await?;
+        let value = self
+            .safe_client
+            .map_get_value(safe_url.xorname(), safe_url.type_tag(), key)
+            .await?;
+
+        match value {
+            MapValue::Seq(value) => Ok((value.version, value.data)),
+            MapValue::Unseq(_) => Err(Error::ContentError(format!(
+                "Map found at \"{}\" is unsequenced",
+                url
+            ))),
+        }
+    }
+
+    /// Update the value of an existing entry of a Map, returning the entry's new version
+    ///
+    /// If `version` is provided, the update is rejected with a `VersionConflict` error
+    /// unless it matches the entry's current version. Otherwise the entry is updated
+    /// regardless of its current version.
+    pub async fn map_update(
+        &mut self,
+        url: &str,
+        key: &[u8],
+        value: &[u8],
+        version: Option<u64>,
+    ) -> Result<u64> {
+        let safe_url = self.resolve_map_url(url).await?;
+        let current_version = match version {
+            Some(version) => version,
+            None => self.map_get(url, key).await?.0,
+        };
+
+        self.safe_client
+            .update_map(
+                safe_url.xorname(),
+                safe_url.type_tag(),
+                key,
+                value,
+                current_version + 1,
+            )
+            .await
+            .map_err(|err| version_conflict(err, current_version))?;
+
+        Ok(current_version + 1)
+    }
+
+    /// Delete an entry from a Map
+    ///
+    /// If `version` is provided, the deletion is rejected with a `VersionConflict` error
+    /// unless it matches the entry's current version.
+    pub async fn map_delete(&mut self, url: &str, key: &[u8], version: Option<u64>) -> Result<()> {
+        let safe_url = self.resolve_map_url(url).await?;
+        let current_version = match version {
+            Some(version) => version,
+            None => self.map_get(url, key).await?.0,
+        };
+
+        self.safe_client
+            .map_delete(
+                safe_url.xorname(),
+                safe_url.type_tag(),
+                key,
+                current_version + 1,
+            )
+            .await
+            .map_err(|err| version_conflict(err, current_version))
+    }
+
+    /// List all the entries of a Map
+    pub async fn map_list(&mut self, url: &str) -> Result<Vec<MapEntry>> {
+        debug!("Listing entries of Map at: {:?}", url);
+        let safe_url = self.resolve_map_url(url).await?;
+        self.fetch_map_entries(&safe_url).await
+    }
+
+    /// Fetch the entries of a Map from a SafeUrl without performing any type of URL resolution
+    pub(crate) async fn fetch_map_entries(&self, safe_url: &SafeUrl) -> Result<Vec<MapEntry>> {
+        let entries = self
+            .safe_client
+            .list_map_entries(safe_url.xorname(), safe_url.type_tag())
+            .await?;
+
+        Ok(entries
+            .into_iter()
+            .map(|(key, value)| MapEntry {
+                key,
+                value: value.data,
+                version: value.version,
+            })
+            .collect())
+    }
+
+    // Resolve the URL and check it targets a Map, i.e. raw content stored on a SeqMap
+    pub(crate) async fn resolve_map_url(&self, url: &str) -> Result<SafeUrl> {
+        let (safe_url, _) = self.parse_and_resolve_url(url).await?;
+        if safe_url.data_type() != SafeDataType::SeqMap
+            || safe_url.content_type() != SafeContentType::Raw
+        {
+            return Err(Error::InvalidInput(format!(
+                "The URL doesn't target a Map: {}",
+                url
+            )));
+        }
+
+        Ok(safe_url)
+    }
+}
+
+// The network rejects the entry actions when the version provided is not the successor
+// of the entry's current one, which is reported as an EntryExists error
+fn version_conflict(err: Error, version: u64) -> Error {
+    match err {
+        Error::EntryExists(msg) => Error::VersionConflict(format!(
+            "The entry doesn't exist or its current version is not {}: {}",
+            version, msg
+        )),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::app::test_helpers::new_safe_instance, retry_loop};
+    use anyhow::{anyhow, bail, Result};
+
+    #[tokio::test]
+    async fn test_map_insert_get_and_list() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.map_create(None, 30_000).await?;
+        let _ = retry_loop!(safe.map_list(&xorurl));
+
+        safe.map_insert(&xorurl, b"key1", b"value1").await?;
+        safe.map_insert(&xorurl, b"key2", b"value2").await?;
+        assert_eq!(
+            safe.map_get(&xorurl, b"key1").await?,
+            (0, b"value1".to_vec())
+        );
+
+        match safe.map_insert(&xorurl, b"key1", b"other").await {
+            Err(Error::EntryExists(_)) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+
+        let entries = safe.map_list(&xorurl).await?;
+        assert_eq!(
+            entries,
+            vec![
+                MapEntry {
+                    key: b"key1".to_vec(),
+                    value: b"value1".to_vec(),
+                    version: 0
+                },
+                MapEntry {
+                    key: b"key2".to_vec(),
+                    value: b"value2".to_vec(),
+                    version: 0
+                }
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_map_update_and_delete() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.map_create(None, 30_000).await?;
+        let _ = retry_loop!(safe.map_list(&xorurl));
+        safe.map_insert(&xorurl, b"key", b"v0").await?;
+
+        assert_eq!(safe.map_update(&xorurl, b"key", b"v1", None).await?, 1);
+        assert_eq!(safe.map_update(&xorurl, b"key", b"v2", Some(1)).await?, 2);
+        assert_eq!(safe.map_get(&xorurl, b"key").await?, (2, b"v2".to_vec()));
+
+        match safe.map_update(&xorurl, b"key", b"v3", Some(0)).await {
+            Err(Error::VersionConflict(_)) => {}
+            other => bail!("Error returned is not the expected one: {:?}", other),
+        }
+
+        safe.map_delete(&xorurl, b"key", Some(2)).await?;
+        match safe.map_get(&xorurl, b"key").await {
+            Err(Error::EntryNotFound(_)) => Ok(()),
+            other => Err(anyhow!(
+                "Error returned is not the expected one: {:?}",
+                other
+            )),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_map_wrong_data_type() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.sequence_create(b"data", None, 30_000, false).await?;
+        let _ = retry_loop!(safe.sequence_get(&xorurl));
+
+        match safe.map_list(&xorurl).await {
+            Err(Error::InvalidInput(msg)) => {
+                assert_eq!(msg, format!("The URL doesn't target a Map: {}", xorurl));
+                Ok(())
+            }
+            other => Err(anyhow!(
+                "Error returned is not the expected one: {:?}",
+                other
+            )),
+        }
+    }
+}
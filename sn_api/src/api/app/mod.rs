@@ -8,13 +8,18 @@
 // Software.
 
 mod auth;
+mod cache;
 mod consts;
 mod helpers;
 mod keys;
+mod kv;
+mod map;
+mod payment_request;
 mod safe_client;
 mod sequence;
 #[cfg(test)]
 mod test_helpers;
+mod watch;
 
 use super::{common, constants, Result};
 use rand::rngs::OsRng;
@@ -32,8 +37,17 @@ pub mod files;
 pub mod nrs;
 pub mod safeurl;
 pub mod wallet;
+pub use cache::CacheStats;
 pub use consts::DEFAULT_XORURL_BASE;
 pub use helpers::parse_coins_amount;
+pub use keys::{
+    DataSignature, KeyFormat, TransferDirection, TransferHistoryEntry, MNEMONIC_DEFAULT_GAP_LIMIT,
+};
+pub use kv::KvStore;
+pub use map::MapEntry;
+pub use payment_request::PaymentRequest;
+pub use sequence::{SequencePermissions, SequenceUserPermissions};
+pub use watch::WatchOptions;
 pub use xor_name::{XorName, XOR_NAME_LEN};
 
 #[derive(Clone)]
@@ -9,6 +9,7 @@
 
 mod nrs_map;
 
+pub use crate::api::app::consts::PREDICATE_LINK;
 pub use nrs_map::{DefaultRdf, NrsMap};
 
 use crate::{
@@ -20,11 +21,16 @@ use crate::{
     Error, Result,
 };
 use log::{debug, info, warn};
+use sn_data_types::PublicKey;
 use std::collections::BTreeMap;
 
 // Type tag to use for the NrsMapContainer stored on Register
 pub(crate) const NRS_MAP_TYPE_TAG: u64 = 1_500;
 
+// Number of times an NRS Map update is re-applied when the NrsMapContainer
+// was concurrently updated by someone else with non-conflicting changes
+const NRS_UPDATE_MAX_RETRIES: usize = 3;
+
 const ERROR_MSG_NO_NRS_MAP_FOUND: &str = "No NRS Map found at this address";
 
 // List of public names uploaded with details if they were added, updated or deleted from NrsMaps
@@ -53,6 +59,7 @@ impl Safe {
                 false,
                 None,
                 false, // don't resolve the URL's path
+                None,
             )
             .await?;
 
@@ -75,40 +82,41 @@ impl Safe {
         }
     }
 
+    /// # Add or update a sub name in an existing NrsMapContainer.
+    ///
+    /// If `expect_version` is provided, the update is rejected with a `VersionConflict`
+    /// error unless the NrsMapContainer is currently at that version. Otherwise, if another
+    /// client appended a new version in the meantime, the change is re-applied on top of it
+    /// as long as it doesn't touch the same sub name.
+    ///
+    /// Note the version check is not atomic: the network offers no conditional append on
+    /// Sequences, so the NrsMapContainer version is read right before appending the new
+    /// NrsMap, and another client appending in between those two operations can still have
+    /// its update overwritten. The check narrows the window for lost updates, it doesn't
+    /// close it.
     pub async fn nrs_map_container_add(
         &self,
         name: &str,
         link: &str,
         default: bool,
         hard_link: bool,
+        expect_version: Option<u64>,
         dry_run: bool,
     ) -> Result<(u64, XorUrl, ProcessedEntries, NrsMap)> {
         info!("Adding to NRS map...");
-        // GET current NRS map from name's TLD
         let (safe_url, _) = validate_nrs_name(name)?;
         let xorurl = safe_url.to_string();
-        let (version, mut nrs_map) = self.nrs_map_container_get(&xorurl).await?;
-        debug!("NRS, Existing data: {:?}", nrs_map);
 
-        let link = nrs_map.update(name, link, default, hard_link)?;
+        let (version, link, nrs_map) = self
+            .update_nrs_map_container(&safe_url, expect_version, dry_run, |nrs_map| {
+                nrs_map.update(name, link, default, hard_link)
+            })
+            .await?;
+
         let mut processed_entries = ProcessedEntries::new();
         processed_entries.insert(name.to_string(), (CONTENT_ADDED_SIGN.to_string(), link));
 
-        debug!("The new NRS Map: {:?}", nrs_map);
-        if !dry_run {
-            // Append new version of the NrsMap in the Public Sequence (NRS Map Container)
-            let nrs_map_xorurl = self.store_nrs_map(&nrs_map).await?;
-            self.safe_client
-                .append_to_sequence(
-                    nrs_map_xorurl.as_bytes(),
-                    safe_url.xorname(),
-                    safe_url.type_tag(),
-                    false,
-                )
-                .await?;
-        }
-
-        Ok((version + 1, xorurl, processed_entries, nrs_map))
+        Ok((version, xorurl, processed_entries, nrs_map))
     }
 
     /// # Create a NrsMapContainer.
@@ -135,6 +143,24 @@ impl Safe {
         default: bool,
         hard_link: bool,
         dry_run: bool,
+    ) -> Result<(XorUrl, ProcessedEntries, NrsMap)> {
+        self.nrs_map_container_create_with_writers(name, link, default, hard_link, dry_run, &[])
+            .await
+    }
+
+    /// # Create a NrsMapContainer which other users can also update.
+    ///
+    /// Apart from the owner, each of the writers is granted permission to append new
+    /// versions to the NrsMapContainer's Sequence, i.e. to add and remove sub names. As with
+    /// any Sequence, permissions can only be set when the NrsMapContainer is created.
+    pub async fn nrs_map_container_create_with_writers(
+        &mut self,
+        name: &str,
+        link: &str,
+        default: bool,
+        hard_link: bool,
+        dry_run: bool,
+        writers: &[PublicKey],
     ) -> Result<(XorUrl, ProcessedEntries, NrsMap)> {
         info!("Creating an NRS map");
         let (_, nrs_url) = validate_nrs_name(name)?;
@@ -167,7 +193,7 @@ impl Safe {
                         nrs_map_xorurl.as_bytes(),
                         Some(nrs_xorname),
                         NRS_MAP_TYPE_TAG,
-                        None,
+                        writers,
                         false,
                     )
                     .await?;
@@ -185,40 +211,67 @@ impl Safe {
         }
     }
 
+    /// # Remove a sub name from an existing NrsMapContainer.
+    ///
+    /// The `expect_version` argument has the same semantics, and the same caveat about the
+    /// check not being atomic, as in `nrs_map_container_add`.
     pub async fn nrs_map_container_remove(
         &self,
         name: &str,
+        expect_version: Option<u64>,
         dry_run: bool,
     ) -> Result<(u64, XorUrl, ProcessedEntries, NrsMap)> {
         info!("Removing from NRS map...");
-        // GET current NRS map from &name TLD
         let (safe_url, _) = validate_nrs_name(name)?;
         let xorurl = safe_url.to_string();
-        let (version, mut nrs_map) = self.nrs_map_container_get(&xorurl).await?;
-        debug!("NRS, Existing data: {:?}", nrs_map);
 
-        let removed_link = nrs_map.nrs_map_remove_subname(name)?;
+        let (version, removed_link, nrs_map) = self
+            .update_nrs_map_container(&safe_url, expect_version, dry_run, |nrs_map| {
+                nrs_map.nrs_map_remove_subname(name)
+            })
+            .await?;
+
         let mut processed_entries = ProcessedEntries::new();
         processed_entries.insert(
             name.to_string(),
             (CONTENT_DELETED_SIGN.to_string(), removed_link),
         );
 
-        debug!("The new NRS Map: {:?}", nrs_map);
-        if !dry_run {
-            // Append new version of the NrsMap in the Public Sequence (NRS Map Container)
-            let nrs_map_xorurl = self.store_nrs_map(&nrs_map).await?;
-            self.safe_client
-                .append_to_sequence(
-                    nrs_map_xorurl.as_bytes(),
-                    safe_url.xorname(),
-                    safe_url.type_tag(),
-                    false,
-                )
-                .await?;
+        Ok((version, xorurl, processed_entries, nrs_map))
+    }
+
+    /// # Update an existing NrsMapContainer to contain exactly the sub names and default provided.
+    ///
+    /// The `sub_names` map is keyed by the sub names relative to the top level `name`,
+    /// e.g. `blog.posts` for `blog.posts.mywebsite`. Sub names not found in it are removed
+    /// from the NrsMap, and all the changes are published as a single new version.
+    /// The `expect_version` argument has the same semantics, and the same caveat about the
+    /// check not being atomic, as in `nrs_map_container_add`.
+    pub async fn nrs_map_container_apply(
+        &self,
+        name: &str,
+        sub_names: &BTreeMap<String, XorUrl>,
+        default: &DefaultRdf,
+        expect_version: Option<u64>,
+        dry_run: bool,
+    ) -> Result<(u64, XorUrl, ProcessedEntries, NrsMap)> {
+        info!("Applying changes to NRS map...");
+        let (safe_url, _) = validate_nrs_name(name)?;
+        if !safe_url.sub_names().is_empty() {
+            return Err(Error::InvalidInput(format!(
+                "The NRS name to apply the changes to cannot contain sub names: {}",
+                name
+            )));
         }
+        let xorurl = safe_url.to_string();
 
-        Ok((version + 1, xorurl, processed_entries, nrs_map))
+        let (version, processed_entries, nrs_map) = self
+            .update_nrs_map_container(&safe_url, expect_version, dry_run, |nrs_map| {
+                nrs_map.sync(safe_url.top_name(), sub_names, default)
+            })
+            .await?;
+
+        Ok((version, xorurl, processed_entries, nrs_map))
     }
 
     /// # Fetch an existing NrsMapContainer.
@@ -314,6 +367,107 @@ impl Safe {
         }
     }
 
+    // Private helper to apply a change to the latest NrsMap and append it as a new
+    // version of the NrsMapContainer. Right before appending we check the NrsMapContainer
+    // is still at the version the change was applied on. If it's not, and no specific version
+    // was expected, we re-apply the change on the newer NrsMap as long as the other writer
+    // didn't touch the same entries, otherwise a VersionConflict error is returned.
+    // There is still a window between the version check and the append in which another
+    // writer's update can be lost, since Sequences don't support a conditional append.
+    async fn update_nrs_map_container<F, T>(
+        &self,
+        safe_url: &SafeUrl,
+        expect_version: Option<u64>,
+        dry_run: bool,
+        apply_change: F,
+    ) -> Result<(u64, T, NrsMap)>
+    where
+        F: Fn(&mut NrsMap) -> Result<T>,
+    {
+        // GET current NRS map from name's TLD, making sure we get
+        // the latest version rather than the one we may have in the cache
+        let xorurl = safe_url.to_string();
+        self.safe_client
+            .forget_sequence_last_entry(safe_url.xorname(), safe_url.type_tag(), false)
+            .await;
+        let (mut version, mut base_nrs_map) = self.nrs_map_container_get(&xorurl).await?;
+        debug!("NRS, Existing data: {:?}", base_nrs_map);
+
+        if let Some(expected) = expect_version {
+            if expected != version {
+                return Err(Error::VersionConflict(format!(
+                    "NRS Map Container is at version {} but version {} was expected",
+                    version, expected
+                )));
+            }
+        }
+
+        let mut attempts = 0;
+        loop {
+            let mut nrs_map = base_nrs_map.clone();
+            let result = apply_change(&mut nrs_map)?;
+            debug!("The new NRS Map: {:?}", nrs_map);
+            if nrs_map == base_nrs_map {
+                debug!("No changes were made to the NRS Map");
+                return Ok((version, result, nrs_map));
+            } else if dry_run {
+                return Ok((version + 1, result, nrs_map));
+            }
+
+            // Store the new NrsMap and make sure nobody appended a new version meanwhile
+            let nrs_map_xorurl = self.store_nrs_map(&nrs_map).await?;
+            self.safe_client
+                .forget_sequence_last_entry(safe_url.xorname(), safe_url.type_tag(), false)
+                .await;
+            let (current_version, current_nrs_map) = self.nrs_map_container_get(&xorurl).await?;
+            if current_version == version {
+                // Append new version of the NrsMap in the Public Sequence (NRS Map Container)
+                self.safe_client
+                    .append_to_sequence(
+                        nrs_map_xorurl.as_bytes(),
+                        safe_url.xorname(),
+                        safe_url.type_tag(),
+                        false,
+                    )
+                    .await?;
+
+                return Ok((version + 1, result, nrs_map));
+            }
+
+            attempts += 1;
+            let our_changes = base_nrs_map.changed_entries(&nrs_map);
+            let their_changes = base_nrs_map.changed_entries(&current_nrs_map);
+            let conflicts = our_changes
+                .intersection(&their_changes)
+                .cloned()
+                .collect::<Vec<_>>();
+            if expect_version.is_some() || !conflicts.is_empty() {
+                return Err(Error::VersionConflict(format!(
+                    "NRS Map Container was updated to version {} while version {} was being modified{}",
+                    current_version,
+                    version,
+                    if conflicts.is_empty() {
+                        "".to_string()
+                    } else {
+                        format!(", conflicting entries: {:?}", conflicts)
+                    }
+                )));
+            } else if attempts > NRS_UPDATE_MAX_RETRIES {
+                return Err(Error::VersionConflict(format!(
+                    "NRS Map Container kept being updated by others, gave up after {} attempts",
+                    attempts
+                )));
+            }
+
+            warn!(
+                "NRS Map Container was updated to version {} while version {} was being modified, re-applying changes on top of it",
+                current_version, version
+            );
+            version = current_version;
+            base_nrs_map = current_nrs_map;
+        }
+    }
+
     // Private helper to serialise an NrsMap and store it in a Public Blob
     async fn store_nrs_map(&self, nrs_map: &NrsMap) -> Result<String> {
         // The NrsMapContainer is a Sequence where each NRS Map version is
@@ -363,7 +517,7 @@ mod tests {
     use super::*;
     use crate::{
         api::app::{
-            consts::PREDICATE_LINK,
+            consts::{CONTENT_UPDATED_SIGN, PREDICATE_LINK},
             test_helpers::{new_safe_instance, random_nrs_name},
         },
         retry_loop, retry_loop_for_pattern,
@@ -432,7 +586,14 @@ mod tests {
         // add subname and set it as the new default too
         let link_v1 = format!("{}?v=1", link);
         let (version, _, _, updated_nrs_map) = safe
-            .nrs_map_container_add(&format!("a.b.{}", site_name), &link_v1, true, false, false)
+            .nrs_map_container_add(
+                &format!("a.b.{}", site_name),
+                &link_v1,
+                true,
+                false,
+                None,
+                false,
+            )
             .await?;
         assert_eq!(version, 1);
         assert_eq!(updated_nrs_map.sub_names_map.len(), 1);
@@ -441,6 +602,65 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_nrs_map_container_add_with_expected_version() -> Result<()> {
+        let site_name = random_nrs_name();
+        let mut safe = new_safe_instance().await?;
+
+        // let's create an empty files container so we have a valid to link
+        let (link, _, _) = safe
+            .files_container_create(None, None, true, true, false)
+            .await?;
+        let link_v0 = format!("{}?v=0", link);
+
+        let (xorurl, _, _) = safe
+            .nrs_map_container_create(&format!("b.{}", site_name), &link_v0, true, false, false)
+            .await?;
+        let _ = retry_loop!(safe.fetch(&xorurl, None));
+
+        // adding a subname expecting the current version succeeds
+        let (version, _, _, _) = safe
+            .nrs_map_container_add(
+                &format!("a.b.{}", site_name),
+                &link_v0,
+                false,
+                false,
+                Some(0),
+                false,
+            )
+            .await?;
+        assert_eq!(version, 1);
+        let _ = retry_loop_for_pattern!(safe.nrs_map_container_get(&xorurl), Ok((version, _)) if *version == 1)?;
+
+        // a second update based on the stale version is rejected
+        match safe
+            .nrs_map_container_add(
+                &format!("c.b.{}", site_name),
+                &link_v0,
+                false,
+                false,
+                Some(0),
+                false,
+            )
+            .await
+        {
+            Ok(_) => Err(anyhow!(
+                "NRS map add was unexpectedly successful".to_string(),
+            )),
+            Err(Error::VersionConflict(msg)) => {
+                assert_eq!(
+                    msg,
+                    "NRS Map Container is at version 1 but version 0 was expected"
+                );
+                Ok(())
+            }
+            other => Err(anyhow!(
+                "Error returned is not the expected one: {:?}",
+                other
+            )),
+        }
+    }
+
     #[tokio::test]
     async fn test_nrs_map_container_add_or_remove_with_versioned_target() -> Result<()> {
         let site_name = random_nrs_name();
@@ -465,6 +685,7 @@ mod tests {
                 "safe://linked-from-a_b_site_name?v=0",
                 true,
                 false,
+                None,
                 false,
             )
             .await
@@ -485,7 +706,7 @@ mod tests {
         };
 
         match safe
-            .nrs_map_container_remove(&versioned_sitename, false)
+            .nrs_map_container_remove(&versioned_sitename, None, false)
             .await
         {
             Ok(_) => Err(anyhow!(
@@ -527,14 +748,21 @@ mod tests {
 
         let link_v1 = format!("{}?v=1", link);
         let _ = safe
-            .nrs_map_container_add(&format!("a2.b.{}", site_name), &link_v1, true, false, false)
+            .nrs_map_container_add(
+                &format!("a2.b.{}", site_name),
+                &link_v1,
+                true,
+                false,
+                None,
+                false,
+            )
             .await?;
 
         let _ = retry_loop_for_pattern!(safe.nrs_map_container_get(&xorurl), Ok((version, _)) if *version == 1)?;
 
         // remove subname
         let (version, _, _, updated_nrs_map) = safe
-            .nrs_map_container_remove(&format!("a.b.{}", site_name), false)
+            .nrs_map_container_remove(&format!("a.b.{}", site_name), None, false)
             .await?;
 
         assert_eq!(version, 2);
@@ -563,7 +791,7 @@ mod tests {
 
         // remove subname
         let (version, _, _, updated_nrs_map) = safe
-            .nrs_map_container_remove(&format!("a.b.{}", site_name), false)
+            .nrs_map_container_remove(&format!("a.b.{}", site_name), None, false)
             .await?;
         assert_eq!(version, 1);
         assert_eq!(updated_nrs_map.sub_names_map.len(), 0);
@@ -606,7 +834,7 @@ mod tests {
 
         // remove subname
         let (version, _, _, updated_nrs_map) = safe
-            .nrs_map_container_remove(&format!("a.b.{}", site_name), false)
+            .nrs_map_container_remove(&format!("a.b.{}", site_name), None, false)
             .await?;
         assert_eq!(version, 1);
         assert_eq!(updated_nrs_map.sub_names_map.len(), 0);
@@ -614,6 +842,89 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_nrs_map_changed_entries() -> Result<()> {
+        let mut base_nrs_map = NrsMap::default();
+        let _ = base_nrs_map.update("a.site", "safe://linked-from-a?v=0", true, false)?;
+
+        let mut our_nrs_map = base_nrs_map.clone();
+        let _ = our_nrs_map.update("b.site", "safe://linked-from-b?v=0", false, false)?;
+        let mut their_nrs_map = base_nrs_map.clone();
+        let _ = their_nrs_map.update("c.site", "safe://linked-from-c?v=0", false, false)?;
+
+        let our_changes = base_nrs_map.changed_entries(&our_nrs_map);
+        let their_changes = base_nrs_map.changed_entries(&their_nrs_map);
+        assert_eq!(our_changes.into_iter().collect::<Vec<_>>(), vec!["b."]);
+        assert_eq!(
+            their_changes.iter().cloned().collect::<Vec<_>>(),
+            vec!["c."]
+        );
+
+        // changing the default conflicts with any other change of the default
+        let _ = our_nrs_map.update("b.site", "safe://linked-from-b?v=0", true, false)?;
+        let mut their_nrs_map = base_nrs_map.clone();
+        let _ = their_nrs_map.update("c.site", "safe://linked-from-c?v=0", true, false)?;
+        let our_changes = base_nrs_map.changed_entries(&our_nrs_map);
+        let their_changes = base_nrs_map.changed_entries(&their_nrs_map);
+        assert!(our_changes.contains(""));
+        assert!(their_changes.contains(""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nrs_map_sync() -> Result<()> {
+        let mut nrs_map = NrsMap::default();
+        let _ = nrs_map.update("a.site", "safe://linked-from-a?v=0", true, false)?;
+        let _ = nrs_map.update("b.site", "safe://linked-from-b?v=0", false, false)?;
+        let _ = nrs_map.update("c.b.site", "safe://linked-from-c-b?v=0", false, false)?;
+
+        let mut sub_names = BTreeMap::new();
+        sub_names.insert("a".to_string(), "safe://linked-from-a?v=0".to_string());
+        sub_names.insert("b".to_string(), "safe://linked-from-b?v=1".to_string());
+        sub_names.insert("d".to_string(), "safe://linked-from-d?v=0".to_string());
+        let default = DefaultRdf::ExistingRdf("d".to_string());
+
+        let processed_entries = nrs_map.sync("site", &sub_names, &default)?;
+        assert_eq!(processed_entries.len(), 4);
+        assert_eq!(
+            processed_entries["c.b.site"],
+            (
+                CONTENT_DELETED_SIGN.to_string(),
+                "safe://linked-from-c-b?v=0".to_string()
+            )
+        );
+        assert_eq!(
+            processed_entries["b.site"],
+            (
+                CONTENT_UPDATED_SIGN.to_string(),
+                "safe://linked-from-b?v=1".to_string()
+            )
+        );
+        assert_eq!(
+            processed_entries["d.site"],
+            (
+                CONTENT_ADDED_SIGN.to_string(),
+                "safe://linked-from-d?v=0".to_string()
+            )
+        );
+        assert_eq!(
+            processed_entries["site"],
+            (
+                CONTENT_UPDATED_SIGN.to_string(),
+                "safe://linked-from-d?v=0".to_string()
+            )
+        );
+        assert_eq!(nrs_map.get_sub_names_links(), sub_names);
+        assert_eq!(nrs_map.get_default_link()?, "safe://linked-from-d?v=0");
+
+        // syncing again with the same content produces no changes
+        let processed_entries = nrs_map.sync("site", &sub_names, &default)?;
+        assert!(processed_entries.is_empty());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_nrs_no_scheme() -> Result<()> {
         let site_name = random_nrs_name();
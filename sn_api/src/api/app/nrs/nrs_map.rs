@@ -7,9 +7,13 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+use super::ProcessedEntries;
 use crate::{
     api::app::{
-        consts::{PREDICATE_CREATED, PREDICATE_LINK, PREDICATE_MODIFIED},
+        consts::{
+            CONTENT_ADDED_SIGN, CONTENT_DELETED_SIGN, CONTENT_UPDATED_SIGN, PREDICATE_CREATED,
+            PREDICATE_LINK, PREDICATE_MODIFIED,
+        },
         fetch::{SafeContentType, SafeDataType},
         helpers::gen_timestamp_secs,
         safeurl::XorUrl,
@@ -19,7 +23,10 @@ use crate::{
 };
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fmt};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
 
 pub(crate) type SubName = String;
 pub(crate) type DefinitionData = BTreeMap<String, String>;
@@ -257,6 +264,117 @@ impl NrsMap {
         gen_nrs_map_summary(&self, "", &mut nrs_map_summary);
         nrs_map_summary
     }
+
+    // Returns the link of each sub name (relative to the top level name) found in the map
+    pub fn get_sub_names_links(&self) -> BTreeMap<SubName, XorUrl> {
+        self.get_map_summary()
+            .into_iter()
+            .filter(|(name, _)| !name.is_empty())
+            .map(|(name, def_data)| {
+                let link = def_data.get(PREDICATE_LINK).cloned().unwrap_or_default();
+                (name.trim_end_matches('.').to_string(), link)
+            })
+            .collect()
+    }
+
+    // Update the NrsMap so it contains exactly the sub names and default provided,
+    // returning the list of names which were added, updated or removed.
+    pub(crate) fn sync(
+        &mut self,
+        top_name: &str,
+        sub_names: &BTreeMap<SubName, XorUrl>,
+        default: &DefaultRdf,
+    ) -> Result<ProcessedEntries> {
+        info!("Syncing NRS map for: {}", top_name);
+        let current_links = self.get_sub_names_links();
+        let current_default = self.default.clone();
+        let current_default_link = self.get_default_link().unwrap_or_default();
+        let mut processed_entries = ProcessedEntries::new();
+
+        // Remove the sub names which are not in the new list
+        for (sub_name, link) in current_links.iter() {
+            if !sub_names.contains_key(sub_name) {
+                let name = format!("{}.{}", sub_name, top_name);
+                let _ = self.nrs_map_remove_subname(&name)?;
+                processed_entries.insert(name, (CONTENT_DELETED_SIGN.to_string(), link.clone()));
+            }
+        }
+
+        // Add the new sub names and update those which are now linked to something else
+        for (sub_name, link) in sub_names.iter() {
+            if sub_name.is_empty() || sub_name.starts_with('.') || sub_name.ends_with('.') {
+                return Err(Error::InvalidInput(format!(
+                    "Invalid sub name \"{}\" for NRS name \"{}\"",
+                    sub_name, top_name
+                )));
+            }
+            let change = match current_links.get(sub_name) {
+                None => CONTENT_ADDED_SIGN,
+                Some(current_link) if current_link != link => CONTENT_UPDATED_SIGN,
+                Some(_) => continue,
+            };
+            let name = format!("{}.{}", sub_name, top_name);
+            let link = self.update(&name, link, false, false)?;
+            processed_entries.insert(name, (change.to_string(), link));
+        }
+
+        // Finally set the default
+        self.default = match default {
+            DefaultRdf::NotSet => DefaultRdf::NotSet,
+            DefaultRdf::ExistingRdf(sub_name) => {
+                if !sub_names.contains_key(sub_name) {
+                    return Err(Error::InvalidInput(format!(
+                        "The default for NRS name \"{}\" is set to a sub name which doesn't exist: {}",
+                        top_name, sub_name
+                    )));
+                }
+                DefaultRdf::ExistingRdf(sub_name.clone())
+            }
+            DefaultRdf::OtherRdf(def_data) => {
+                let link = def_data.get(PREDICATE_LINK).ok_or_else(|| {
+                    Error::InvalidInput(format!(
+                        "No link found for the default of NRS name \"{}\"",
+                        top_name
+                    ))
+                })?;
+                validate_nrs_link(link)?;
+                match &current_default {
+                    DefaultRdf::OtherRdf(current_def_data)
+                        if current_def_data.get(PREDICATE_LINK) == Some(link) =>
+                    {
+                        current_default.clone()
+                    }
+                    _ => DefaultRdf::OtherRdf(create_nrs_name_metadata(link)),
+                }
+            }
+        };
+
+        if self.default != current_default {
+            let change = match (&current_default, &self.default) {
+                (_, DefaultRdf::NotSet) => (CONTENT_DELETED_SIGN.to_string(), current_default_link),
+                (DefaultRdf::NotSet, _) => {
+                    (CONTENT_ADDED_SIGN.to_string(), self.get_default_link()?)
+                }
+                _ => (CONTENT_UPDATED_SIGN.to_string(), self.get_default_link()?),
+            };
+            processed_entries.insert(top_name.to_string(), change);
+        }
+
+        Ok(processed_entries)
+    }
+
+    // Returns the list of names (as found in the map summary) whose definitions
+    // differ between this NrsMap and the other one provided, including the default.
+    pub(crate) fn changed_entries(&self, other: &NrsMap) -> BTreeSet<String> {
+        let summary = self.get_map_summary();
+        let other_summary = other.get_map_summary();
+        summary
+            .keys()
+            .chain(other_summary.keys())
+            .filter(|name| summary.get(*name) != other_summary.get(*name))
+            .cloned()
+            .collect()
+    }
 }
 
 fn create_nrs_name_metadata(link: &str) -> DefinitionData {
@@ -0,0 +1,289 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+// Payment requests are Wallet or SafeKey URLs with the details of the payment
+// set as query parameters, e.g.:
+// safe://<wallet-or-safekey>?amount=1.5&memo=invoice-42&expires=2021-04-01T00:00:00Z
+
+use super::{
+    helpers::parse_coins_amount,
+    safeurl::{SafeContentType, SafeDataType, SafeUrl},
+};
+use crate::{Error, Result, Safe};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+const PAYMENT_REQUEST_AMOUNT_KEY: &str = "amount";
+const PAYMENT_REQUEST_MEMO_KEY: &str = "memo";
+const PAYMENT_REQUEST_EXPIRES_KEY: &str = "expires";
+
+/// The details of a payment requested with a payment request URL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    /// URL of the Wallet or SafeKey the payment is to be made to
+    pub to: String,
+    /// Amount of safecoins requested
+    pub amount: String,
+    /// Reference of the payment, e.g. an invoice number
+    pub memo: Option<String>,
+    /// Date and time (RFC 3339) after which the request is no longer valid
+    pub expires: Option<String>,
+}
+
+impl PaymentRequest {
+    /// Check the amount about to be paid matches the amount requested
+    pub fn check_amount(&self, amount: &str) -> Result<()> {
+        let requested = parse_coins_amount(&self.amount)?;
+        let paying = parse_coins_amount(amount)?;
+        if requested.as_nano() != paying.as_nano() {
+            return Err(Error::InvalidAmount(format!(
+                "The amount '{}' doesn't match the amount requested in the payment request: {}",
+                amount, self.amount
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Safe {
+    /// # Create a payment request URL
+    ///
+    /// The URL of a Wallet or SafeKey, either a XOR-URL or an NRS-URL, is combined with
+    /// the amount requested, and optionally a memo and an expiry time, as query parameters.
+    pub fn payment_request_create(
+        &self,
+        to: &str,
+        amount: &str,
+        memo: Option<&str>,
+        expires: Option<DateTime<Utc>>,
+    ) -> Result<String> {
+        let mut safeurl = parse_payment_destination(to)?;
+
+        let amount_coins = parse_coins_amount(amount)?;
+        if amount_coins.as_nano() == 0 {
+            return Err(Error::InvalidAmount(
+                "Cannot request a zero-value payment".to_string(),
+            ));
+        }
+        if let Some(expires) = expires {
+            if expires <= Utc::now() {
+                return Err(Error::InvalidInput(
+                    "The expiry time of a payment request must be in the future".to_string(),
+                ));
+            }
+        }
+
+        safeurl.set_query_key(PAYMENT_REQUEST_AMOUNT_KEY, Some(&amount_coins.to_string()))?;
+        safeurl.set_query_key(PAYMENT_REQUEST_MEMO_KEY, memo)?;
+        let expires = expires.map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true));
+        safeurl.set_query_key(PAYMENT_REQUEST_EXPIRES_KEY, expires.as_deref())?;
+
+        Ok(safeurl.to_string())
+    }
+
+    /// # Parse and validate a payment request URL
+    ///
+    /// The request is rejected if it doesn't contain a valid amount, if any of the payment's
+    /// details is set more than once, or if it already expired.
+    pub fn payment_request_parse(&self, url: &str) -> Result<PaymentRequest> {
+        let mut safeurl = parse_payment_destination(url)?;
+
+        for key in &[
+            PAYMENT_REQUEST_AMOUNT_KEY,
+            PAYMENT_REQUEST_MEMO_KEY,
+            PAYMENT_REQUEST_EXPIRES_KEY,
+        ] {
+            if safeurl.query_key(key).len() > 1 {
+                return Err(Error::InvalidInput(format!(
+                    "Invalid payment request, the '{}' is set more than once",
+                    key
+                )));
+            }
+        }
+
+        let amount = match safeurl.query_key_first(PAYMENT_REQUEST_AMOUNT_KEY) {
+            Some(amount) => parse_coins_amount(&amount)?.to_string(),
+            None => {
+                return Err(Error::InvalidInput(
+                    "Invalid payment request, it doesn't contain the amount requested".to_string(),
+                ))
+            }
+        };
+        let memo = safeurl.query_key_first(PAYMENT_REQUEST_MEMO_KEY);
+        let expires = match safeurl.query_key_first(PAYMENT_REQUEST_EXPIRES_KEY) {
+            Some(expires) => {
+                let time = DateTime::parse_from_rfc3339(&expires).map_err(|err| {
+                    Error::InvalidInput(format!(
+                        "Invalid expiry time '{}' in payment request: {}",
+                        expires, err
+                    ))
+                })?;
+                if time.with_timezone(&Utc) <= Utc::now() {
+                    return Err(Error::InvalidInput(format!(
+                        "The payment request expired at {}",
+                        expires
+                    )));
+                }
+                Some(expires)
+            }
+            None => None,
+        };
+
+        // the destination is the URL without the payment's details
+        safeurl.set_query_key(PAYMENT_REQUEST_AMOUNT_KEY, None)?;
+        safeurl.set_query_key(PAYMENT_REQUEST_MEMO_KEY, None)?;
+        safeurl.set_query_key(PAYMENT_REQUEST_EXPIRES_KEY, None)?;
+
+        Ok(PaymentRequest {
+            to: safeurl.to_string(),
+            amount,
+            memo,
+            expires,
+        })
+    }
+}
+
+// Parse the URL payments are to be made to, XOR-URLs can be checked to be
+// targetting a Wallet or SafeKey, while NRS-URLs are resolved when paying
+fn parse_payment_destination(url: &str) -> Result<SafeUrl> {
+    let safeurl = SafeUrl::from_url(url).map_err(|err| {
+        Error::InvalidInput(format!(
+            "Invalid payment request destination URL '{}': {}",
+            url, err
+        ))
+    })?;
+
+    if safeurl.is_nrsurl()
+        || safeurl.content_type() == SafeContentType::Wallet
+        || (safeurl.content_type() == SafeContentType::Raw
+            && safeurl.data_type() == SafeDataType::SafeKey)
+    {
+        Ok(safeurl)
+    } else {
+        Err(Error::InvalidInput(format!(
+            "Payment requests can only be made for a Wallet or SafeKey, but the URL targets: {:?} ({})",
+            safeurl.content_type(),
+            safeurl.data_type()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::app::DEFAULT_XORURL_BASE;
+    use anyhow::Result;
+    use chrono::Duration;
+    use xor_name::XorName;
+
+    fn safekey_xorurl() -> Result<String> {
+        Ok(SafeUrl::encode_safekey(
+            rand::random::<XorName>(),
+            DEFAULT_XORURL_BASE,
+        )?)
+    }
+
+    #[test]
+    fn test_payment_request_create_and_parse() -> Result<()> {
+        let safe = Safe::default();
+        let to = safekey_xorurl()?;
+        let expires = Utc::now() + Duration::days(1);
+
+        let url = safe.payment_request_create(&to, "1.5", Some("invoice 42"), Some(expires))?;
+        assert!(url.starts_with(&to));
+        assert!(url.contains("amount=1.500000000"));
+
+        let request = safe.payment_request_parse(&url)?;
+        assert_eq!(request.to, to);
+        assert_eq!(request.amount, "1.500000000");
+        assert_eq!(request.memo.as_deref(), Some("invoice 42"));
+        assert_eq!(
+            request.expires,
+            Some(expires.to_rfc3339_opts(SecondsFormat::Secs, true))
+        );
+
+        // without memo and expiry time, and for an NRS-URL
+        let url = safe.payment_request_create("safe://shop", "20", None, None)?;
+        let request = safe.payment_request_parse(&url)?;
+        assert_eq!(request.to, "safe://shop");
+        assert_eq!(request.memo, None);
+        assert_eq!(request.expires, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_payment_request_invalid() -> Result<()> {
+        let safe = Safe::default();
+        let to = safekey_xorurl()?;
+
+        // not a Wallet or SafeKey
+        let files_container = SafeUrl::encode_mutable_data(
+            rand::random::<XorName>(),
+            1_100,
+            SafeContentType::FilesContainer,
+            DEFAULT_XORURL_BASE,
+        )?;
+        assert!(matches!(
+            safe.payment_request_create(&files_container, "1", None, None),
+            Err(Error::InvalidInput(_))
+        ));
+
+        // invalid or missing amounts
+        assert!(matches!(
+            safe.payment_request_create(&to, "0", None, None),
+            Err(Error::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            safe.payment_request_parse(&format!("{}?amount=abc", to)),
+            Err(Error::InvalidAmount(_))
+        ));
+        assert!(matches!(
+            safe.payment_request_parse(&to),
+            Err(Error::InvalidInput(_))
+        ));
+
+        // an amount set more than once
+        assert!(matches!(
+            safe.payment_request_parse(&format!("{}?amount=1&amount=100", to)),
+            Err(Error::InvalidInput(_))
+        ));
+
+        // expired requests
+        let past = Utc::now() - Duration::minutes(1);
+        assert!(matches!(
+            safe.payment_request_create(&to, "1", None, Some(past)),
+            Err(Error::InvalidInput(_))
+        ));
+        let expired = format!(
+            "{}?amount=1&expires={}",
+            to,
+            past.to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+        assert!(matches!(
+            safe.payment_request_parse(&expired),
+            Err(Error::InvalidInput(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_payment_request_check_amount() -> Result<()> {
+        let safe = Safe::default();
+        let url = safe.payment_request_create(&safekey_xorurl()?, "1.5", None, None)?;
+        let request = safe.payment_request_parse(&url)?;
+
+        request.check_amount("1.5")?;
+        request.check_amount("1.500000000")?;
+        assert!(matches!(
+            request.check_amount("1.6"),
+            Err(Error::InvalidAmount(_))
+        ));
+        Ok(())
+    }
+}
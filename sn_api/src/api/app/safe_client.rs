@@ -7,30 +7,67 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::fetch::Range;
-use crate::{api::ipc::BootstrapConfig, Error, Result};
+use super::{
+    cache::{CacheKey, ResolutionCache},
+    fetch::Range,
+};
+use crate::{
+    api::{common::ed_pk_from_xorname, ipc::BootstrapConfig},
+    Error, Result,
+};
 use hex::encode;
 use log::{debug, info};
 use sn_client::{Client, Error as ClientError, ErrorMessage, TransfersError};
 use sn_data_types::{
-    BlobAddress, Error as SafeNdError, Keypair, Map, MapAction, MapAddress, MapEntryActions,
-    MapPermissionSet, MapSeqEntryActions, MapSeqValue, MapValue, PublicKey, SequenceAddress,
-    SequencePrivatePermissions, SequencePublicPermissions, SequenceUser, Token,
+    ActorHistory, BlobAddress, Error as SafeNdError, Keypair, Map, MapAction, MapAddress,
+    MapEntryActions, MapPermissionSet, MapSeqEntryActions, MapSeqValue, MapValue, PublicKey,
+    SequenceAction, SequenceAddress, SequenceIndex, SequencePrivatePermissions,
+    SequencePublicPermissions, SequenceUser, Token,
 };
 use std::{
     collections::{BTreeMap, HashSet},
     net::SocketAddr,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
 };
 use xor_name::XorName;
 
 const APP_NOT_CONNECTED: &str = "Application is not connected to the network";
 
+// Counters of the reads sent to the network, i.e. not served from the cache,
+// and the number of bytes of content they retrieved. They are shared by all
+// the clones of a client, thus by all the clones of a Safe instance.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct NetworkStats {
+    calls: Arc<AtomicU64>,
+    bytes: Arc<AtomicU64>,
+}
+
+impl NetworkStats {
+    fn record(&self, bytes: usize) {
+        let _ = self.calls.fetch_add(1, Ordering::Relaxed);
+        let _ = self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    // Current number of calls made and bytes retrieved
+    pub(crate) fn snapshot(&self) -> (u64, u64) {
+        (
+            self.calls.load(Ordering::Relaxed),
+            self.bytes.load(Ordering::Relaxed),
+        )
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct SafeAppClient {
     safe_client: Option<Client>,
     pub(crate) bootstrap_config: Option<HashSet<SocketAddr>>,
     config_path: Option<PathBuf>,
+    pub(crate) cache: ResolutionCache,
+    pub(crate) net_stats: NetworkStats,
 }
 
 impl SafeAppClient {
@@ -47,6 +84,8 @@ impl SafeAppClient {
             safe_client: None,
             bootstrap_config: None,
             config_path: None,
+            cache: ResolutionCache::default(),
+            net_stats: NetworkStats::default(),
         }
     }
 
@@ -109,6 +148,30 @@ impl SafeAppClient {
             .map_err(|err| Error::NetDataError(format!("Failed to retrieve balance: {:?}", err)))
     }
 
+    // Read the balance of a SafeKey from its XorName, which doesn't require its secret key
+    pub async fn read_balance_from_xorname(&self, xorname: XorName) -> Result<Token> {
+        let pk = ed_pk_from_xorname(xorname)?;
+        let client = self.get_safe_client()?;
+
+        client
+            .get_balance_for(pk)
+            .await
+            .map_err(|err| Error::NetDataError(format!("Failed to retrieve balance: {:?}", err)))
+    }
+
+    pub async fn read_history_from_keypair(&self, id: Keypair) -> Result<ActorHistory> {
+        let temp_client = Client::new(
+            Some(id),
+            self.config_path.as_deref(),
+            self.bootstrap_config.clone(),
+        )
+        .await?;
+
+        temp_client.get_history().await.map_err(|err| {
+            Error::NetDataError(format!("Failed to retrieve transfer history: {:?}", err))
+        })
+    }
+
     #[cfg(feature = "simulated-payouts")]
     pub async fn trigger_simulated_farming_payout(
         &self,
@@ -137,18 +200,8 @@ impl SafeAppClient {
         to_xorname: XorName,
         amount: Token,
     ) -> Result<u64> {
-        // Get pk from xorname. We assume Ed25519 key for now, which is
-        // 32 bytes long, just like a xorname.
-        // TODO: support for BLS keys which are longer.
-        let to_pk = ed25519_dalek::PublicKey::from_bytes(to_xorname.as_ref()).map_err(|err| {
-            Error::NetDataError(format!(
-                "Failed to derive Ed25519 PublicKey from Xorname '{}': {:?}",
-                to_xorname, err
-            ))
-        })?;
-
-        self.safecoin_transfer_to_pk(from_id, to_pk.into(), amount)
-            .await
+        let to_pk = ed_pk_from_xorname(to_xorname)?;
+        self.safecoin_transfer_to_pk(from_id, to_pk, amount).await
     }
 
     pub async fn safecoin_transfer_to_pk(
@@ -215,6 +268,20 @@ impl SafeAppClient {
     pub async fn get_public_blob(&self, xorname: XorName, range: Range) -> Result<Vec<u8>> {
         debug!("Fetching immutable data: {:?}", &xorname);
 
+        // Blobs are immutable, so if we have it in the cache we can serve any range from it
+        let cache_key = CacheKey::Blob(xorname);
+        if let Some((_, data)) = self.cache.get(&cache_key).await {
+            return Ok(match range {
+                Some((start, end)) => {
+                    let len = data.len() as u64;
+                    let end_index = end.unwrap_or(len).min(len);
+                    let start_index = start.unwrap_or(0).min(end_index);
+                    data[start_index as usize..end_index as usize].to_vec()
+                }
+                None => data,
+            });
+        }
+
         let client = self.get_safe_client()?;
         let blob_address = BlobAddress::Public(xorname);
         let data = if let Some((start, end)) = range {
@@ -228,12 +295,18 @@ impl SafeAppClient {
             client.read_blob(blob_address, None, None).await
         }
         .map_err(|e| Error::NetDataError(format!("Failed to GET Public Blob: {:?}", e)))?;
+        self.net_stats.record(data.len());
 
         debug!(
             "Public Blob data successfully retrieved from: {:?}",
             &xorname
         );
 
+        // We only cache the Blob when we've got its whole content
+        if range.is_none() {
+            self.cache.put(cache_key, 0, &data).await;
+        }
+
         Ok(data)
     }
 
@@ -305,7 +378,7 @@ impl SafeAppClient {
         let key_vec = key.to_vec();
         let address = MapAddress::Seq { name, tag };
 
-        client
+        let value = client
             .get_map_value(address, key_vec)
             .await
             .map_err(|err| match err {
@@ -328,7 +401,14 @@ impl SafeAppClient {
                     ))
                 }
                 err => Error::NetDataError(format!("Failed to retrieve a key. {:?}", err)),
-            })
+            })?;
+
+        let bytes = match &value {
+            MapValue::Seq(seq_value) => seq_value.data.len(),
+            MapValue::Unseq(data) => data.len(),
+        };
+        self.net_stats.record(bytes);
+        Ok(value)
     }
 
     pub async fn list_map_entries(
@@ -337,7 +417,7 @@ impl SafeAppClient {
         tag: u64,
     ) -> Result<BTreeMap<Vec<u8>, MapSeqValue>> {
         let client = self.get_safe_client()?;
-        client
+        let entries = client
             .list_seq_map_entries(name, tag)
             .await
             .map_err(|err| match err {
@@ -364,7 +444,14 @@ impl SafeAppClient {
                     ))
                 }
                 err => Error::NetDataError(format!("Failed to get Sequenced Map. {:?}", err)),
-            })
+            })?;
+
+        let bytes = entries
+            .iter()
+            .map(|(key, value)| key.len() + value.data.len())
+            .sum();
+        self.net_stats.record(bytes);
+        Ok(entries)
     }
 
     async fn edit_map_entries(
@@ -403,13 +490,26 @@ impl SafeAppClient {
             .await
     }
 
+    pub async fn map_delete(
+        &self,
+        name: XorName,
+        tag: u64,
+        key: &[u8],
+        version: u64,
+    ) -> Result<()> {
+        let entry_actions = MapSeqEntryActions::new();
+        let entry_actions = entry_actions.del(key.to_vec(), version);
+        self.edit_map_entries(name, tag, entry_actions, "Failed to delete from SeqMap")
+            .await
+    }
+
     // === Sequence data operations ===
     pub async fn store_sequence(
         &self,
         data: &[u8],
         name: Option<XorName>,
         tag: u64,
-        _permissions: Option<String>,
+        writers: &[PublicKey],
         private: bool,
     ) -> Result<XorName> {
         debug!(
@@ -428,9 +528,13 @@ impl SafeAppClient {
 
         // Store the Sequence on the network
         let _address = if private {
-            // Set permissions for append, delete, and manage perms to this application
+            // Set permissions for append, delete, and manage perms to this application,
+            // and for read and append to any other writer the Sequence is shared with
             let mut perms = BTreeMap::default();
             let _ = perms.insert(owner, SequencePrivatePermissions::new(true, true));
+            for writer in writers.iter() {
+                let _ = perms.insert(*writer, SequencePrivatePermissions::new(true, true));
+            }
 
             client
                 .store_private_sequence(Some(vec![data.to_vec()]), xorname, tag, owner, perms)
@@ -439,10 +543,17 @@ impl SafeAppClient {
                     Error::NetDataError(format!("Failed to store Private Sequence data: {:?}", e))
                 })?
         } else {
-            // Set permissions for append and manage perms to this application
+            // Set permissions for append and manage perms to this application,
+            // and for append to any other writer the Sequence is shared with
             let user_app = SequenceUser::Key(owner);
             let mut perms = BTreeMap::default();
             let _ = perms.insert(user_app, SequencePublicPermissions::new(true));
+            for writer in writers.iter() {
+                let _ = perms.insert(
+                    SequenceUser::Key(*writer),
+                    SequencePublicPermissions::new(true),
+                );
+            }
 
             client
                 .store_public_sequence(Some(vec![data.to_vec()]), xorname, tag, owner, perms)
@@ -455,6 +566,65 @@ impl SafeAppClient {
         Ok(xorname)
     }
 
+    // Fetch the owner of a Sequence, and the read and append permissions of each user
+    // it has granted permissions to. Users of Private Sequences are always keys.
+    pub async fn sequence_get_policy(
+        &self,
+        name: XorName,
+        tag: u64,
+        private: bool,
+    ) -> Result<(PublicKey, Vec<(SequenceUser, bool, bool)>)> {
+        debug!(
+            "Fetching policy of {} Sequence data w/ type: {:?}, xorname: {:?}",
+            if private { "Private" } else { "Public" },
+            tag,
+            name
+        );
+
+        let client = self.get_safe_client()?;
+        let map_err = |err: ClientError| {
+            if let ClientError::NetworkDataError(SafeNdError::NoSuchData) = err {
+                Error::ContentNotFound(format!("No Sequence found at XoR name {}", name))
+            } else {
+                Error::NetDataError(format!("Failed to retrieve Sequence policy: {:?}", err))
+            }
+        };
+
+        if private {
+            let policy = client
+                .get_sequence_private_policy(SequenceAddress::Private { name, tag })
+                .await
+                .map_err(map_err)?;
+            let users = policy
+                .permissions
+                .iter()
+                .map(|(user, perms)| {
+                    (
+                        SequenceUser::Key(*user),
+                        perms.is_allowed(SequenceAction::Read),
+                        perms.is_allowed(SequenceAction::Append),
+                    )
+                })
+                .collect();
+            Ok((policy.owner, users))
+        } else {
+            let policy = client
+                .get_sequence_public_policy(SequenceAddress::Public { name, tag })
+                .await
+                .map_err(map_err)?;
+            // anyone can read a Public Sequence
+            let users = policy
+                .permissions
+                .iter()
+                .map(|(user, perms)| {
+                    let append = perms.is_allowed(SequenceAction::Append).unwrap_or(false);
+                    (*user, true, append)
+                })
+                .collect();
+            Ok((policy.owner, users))
+        }
+    }
+
     pub async fn sequence_get_last_entry(
         &self,
         name: XorName,
@@ -468,6 +638,11 @@ impl SafeAppClient {
             name
         );
 
+        let cache_key = CacheKey::SequenceLastEntry { name, tag, private };
+        if let Some(last_entry) = self.cache.get(&cache_key).await {
+            return Ok(last_entry);
+        }
+
         let client = self.get_safe_client()?;
 
         let sequence_address = if private {
@@ -476,7 +651,7 @@ impl SafeAppClient {
             SequenceAddress::Public { name, tag }
         };
 
-        client
+        let (index, entry) = client
             .get_sequence_last_entry(sequence_address)
             .await
             .map_err(|err| {
@@ -488,7 +663,27 @@ impl SafeAppClient {
                         err
                     ))
                 }
-            })
+            })?;
+        self.net_stats.record(entry.len());
+
+        // The entry at this index won't ever change, so we can cache it as such as well
+        self.cache.put(cache_key, index, &entry).await;
+        let entry_cache_key = CacheKey::SequenceEntry {
+            name,
+            tag,
+            private,
+            index,
+        };
+        self.cache.put(entry_cache_key, index, &entry).await;
+
+        Ok((index, entry))
+    }
+
+    // Forget the last entry of a Sequence we may have in the cache,
+    // so it's fetched from the network next time it's needed.
+    pub async fn forget_sequence_last_entry(&self, name: XorName, tag: u64, private: bool) {
+        let cache_key = CacheKey::SequenceLastEntry { name, tag, private };
+        self.cache.remove(&cache_key).await;
     }
 
     pub async fn sequence_get_entry(
@@ -505,6 +700,16 @@ impl SafeAppClient {
             name
         );
 
+        let cache_key = CacheKey::SequenceEntry {
+            name,
+            tag,
+            private,
+            index,
+        };
+        if let Some((_, entry)) = self.cache.get(&cache_key).await {
+            return Ok(entry);
+        }
+
         let client = self.get_safe_client()?;
 
         let sequence_address = if private {
@@ -529,10 +734,95 @@ impl SafeAppClient {
                     ))
                 }
             })?;
+        self.net_stats.record(entry.len());
 
+        self.cache.put(cache_key, index, &entry).await;
         Ok(entry.to_vec())
     }
 
+    // Fetch the entries of a Sequence within the range of indexes [start, end)
+    pub async fn sequence_get_range(
+        &self,
+        name: XorName,
+        tag: u64,
+        start: u64,
+        end: u64,
+        private: bool,
+    ) -> Result<Vec<Vec<u8>>> {
+        debug!(
+            "Fetching range [{}, {}) of {} Sequence data w/ type: {:?}, xorname: {:?}",
+            start,
+            end,
+            if private { "Private" } else { "Public" },
+            tag,
+            name
+        );
+
+        // All entries may have been cached already when reading previous ranges
+        let mut cached_entries = vec![];
+        for index in start..end {
+            let cache_key = CacheKey::SequenceEntry {
+                name,
+                tag,
+                private,
+                index,
+            };
+            match self.cache.get(&cache_key).await {
+                Some((_, entry)) => cached_entries.push(entry),
+                None => break,
+            }
+        }
+        if cached_entries.len() as u64 == end - start {
+            return Ok(cached_entries);
+        }
+
+        let client = self.get_safe_client()?;
+
+        let sequence_address = if private {
+            SequenceAddress::Private { name, tag }
+        } else {
+            SequenceAddress::Public { name, tag }
+        };
+
+        let entries = client
+            .get_sequence_range(
+                sequence_address,
+                (
+                    SequenceIndex::FromStart(start),
+                    SequenceIndex::FromStart(end),
+                ),
+            )
+            .await
+            .map_err(|err| {
+                if let ClientError::NetworkDataError(SafeNdError::NoSuchEntry) = err {
+                    Error::VersionNotFound(format!(
+                        "Invalid range of versions [{}, {}) for Sequence found at XoR name {}",
+                        start, end, name
+                    ))
+                } else {
+                    Error::NetDataError(format!(
+                        "Failed to retrieve range of entries from Sequence data: {:?}",
+                        err
+                    ))
+                }
+            })?;
+        self.net_stats
+            .record(entries.iter().map(|entry| entry.len()).sum());
+
+        // Entries won't ever change, so we can cache them individually
+        for (index, entry) in (start..).zip(entries.iter()) {
+            let cache_key = CacheKey::SequenceEntry {
+                name,
+                tag,
+                private,
+                index,
+            };
+            self.cache.put(cache_key, index, entry).await;
+        }
+
+        Ok(entries)
+    }
+
     pub async fn append_to_sequence(
         &self,
         data: &[u8],
@@ -558,6 +848,10 @@ impl SafeAppClient {
         client
             .append_to_sequence(sequence_address, data.to_vec())
             .await
-            .map_err(|e| Error::NetDataError(format!("Failed to append to Sequence: {:?}", e)))
+            .map_err(|e| Error::NetDataError(format!("Failed to append to Sequence: {:?}", e)))?;
+
+        // The last entry we may have cached is not the latest anymore
+        self.forget_sequence_last_entry(name, tag, private).await;
+        Ok(())
     }
 }
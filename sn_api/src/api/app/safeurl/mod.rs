@@ -10,6 +10,8 @@
 mod url_parts;
 mod xorurl_media_types;
 
+pub use url_parts::HomographRisk;
+
 use crate::{
     api::app::{nrs::NRS_MAP_TYPE_TAG, DEFAULT_XORURL_BASE},
     Error, Result,
@@ -20,14 +22,17 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use tiny_keccak::{Hasher, Sha3};
 use url::Url;
-use url_parts::SafeUrlParts;
+use url_parts::{detect_homograph, public_name_to_unicode, SafeUrlParts};
 use xor_name::{XorName, XOR_NAME_LEN}; // for parsing raw path
 use xorurl_media_types::{MEDIA_TYPE_CODES, MEDIA_TYPE_STR};
 
 const SAFE_URL_PROTOCOL: &str = "safe://";
 const SAFE_URL_SCHEME: &str = "safe";
 const XOR_URL_VERSION_1: u64 = 0x1; // TODO: consider using 16 bits
-const XOR_URL_STR_MAX_LENGTH: usize = 44;
+const XOR_URL_VERSION_2: u64 = 0x2; // same as v1 plus a checksum
+const XOR_URL_STR_MAX_LENGTH: usize = 44; // not including the checksum
+const XOR_URL_CHECKSUM_LEN: usize = 4;
+const XOR_URL_CHECKSUM_ERROR: &str = "Invalid XOR-URL checksum";
 const XOR_NAME_BYTES_OFFSET: usize = 4; // offset where to find the XoR name bytes
 const URL_VERSION_QUERY_NAME: &str = "v";
 
@@ -40,6 +45,10 @@ pub enum XorUrlBase {
     Base32z,
     Base32,
     Base64,
+    Base58Btc,
+    Base36,
+    Base16,
+    Base64Url,
 }
 
 impl std::str::FromStr for XorUrlBase {
@@ -49,8 +58,12 @@ impl std::str::FromStr for XorUrlBase {
             "base32z" => Ok(Self::Base32z),
             "base32" => Ok(Self::Base32),
             "base64" => Ok(Self::Base64),
+            "base58btc" => Ok(Self::Base58Btc),
+            "base36" => Ok(Self::Base36),
+            "base16" => Ok(Self::Base16),
+            "base64url" => Ok(Self::Base64Url),
             other => Err(Error::InvalidInput(format!(
-                "Invalid XOR URL base encoding: {}. Supported values are base32z, base32, and base64",
+                "Invalid XOR URL base encoding: {}. Supported values are base32z, base32, base64, base58btc, base36, base16, and base64url",
                 other
             ))),
         }
@@ -65,12 +78,7 @@ impl fmt::Display for XorUrlBase {
 
 impl XorUrlBase {
     pub fn from_u8(value: u8) -> Result<Self> {
-        match value {
-            0 => Ok(Self::Base32z),
-            1 => Ok(Self::Base32),
-            2 => Ok(Self::Base64),
-            _other => Err(Error::InvalidInput("Invalid XOR URL base encoding code. Supported values are 0=base32z, 1=base32, and 2=base64".to_string())),
-        }
+        Self::from_u16(u16::from(value))
     }
 
     pub fn from_u16(value: u16) -> Result<Self> {
@@ -78,7 +86,24 @@ impl XorUrlBase {
             0 => Ok(Self::Base32z),
             1 => Ok(Self::Base32),
             2 => Ok(Self::Base64),
-            _other => Err(Error::InvalidInput("Invalid XOR URL base encoding code. Supported values are 0=base32z, 1=base32, and 2=base64".to_string())),
+            3 => Ok(Self::Base58Btc),
+            4 => Ok(Self::Base36),
+            5 => Ok(Self::Base16),
+            6 => Ok(Self::Base64Url),
+            _other => Err(Error::InvalidInput("Invalid XOR URL base encoding code. Supported values are 0=base32z, 1=base32, 2=base64, 3=base58btc, 4=base36, 5=base16, and 6=base64url".to_string())),
+        }
+    }
+
+    // The corresponding multibase encoding
+    fn multibase(self) -> Base {
+        match self {
+            Self::Base32z => Base::Base32Z,
+            Self::Base32 => Base::Base32Lower,
+            Self::Base64 => Base::Base64,
+            Self::Base58Btc => Base::Base58Btc,
+            Self::Base36 => Base::Base36Lower,
+            Self::Base16 => Base::Base16Lower,
+            Self::Base64Url => Base::Base64Url,
         }
     }
 }
@@ -100,6 +125,25 @@ impl std::fmt::Display for SafeContentType {
     }
 }
 
+impl std::str::FromStr for SafeContentType {
+    type Err = Error;
+    fn from_str(str: &str) -> Result<Self> {
+        match str {
+            "Raw" => Ok(Self::Raw),
+            "Wallet" => Ok(Self::Wallet),
+            "FilesContainer" => Ok(Self::FilesContainer),
+            "NrsMapContainer" => Ok(Self::NrsMapContainer),
+            media_type if SafeUrl::is_media_type_supported(media_type) => {
+                Ok(Self::MediaType(media_type.to_string()))
+            }
+            other => Err(Error::InvalidInput(format!(
+                "Invalid content type: {}. Supported values are Raw, Wallet, FilesContainer, NrsMapContainer, or a supported media-type",
+                other
+            ))),
+        }
+    }
+}
+
 impl SafeContentType {
     pub fn from_u16(value: u16) -> Result<Self> {
         match value {
@@ -145,6 +189,25 @@ impl std::fmt::Display for SafeDataType {
     }
 }
 
+impl std::str::FromStr for SafeDataType {
+    type Err = Error;
+    fn from_str(str: &str) -> Result<Self> {
+        match str {
+            "SafeKey" => Ok(Self::SafeKey),
+            "PublicBlob" => Ok(Self::PublicBlob),
+            "PrivateBlob" => Ok(Self::PrivateBlob),
+            "PublicSequence" => Ok(Self::PublicSequence),
+            "PrivateSequence" => Ok(Self::PrivateSequence),
+            "SeqMap" => Ok(Self::SeqMap),
+            "UnseqMap" => Ok(Self::UnseqMap),
+            other => Err(Error::InvalidInput(format!(
+                "Invalid SAFE data type: {}. Supported values are SafeKey, PublicBlob, PrivateBlob, PublicSequence, PrivateSequence, SeqMap, and UnseqMap",
+                other
+            ))),
+        }
+    }
+}
+
 impl SafeDataType {
     pub fn from_u64(value: u64) -> Result<Self> {
         match value {
@@ -210,7 +273,7 @@ impl SafeUrlType {
 ///   sub_names()   --> None
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SafeUrl {
-    encoding_version: u64,      // currently only v1 supported
+    encoding_version: u64,      // v1, or v2 which includes a checksum
     xor_name: XorName,          // applies to nrsurl and xorurl
     public_name: String,        // "a.b.name" in "a.b.name"
     top_name: String,           // "name" in "a.b.name"
@@ -320,7 +383,7 @@ impl SafeUrl {
 
         // finally, instantiate.
         let mut safe_url = Self {
-            encoding_version: XOR_URL_VERSION_1,
+            encoding_version: XOR_URL_VERSION_2,
             xor_name,
             public_name,
             top_name,
@@ -378,6 +441,10 @@ impl SafeUrl {
     pub fn from_url(url: &str) -> Result<Self> {
         match Self::from_xorurl(url) {
             Ok(enc) => Ok(enc),
+            // a mistyped XOR-URL shouldn't be resolved as an NRS name
+            Err(Error::InvalidXorUrl(msg)) if msg.starts_with(XOR_URL_CHECKSUM_ERROR) => {
+                Err(Error::InvalidXorUrl(msg))
+            }
             Err(err) => {
                 info!(
                     "Falling back to NRS. XorUrl decoding failed with: {:?}",
@@ -425,8 +492,15 @@ impl SafeUrl {
 
         let type_tag_offset = XOR_NAME_BYTES_OFFSET + XOR_NAME_LEN; // offset where to find the type tag bytes
 
+        // the checksum is appended only since version 2 of the encoding
+        let checksum_len = if xorurl_bytes.first() == Some(&(XOR_URL_VERSION_2 as u8)) {
+            XOR_URL_CHECKSUM_LEN
+        } else {
+            0
+        };
+
         // check if too short
-        if xorurl_bytes.len() < type_tag_offset {
+        if xorurl_bytes.len() < type_tag_offset + checksum_len {
             return Err(Error::InvalidXorUrl(format!(
                 "Invalid XOR-URL, encoded string too short: {} bytes",
                 xorurl_bytes.len()
@@ -434,7 +508,7 @@ impl SafeUrl {
         }
 
         // check if too long
-        if xorurl_bytes.len() > XOR_URL_STR_MAX_LENGTH {
+        if xorurl_bytes.len() > XOR_URL_STR_MAX_LENGTH + checksum_len {
             return Err(Error::InvalidXorUrl(format!(
                 "Invalid XOR-URL, encoded string too long: {} bytes",
                 xorurl_bytes.len()
@@ -444,13 +518,33 @@ impl SafeUrl {
         // let's make sure we support the XOR_URL version
         let u8_version: u8 = xorurl_bytes[0];
         let encoding_version: u64 = u64::from(u8_version);
-        if encoding_version != XOR_URL_VERSION_1 {
+        if encoding_version != XOR_URL_VERSION_1 && encoding_version != XOR_URL_VERSION_2 {
             return Err(Error::InvalidXorUrl(format!(
                 "Invalid or unsupported XOR-URL encoding version: {}",
                 encoding_version
             )));
         }
 
+        // verify the checksum, trying to find the typo when it doesn't match
+        let (xorurl_bytes, checksum) = xorurl_bytes.split_at(xorurl_bytes.len() - checksum_len);
+        if checksum_len > 0 && checksum != xorurl_checksum(xorurl_bytes) {
+            let msg = match locate_xorurl_typo(&parts.top_name) {
+                Some(index) => {
+                    let offset = xorurl
+                        .find(&parts.top_name)
+                        .map(|offset| xorurl[..offset].chars().count())
+                        .unwrap_or(0);
+                    format!(
+                        "{}, likely typo at position {}",
+                        XOR_URL_CHECKSUM_ERROR,
+                        offset + index + 1
+                    )
+                }
+                None => format!("{}, the XOR-URL may contain typos", XOR_URL_CHECKSUM_ERROR),
+            };
+            return Err(Error::InvalidXorUrl(msg));
+        }
+
         let mut content_type_bytes = [0; 2];
         content_type_bytes[0..].copy_from_slice(&xorurl_bytes[1..3]);
         let content_type = match u16::from_be_bytes(content_type_bytes) {
@@ -502,7 +596,7 @@ impl SafeUrl {
         type_tag_bytes[8 - type_tag_bytes_len..].copy_from_slice(&xorurl_bytes[type_tag_offset..]);
         let type_tag: u64 = u64::from_be_bytes(type_tag_bytes);
 
-        Self::new(
+        let mut safe_url = Self::new(
             xor_name,
             None, // no nrs_name for an xorurl
             type_tag,
@@ -513,7 +607,11 @@ impl SafeUrl {
             Some(&parts.query_string),
             Some(&parts.fragment),
             None,
-        )
+        )?;
+
+        // keep the version so it's encoded back into the same XOR-URL
+        safe_url.encoding_version = encoding_version;
+        Ok(safe_url)
     }
 
     /// The url scheme.  Only 'safe' scheme is presently supported.
@@ -568,6 +666,24 @@ impl SafeUrl {
         &self.public_name
     }
 
+    /// The public_name in url, in its Unicode form.
+    ///
+    /// Internationalised names are kept in their ASCII (punycode) form,
+    /// eg a.xn--caf-dma --> a.café
+    pub fn public_name_unicode(&self) -> String {
+        public_name_to_unicode(&self.public_name)
+    }
+
+    /// Checks if the public_name could be visually mistaken for a
+    /// different name, e.g. when it mixes Latin and Cyrillic characters.
+    pub fn homograph_risk(&self) -> Option<HomographRisk> {
+        if self.is_nrsurl() {
+            detect_homograph(&self.public_name_unicode())
+        } else {
+            None
+        }
+    }
+
     /// returns top name of name field.
     ///
     /// eg: a.b.name --> name
@@ -816,12 +932,13 @@ impl SafeUrl {
         &self.safeurl_type
     }
 
-    // XOR-URL encoding format (var length from 36 to 44 bytes):
+    // XOR-URL encoding format (var length from 36 to 44 bytes, or 40 to 48 bytes since v2):
     // 1 byte for encoding version
     // 2 bytes for content type (enough to start including some MIME types also)
     // 1 byte for SAFE native data type
     // 32 bytes for XoR Name
-    // and up to 8 bytes for type_tag
+    // up to 8 bytes for type_tag
+    // and since v2, 4 bytes of checksum of all the above
     // query param "v=" is treated as the content version
 
     /// serializes the URL to an XorUrl string.
@@ -868,7 +985,7 @@ impl SafeUrl {
     /// serializes name portion of xorurl using a particular base encoding.
     pub fn name_to_base(&self, base: XorUrlBase, include_subnames: bool) -> String {
         // let's set the first byte with the XOR-URL format version
-        let mut cid_vec: Vec<u8> = vec![XOR_URL_VERSION_1 as u8];
+        let mut cid_vec: Vec<u8> = vec![self.encoding_version as u8];
 
         cid_vec.extend_from_slice(&self.content_type_u16.to_be_bytes());
 
@@ -883,12 +1000,13 @@ impl SafeUrl {
         // add the non-zero bytes of type_tag
         cid_vec.extend_from_slice(&self.type_tag.to_be_bytes()[start_byte..]);
 
-        let base_encoding = match base {
-            XorUrlBase::Base32z => Base::Base32Z,
-            XorUrlBase::Base32 => Base::Base32Lower,
-            XorUrlBase::Base64 => Base::Base64,
-        };
-        let top_name = base_encode(base_encoding, cid_vec);
+        // since v2 a checksum of all the above is appended
+        if self.encoding_version >= XOR_URL_VERSION_2 {
+            let checksum = xorurl_checksum(&cid_vec);
+            cid_vec.extend_from_slice(&checksum);
+        }
+
+        let top_name = base_encode(base.multibase(), cid_vec);
 
         if include_subnames {
             let sub_names = self.sub_names();
@@ -912,6 +1030,39 @@ impl SafeUrl {
         urlencoding::encode(s)
     }
 
+    /// Resolves a URL reference relative to this URL, as per RFC 3986.
+    ///
+    /// The reference can be an absolute URL, a network-path reference
+    /// (e.g. "//name/path"), an absolute or relative path which can contain
+    /// "." and ".." segments, or just a query string and/or a fragment.
+    ///
+    /// When the reference is relative, the content version ("?v=") of this
+    /// URL is retained unless the reference sets its own, so all the links
+    /// of a site are resolved using the same version of it.
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - the (possibly relative) URL to resolve
+    pub fn join(&self, reference: &str) -> Result<Self> {
+        let base = Url::parse(&self.to_string()).map_err(|err| {
+            Error::InvalidXorUrl(format!("Problem parsing the URL \"{}\": {}", self, err))
+        })?;
+        let joined = base.join(reference).map_err(|err| {
+            Error::InvalidInput(format!(
+                "Failed to resolve \"{}\" relative to \"{}\": {}",
+                reference, self, err
+            ))
+        })?;
+        let mut safe_url = Self::from_url(joined.as_str())?;
+
+        let is_relative = !reference.starts_with("//") && !has_url_scheme(reference);
+        if is_relative && safe_url.content_version().is_none() {
+            safe_url.set_content_version(self.content_version());
+        }
+
+        Ok(safe_url)
+    }
+
     /// Validates that a SafeUrl instance can be parsed correctly.
     ///
     /// SafeUrl::from_url() performs rigorous validation,
@@ -1188,6 +1339,64 @@ impl SafeUrl {
     }
 }
 
+// Checks if a URL reference starts with a scheme, i.e. it's an absolute URL
+fn has_url_scheme(reference: &str) -> bool {
+    match reference.find(':') {
+        Some(pos) => {
+            let scheme = &reference[..pos];
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }
+        None => false,
+    }
+}
+
+// Checksum appended to the bytes of XOR-URLs encoded with version 2 or above
+fn xorurl_checksum(bytes: &[u8]) -> [u8; XOR_URL_CHECKSUM_LEN] {
+    let mut hasher = Sha3::v256();
+    let mut hash = [0; 32];
+    hasher.update(bytes);
+    hasher.finalize(&mut hash);
+    let mut checksum = [0; XOR_URL_CHECKSUM_LEN];
+    checksum.copy_from_slice(&hash[..XOR_URL_CHECKSUM_LEN]);
+    checksum
+}
+
+// Find the position of a single mistyped character in an encoded XOR-URL name,
+// by looking for the one substitution which makes its checksum valid
+fn locate_xorurl_typo(encoded_name: &str) -> Option<usize> {
+    let chars: Vec<char> = encoded_name.chars().collect();
+    let candidates: Vec<char> = ('0'..='9')
+        .chain('a'..='z')
+        .chain('A'..='Z')
+        .chain("+/-_".chars())
+        .collect();
+
+    let is_valid = |name: &str| match base_decode(name) {
+        Ok((_, bytes)) => {
+            bytes.len() > XOR_URL_CHECKSUM_LEN && bytes[0] == XOR_URL_VERSION_2 as u8 && {
+                let (content, checksum) = bytes.split_at(bytes.len() - XOR_URL_CHECKSUM_LEN);
+                checksum == xorurl_checksum(content)
+            }
+        }
+        Err(_) => false,
+    };
+
+    // the first char is the multibase prefix
+    (1..chars.len()).find(|index| {
+        candidates
+            .iter()
+            .filter(|candidate| **candidate != chars[*index])
+            .any(|candidate| {
+                let mut fixed = chars.clone();
+                fixed[*index] = *candidate;
+                is_valid(&fixed.iter().collect::<String>())
+            })
+    })
+}
+
 impl fmt::Display for SafeUrl {
     /// serializes the URL to a string.
     ///
@@ -1328,7 +1537,7 @@ mod tests {
             XorUrlBase::Base32,
         )?;
         let base32_xorurl =
-            "safe://baeaaaajrgiztinjwg44dsmbrgiztinjwg44dsmbrgiztinjwg44dsmbrgktdepcnjiza";
+            "safe://baiaaaajrgiztinjwg44dsmbrgiztinjwg44dsmbrgiztinjwg44dsmbrgktdepcnjizax4swcm";
         assert_eq!(xorurl, base32_xorurl);
         Ok(())
     }
@@ -1337,7 +1546,8 @@ mod tests {
     fn test_safeurl_base32z_encoding() -> Result<()> {
         let xor_name = XorName(*b"12345678901234567890123456789012");
         let xorurl = SafeUrl::encode_blob(xor_name, SafeContentType::Raw, XorUrlBase::Base32z)?;
-        let base32z_xorurl = "safe://hyryyyyjtge3uepjsghhd1cbtge3uepjsghhd1cbtge3uepjsghhd1cbtge";
+        let base32z_xorurl =
+            "safe://hyeyyyyjtge3uepjsghhd1cbtge3uepjsghhd1cbtge3uepjsghhd1cbtge61gkab";
         assert_eq!(xorurl, base32z_xorurl);
         Ok(())
     }
@@ -1352,12 +1562,12 @@ mod tests {
             XorUrlBase::Base64,
             false,
         )?;
-        let base64_xorurl = "safe://mAQACAzEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDEyRfRh";
+        let base64_xorurl = "safe://mAgACAzEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDEyRfRhQS2mUw";
         assert_eq!(xorurl, base64_xorurl);
         let safeurl = SafeUrl::from_url(&base64_xorurl)?;
         assert_eq!(base64_xorurl, safeurl.to_base(XorUrlBase::Base64));
         assert_eq!("", safeurl.path());
-        assert_eq!(XOR_URL_VERSION_1, safeurl.encoding_version());
+        assert_eq!(XOR_URL_VERSION_2, safeurl.encoding_version());
         assert_eq!(xor_name, safeurl.xorname());
         assert_eq!(4_584_545, safeurl.type_tag());
         assert_eq!(SafeDataType::PublicSequence, safeurl.data_type());
@@ -1365,15 +1575,94 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_safeurl_other_base_encodings() -> Result<()> {
+        let xor_name = XorName(*b"12345678901234567890123456789012");
+        let xorurl = SafeUrl::encode_sequence_data(
+            xor_name,
+            4_584_545,
+            SafeContentType::FilesContainer,
+            XorUrlBase::Base32z,
+            false,
+        )?;
+        let safeurl = SafeUrl::from_url(&xorurl)?;
+
+        for (base, prefix) in vec![
+            (XorUrlBase::Base58Btc, "safe://z"),
+            (XorUrlBase::Base36, "safe://k"),
+            (XorUrlBase::Base16, "safe://f"),
+            (XorUrlBase::Base64Url, "safe://u"),
+        ] {
+            let converted = safeurl.to_base(base);
+            assert!(converted.starts_with(prefix));
+            let decoded = SafeUrl::from_url(&converted)?;
+            assert_eq!(decoded, safeurl);
+            assert_eq!(decoded.to_base(XorUrlBase::Base32z), xorurl);
+        }
+
+        assert_eq!(XorUrlBase::from_u8(3)?.to_string(), "Base58Btc");
+        assert_eq!("base36".parse::<XorUrlBase>()?.to_string(), "Base36");
+        Ok(())
+    }
+
     #[test]
     fn test_safeurl_default_base_encoding() -> Result<()> {
         let xor_name = XorName(*b"12345678901234567890123456789012");
-        let base32z_xorurl = "safe://hyryyyyjtge3uepjsghhd1cbtge3uepjsghhd1cbtge3uepjsghhd1cbtge";
+        let base32z_xorurl =
+            "safe://hyeyyyyjtge3uepjsghhd1cbtge3uepjsghhd1cbtge3uepjsghhd1cbtge61gkab";
         let xorurl = SafeUrl::encode_blob(xor_name, SafeContentType::Raw, DEFAULT_XORURL_BASE)?;
         assert_eq!(xorurl, base32z_xorurl);
         Ok(())
     }
 
+    #[test]
+    fn test_safeurl_version_1_decoding() -> Result<()> {
+        let xor_name = XorName(*b"12345678901234567890123456789012");
+        let v1_xorurls = vec![
+            (
+                "safe://baeaaaajrgiztinjwg44dsmbrgiztinjwg44dsmbrgiztinjwg44dsmbrgktdepcnjiza",
+                XorUrlBase::Base32,
+                0xa632_3c4d_4a32,
+            ),
+            (
+                "safe://hyryyyyjtge3uepjsghhd1cbtge3uepjsghhd1cbtge3uepjsghhd1cbtge",
+                XorUrlBase::Base32z,
+                0,
+            ),
+        ];
+
+        for (xorurl, base, type_tag) in v1_xorurls {
+            let safeurl = SafeUrl::from_url(xorurl)?;
+            assert_eq!(XOR_URL_VERSION_1, safeurl.encoding_version());
+            assert_eq!(xor_name, safeurl.xorname());
+            assert_eq!(type_tag, safeurl.type_tag());
+            assert_eq!(SafeDataType::PublicBlob, safeurl.data_type());
+            // it's encoded back with the same version
+            assert_eq!(xorurl, safeurl.to_base(base));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_safeurl_checksum_typo() -> Result<()> {
+        let xorurl = "safe://hyeyyyyjtge3uepjsghhd1cbtge3uepjsghhd1cbtge3uepjsghhd1cbtge61gkab";
+        let mistyped = "safe://hyeyyyyjtge3uxpjsghhd1cbtge3uepjsghhd1cbtge3uepjsghhd1cbtge61gkab";
+        assert!(SafeUrl::from_xorurl(xorurl).is_ok());
+
+        // it shouldn't fall back to be parsed as an NRS-URL either
+        match SafeUrl::from_url(mistyped) {
+            Ok(_) => Err(anyhow!("Unexpectedly parsed a mistyped xorurl".to_string())),
+            Err(Error::InvalidXorUrl(msg)) => {
+                assert_eq!(msg, "Invalid XOR-URL checksum, likely typo at position 21");
+                Ok(())
+            }
+            other => Err(anyhow!(
+                "Error returned is not the expected one: {:?}",
+                other
+            )),
+        }
+    }
+
     #[test]
     fn test_safeurl_decoding() -> Result<()> {
         let xor_name = XorName(*b"12345678901234567890123456789012");
@@ -1399,7 +1688,7 @@ mod tests {
         let safeurl = SafeUrl::from_url(&xorurl)?;
 
         assert_eq!(subdirs, safeurl.path());
-        assert_eq!(XOR_URL_VERSION_1, safeurl.encoding_version());
+        assert_eq!(XOR_URL_VERSION_2, safeurl.encoding_version());
         assert_eq!(xor_name, safeurl.xorname());
         assert_eq!(type_tag, safeurl.type_tag());
         assert_eq!(SafeDataType::PublicBlob, safeurl.data_type());
@@ -1429,7 +1718,7 @@ mod tests {
             safeurl_with_path.to_base(XorUrlBase::Base32z)
         );
         assert_eq!("/subfolder/file", safeurl_with_path.path());
-        assert_eq!(XOR_URL_VERSION_1, safeurl_with_path.encoding_version());
+        assert_eq!(XOR_URL_VERSION_2, safeurl_with_path.encoding_version());
         assert_eq!(xor_name, safeurl_with_path.xorname());
         assert_eq!(type_tag, safeurl_with_path.type_tag());
         assert_eq!(SafeDataType::PublicSequence, safeurl_with_path.data_type());
@@ -1462,7 +1751,7 @@ mod tests {
             safeurl_with_subname.to_base(XorUrlBase::Base32z)
         );
         assert_eq!("", safeurl_with_subname.path());
-        assert_eq!(1, safeurl_with_subname.encoding_version());
+        assert_eq!(2, safeurl_with_subname.encoding_version());
         assert_eq!(xor_name, safeurl_with_subname.xorname());
         assert_eq!(type_tag, safeurl_with_subname.type_tag());
         assert_eq!(&["sub"], safeurl_with_subname.sub_names_vec());
@@ -1519,7 +1808,6 @@ mod tests {
             XorUrlBase::Base32z,
         )?;
 
-        // TODO: we need to add checksum to be able to detect even 1 single char change
         let len = xorurl.len() - 2;
         match SafeUrl::from_xorurl(&xorurl[..len]) {
             Ok(_) => Err(anyhow!(
@@ -1768,6 +2056,97 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_safeurl_unicode_public_name() -> Result<()> {
+        // precomposed and decomposed forms of 'é' resolve to the same name
+        let composed = SafeUrl::from_url("safe://caf\u{00E9}/index.html")?;
+        let decomposed = SafeUrl::from_url("safe://cafe\u{0301}/index.html")?;
+
+        assert_eq!(composed.xorname(), decomposed.xorname());
+        assert_eq!(composed.public_name(), "xn--caf-dma");
+        assert_eq!(composed.public_name_unicode(), "caf\u{00E9}");
+        assert_eq!(composed.to_string(), "safe://xn--caf-dma/index.html");
+        assert_eq!(composed.homograph_risk(), None);
+
+        // 'paypal' with a Cyrillic 'а'
+        let spoofed = SafeUrl::from_url("safe://p\u{0430}ypal")?;
+        assert!(matches!(
+            spoofed.homograph_risk(),
+            Some(HomographRisk::MixedScript { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_safeurl_join_nrsurl() -> Result<()> {
+        let base = SafeUrl::from_url("safe://sub.mysite/docs/guide/index.html?v=3#top")?;
+
+        let references = vec![
+            ("intro.html", "safe://sub.mysite/docs/guide/intro.html?v=3"),
+            (
+                "./intro.html",
+                "safe://sub.mysite/docs/guide/intro.html?v=3",
+            ),
+            ("../img/logo.png", "safe://sub.mysite/docs/img/logo.png?v=3"),
+            ("../../../../up.html", "safe://sub.mysite/up.html?v=3"),
+            ("/about.html", "safe://sub.mysite/about.html?v=3"),
+            (
+                "?lang=en",
+                "safe://sub.mysite/docs/guide/index.html?lang=en&v=3",
+            ),
+            (
+                "#install",
+                "safe://sub.mysite/docs/guide/index.html?v=3#install",
+            ),
+            (
+                "page.html?v=1",
+                "safe://sub.mysite/docs/guide/page.html?v=1",
+            ),
+            ("safe://othersite/page.html", "safe://othersite/page.html"),
+            ("//othersite/page.html", "safe://othersite/page.html"),
+        ];
+
+        for (reference, expected) in references {
+            assert_eq!(base.join(reference)?.to_string(), expected);
+        }
+
+        // references with other schemes can't be resolved
+        assert!(base.join("https://example.com").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_safeurl_join_xorurl() -> Result<()> {
+        let xor_name = XorName(*b"12345678901234567890123456789012");
+        let xorurl = SafeUrl::encode(
+            xor_name,
+            None,
+            1_100,
+            SafeDataType::PublicSequence,
+            SafeContentType::FilesContainer,
+            Some("/dir/file.html"),
+            None,
+            None,
+            None,
+            Some(2),
+            XorUrlBase::Base32z,
+        )?;
+        let base = SafeUrl::from_url(&xorurl)?;
+
+        let joined = base.join("../other dir/other.html")?;
+        assert!(joined.is_xorurl());
+        assert_eq!(joined.xorname(), xor_name);
+        assert_eq!(joined.type_tag(), 1_100);
+        assert_eq!(joined.content_type(), SafeContentType::FilesContainer);
+        assert_eq!(joined.path(), "/other%20dir/other.html");
+        assert_eq!(joined.content_version(), Some(2));
+
+        let joined = base.join("/index.html?v=0")?;
+        assert_eq!(joined.path(), "/index.html");
+        assert_eq!(joined.content_version(), Some(0));
+        Ok(())
+    }
+
     #[test]
     fn test_safeurl_from_url_validation() -> Result<()> {
         // Tests basic URL syntax errors that are common to
@@ -9,6 +9,8 @@
 
 use crate::{Error, Result};
 use log::debug;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, fmt};
 use uhttp_uri::HttpUri;
 use url::Url;
 
@@ -61,6 +63,67 @@ const INVALID_NRS_CHARS: [char; 30] = [
     '\u{206F}', // nominal digit shapes
 ];
 
+// Prefix of labels which are encoded in their ASCII (punycode) form
+const IDNA_ACE_PREFIX: &str = "xn--";
+
+// Letters from other scripts which are rendered just like a Latin letter.
+// A label made only of these can be visually mistaken for a Latin one.
+const LATIN_CONFUSABLES: [char; 24] = [
+    // Cyrillic
+    '\u{0430}', // а
+    '\u{0441}', // с
+    '\u{0435}', // е
+    '\u{04BB}', // һ
+    '\u{0456}', // і
+    '\u{0458}', // ј
+    '\u{04CF}', // ӏ
+    '\u{043E}', // о
+    '\u{0440}', // р
+    '\u{051B}', // ԛ
+    '\u{0455}', // ѕ
+    '\u{051D}', // ԝ
+    '\u{0445}', // х
+    '\u{0443}', // у
+    '\u{0501}', // ԁ
+    '\u{0475}', // ѵ
+    // Greek
+    '\u{03BF}', // ο
+    '\u{03B9}', // ι
+    '\u{03BA}', // κ
+    '\u{03BD}', // ν
+    '\u{03C1}', // ρ
+    '\u{03C5}', // υ
+    '\u{03B1}', // α
+    '\u{03F3}', // ϳ
+];
+
+/// Risk of a public name being visually mistaken for a different one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HomographRisk {
+    /// A label mixes letters from several scripts, e.g. Latin and Cyrillic
+    MixedScript { label: String, scripts: Vec<String> },
+    /// A label is made only of letters which look like Latin letters
+    WholeScriptConfusable { label: String, script: String },
+}
+
+impl fmt::Display for HomographRisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MixedScript { label, scripts } => write!(
+                f,
+                "the label '{}' mixes characters from different scripts ({})",
+                label,
+                scripts.join(", ")
+            ),
+            Self::WholeScriptConfusable { label, script } => write!(
+                f,
+                "the label '{}' is made only of {} characters which look like Latin characters",
+                label, script
+            ),
+        }
+    }
+}
+
 // A simple struct to represent the basic components parsed
 // from a Safe URL without any decoding.
 //
@@ -104,7 +167,7 @@ impl SafeUrlParts {
 
         // validate name (url host) is not empty
         let public_name = match parsing_url.host_str() {
-            Some(h) => h.to_string(),
+            Some(h) => normalise_public_name(h)?,
             None => {
                 let msg = format!("Problem parsing the URL \"{}\": {}", url, "missing name");
                 return Err(Error::InvalidXorUrl(msg));
@@ -203,6 +266,119 @@ impl SafeUrlParts {
     }
 }
 
+// Normalises each label of a public name. Labels with non-ASCII characters
+// are mapped as per IDNA (UTS #46, which includes Unicode NFC normalisation)
+// and converted into their ASCII (punycode) form, so the different ways
+// of writing the same name all end up hashing to the same XorName.
+// ASCII labels are kept untouched, thus names already in use don't change.
+fn normalise_public_name(host: &str) -> Result<String> {
+    let labels = host
+        .split('.')
+        .map(|label| {
+            // rust-url percent-encodes the non-ASCII characters of the host
+            let decoded = match urlencoding::decode(label) {
+                Ok(decoded) if !decoded.is_ascii() && !decoded.contains('.') => decoded,
+                _ => return Ok(label.to_string()),
+            };
+
+            idna::Config::default()
+                .check_hyphens(false)
+                .to_ascii(&decoded)
+                .map_err(|err| {
+                    Error::InvalidInput(format!(
+                        "The name '{}' is not a valid internationalised name: {:?}",
+                        decoded, err
+                    ))
+                })
+        })
+        .collect::<Result<Vec<String>>>()?;
+
+    Ok(labels.join("."))
+}
+
+// Returns the Unicode form of a public name, decoding its punycode labels
+pub(crate) fn public_name_to_unicode(public_name: &str) -> String {
+    if !public_name.contains(IDNA_ACE_PREFIX) {
+        return public_name.to_string();
+    }
+
+    public_name
+        .split('.')
+        .map(|label| {
+            if label.starts_with(IDNA_ACE_PREFIX) {
+                let (unicode, result) = idna::domain_to_unicode(label);
+                if result.is_ok() {
+                    return unicode;
+                }
+            }
+            label.to_string()
+        })
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+// Detects if any of the labels of a public name (in its Unicode form)
+// could be visually mistaken for a different name.
+pub(crate) fn detect_homograph(unicode_name: &str) -> Option<HomographRisk> {
+    for label in unicode_name.split('.') {
+        let scripts: BTreeSet<&str> = label.chars().filter_map(char_script).collect();
+        if scripts.len() > 1 && !is_allowed_scripts_mix(&scripts) {
+            return Some(HomographRisk::MixedScript {
+                label: label.to_string(),
+                scripts: scripts.iter().map(|s| s.to_string()).collect(),
+            });
+        }
+
+        if let Some(script) = scripts.iter().next() {
+            let is_confusable =
+                |c: char| LATIN_CONFUSABLES.contains(&c) || char_script(c).is_none();
+            if (*script == "Cyrillic" || *script == "Greek") && label.chars().all(is_confusable) {
+                return Some(HomographRisk::WholeScriptConfusable {
+                    label: label.to_string(),
+                    script: script.to_string(),
+                });
+            }
+        }
+    }
+
+    None
+}
+
+// CJK scripts are commonly mixed together, and with Latin letters
+fn is_allowed_scripts_mix(scripts: &BTreeSet<&str>) -> bool {
+    let allowed_mixes: [&[&str]; 2] = [
+        &["Latin", "Han", "Hiragana/Katakana"],
+        &["Latin", "Han", "Hangul"],
+    ];
+    allowed_mixes
+        .iter()
+        .any(|mix| scripts.iter().all(|script| mix.contains(script)))
+}
+
+// Script of a letter, or None for digits, hyphens, combining marks
+// and any other characters which are common to all scripts.
+fn char_script(c: char) -> Option<&'static str> {
+    match c {
+        'a'..='z' | 'A'..='Z' => Some("Latin"),
+        '\u{00C0}'..='\u{024F}' if c != '\u{00D7}' && c != '\u{00F7}' => Some("Latin"),
+        '\u{1E00}'..='\u{1EFF}' => Some("Latin"),
+        '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Some("Greek"),
+        '\u{0400}'..='\u{052F}' => Some("Cyrillic"),
+        '\u{0530}'..='\u{058F}' => Some("Armenian"),
+        '\u{0590}'..='\u{05FF}' => Some("Hebrew"),
+        '\u{0600}'..='\u{06FF}' | '\u{0750}'..='\u{077F}' => Some("Arabic"),
+        '\u{0900}'..='\u{097F}' => Some("Devanagari"),
+        '\u{0E00}'..='\u{0E7F}' => Some("Thai"),
+        '\u{10A0}'..='\u{10FF}' => Some("Georgian"),
+        '\u{1100}'..='\u{11FF}' | '\u{3130}'..='\u{318F}' | '\u{AC00}'..='\u{D7AF}' => {
+            Some("Hangul")
+        }
+        '\u{3040}'..='\u{30FF}' => Some("Hiragana/Katakana"),
+        '\u{3400}'..='\u{4DBF}' | '\u{4E00}'..='\u{9FFF}' => Some("Han"),
+        _ => None,
+    }
+}
+
 fn validate_url_chars(url: &str) -> Result<()> {
     // validate no whitespace in url
     if url.contains(char::is_whitespace) {
@@ -227,6 +403,56 @@ mod tests {
     use super::*;
     use anyhow::{anyhow, Result};
 
+    #[test]
+    fn test_safeurl_parts_unicode_names_normalisation() -> Result<()> {
+        // precomposed and decomposed forms of 'é'
+        let composed = SafeUrlParts::parse("safe://sub.caf\u{00E9}", false)?;
+        let decomposed = SafeUrlParts::parse("safe://sub.cafe\u{0301}", false)?;
+        let punycode = SafeUrlParts::parse("safe://sub.xn--caf-dma", false)?;
+
+        assert_eq!(composed.public_name, "sub.xn--caf-dma");
+        assert_eq!(composed.top_name, "xn--caf-dma");
+        assert_eq!(composed.sub_names, "sub");
+        assert_eq!(decomposed.public_name, composed.public_name);
+        assert_eq!(punycode.public_name, composed.public_name);
+        assert_eq!(
+            public_name_to_unicode(&composed.public_name),
+            "sub.caf\u{00E9}"
+        );
+
+        // ASCII names are kept as they are
+        let ascii = SafeUrlParts::parse("safe://My-Name", false)?;
+        assert_eq!(ascii.public_name, "My-Name");
+        assert_eq!(public_name_to_unicode(&ascii.public_name), "My-Name");
+        Ok(())
+    }
+
+    #[test]
+    fn test_safeurl_parts_detect_homograph() {
+        assert_eq!(detect_homograph("safenetwork"), None);
+        assert_eq!(detect_homograph("caf\u{00E9}.d\u{00E9}j\u{00E0}-vu"), None);
+        assert_eq!(detect_homograph("\u{4E2D}\u{6587}abc"), None);
+        assert_eq!(detect_homograph("\u{043C}\u{0438}\u{0440}"), None);
+
+        // 'p' and 'a' written with Cyrillic letters
+        assert_eq!(
+            detect_homograph("\u{0440}\u{0430}y\u{0440}al"),
+            Some(HomographRisk::MixedScript {
+                label: "\u{0440}\u{0430}y\u{0440}al".to_string(),
+                scripts: vec!["Cyrillic".to_string(), "Latin".to_string()],
+            })
+        );
+
+        // 'ехро' written only with Cyrillic letters
+        assert_eq!(
+            detect_homograph("sub.\u{0435}\u{0445}\u{0440}\u{043E}"),
+            Some(HomographRisk::WholeScriptConfusable {
+                label: "\u{0435}\u{0445}\u{0440}\u{043E}".to_string(),
+                script: "Cyrillic".to_string(),
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_safeurl_validate_url_chars_with_whitespace() -> Result<()> {
         let urls = vec![
@@ -13,8 +13,32 @@ use super::{
 };
 use crate::{Error, Result};
 use log::debug;
+use serde::{Deserialize, Serialize};
+use sn_data_types::{PublicKey, SequenceUser};
 use xor_name::XorName;
 
+/// The permissions granted to a user of a Sequence
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceUserPermissions {
+    /// Hex-encoded public key of the user, or 'Anyone' for permissions granted to all users
+    pub user: String,
+    /// Whether the user can read the Sequence's entries
+    pub read: bool,
+    /// Whether the user can append entries to the Sequence
+    pub append: bool,
+}
+
+/// The owner of a Sequence and the permissions granted to its users
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequencePermissions {
+    /// Hex-encoded public key of the Sequence's owner
+    pub owner: String,
+    /// Whether it's a Private Sequence
+    pub private: bool,
+    /// The permissions of each user
+    pub users: Vec<SequenceUserPermissions>,
+}
+
 impl Safe {
     /// Create a Public Sequence on the network
     ///
@@ -36,10 +60,27 @@ impl Safe {
         name: Option<XorName>,
         type_tag: u64,
         private: bool,
+    ) -> Result<XorUrl> {
+        self.sequence_create_with_writers(data, name, type_tag, private, &[])
+            .await
+    }
+
+    /// Create a Sequence on the network which other users can also append to
+    ///
+    /// Apart from the owner, each of the writers is granted permission to append to the
+    /// Sequence, and to read it if it's Private. Note that permissions can only be set when
+    /// the Sequence is created, they cannot be changed afterwards.
+    pub async fn sequence_create_with_writers(
+        &mut self,
+        data: &[u8],
+        name: Option<XorName>,
+        type_tag: u64,
+        private: bool,
+        writers: &[PublicKey],
     ) -> Result<XorUrl> {
         let xorname = self
             .safe_client
-            .store_sequence(data, name, type_tag, None, private)
+            .store_sequence(data, name, type_tag, writers, private)
             .await?;
 
         SafeUrl::encode_sequence_data(
@@ -114,6 +155,147 @@ impl Safe {
         }
     }
 
+    /// Get a range of entries from a Sequence on the network
+    ///
+    /// The entries with versions from `from` (inclusive) up to `to` (exclusive) are returned,
+    /// or up to the latest entry if `to` is not provided. The range is truncated at the latest
+    /// entry, thus a range beyond the end of the Sequence results in no entries being returned.
+    ///
+    /// ## Example
+    /// ```
+    /// # use sn_api::Safe;
+    /// # let mut safe = Safe::default();
+    /// # async_std::task::block_on(async {
+    /// #   safe.connect("", Some("fake-credentials")).await.unwrap();
+    ///     let xorurl = safe.sequence_create(b"First", None, 20_000, false).await.unwrap();
+    ///     safe.append_to_sequence(&xorurl, b"Second").await.unwrap();
+    ///     safe.append_to_sequence(&xorurl, b"Third").await.unwrap();
+    ///     let entries = safe.sequence_get_range(&xorurl, 1, None).await.unwrap();
+    ///     assert_eq!(entries, vec![(1, b"Second".to_vec()), (2, b"Third".to_vec())]);
+    /// # });
+    /// ```
+    pub async fn sequence_get_range(
+        &mut self,
+        url: &str,
+        from: u64,
+        to: Option<u64>,
+    ) -> Result<Vec<(u64, Vec<u8>)>> {
+        debug!(
+            "Getting range [{}, {:?}) of Sequence data from: {:?}",
+            from, to, url
+        );
+        let safe_url = Safe::parse_url(url)?;
+        if safe_url.content_version().is_some() {
+            return Err(Error::InvalidInput(format!(
+                "The target URL cannot contain a version when reading a range of entries: {}",
+                url
+            )));
+        };
+        if let Some(to) = to {
+            if to < from {
+                return Err(Error::InvalidInput(format!(
+                    "Invalid range of versions, the end ({}) is lower than the start ({})",
+                    to, from
+                )));
+            }
+        }
+
+        let (safe_url, _) = self.parse_and_resolve_url(url).await?;
+        let last_version = match self.fetch_sequence(&safe_url).await {
+            Ok((version, _)) => version,
+            Err(Error::EmptyContent(_)) => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+
+        let end = match to {
+            Some(to) if to <= last_version => to,
+            _ => last_version + 1,
+        };
+        if from >= end {
+            return Ok(vec![]);
+        }
+
+        let entries = self
+            .safe_client
+            .sequence_get_range(
+                safe_url.xorname(),
+                safe_url.type_tag(),
+                from,
+                end,
+                safe_url.data_type() == SafeDataType::PrivateSequence,
+            )
+            .await?;
+
+        Ok((from..).zip(entries).collect())
+    }
+
+    /// Get the latest entry of a Sequence, always fetching it from the network
+    ///
+    /// Unlike `sequence_get`, any cached copy of the latest entry is ignored, so it can be used
+    /// to poll a Sequence for new entries being appended.
+    pub async fn sequence_get_latest(&mut self, url: &str) -> Result<(u64, Vec<u8>)> {
+        let safe_url = Safe::parse_url(url)?;
+        if safe_url.content_version().is_some() {
+            return Err(Error::InvalidInput(format!(
+                "The target URL cannot contain a version: {}",
+                url
+            )));
+        };
+
+        let (safe_url, _) = self.parse_and_resolve_url(url).await?;
+        self.safe_client
+            .forget_sequence_last_entry(
+                safe_url.xorname(),
+                safe_url.type_tag(),
+                safe_url.data_type() == SafeDataType::PrivateSequence,
+            )
+            .await;
+
+        self.fetch_sequence(&safe_url).await
+    }
+
+    /// Get the owner of a Sequence and the permissions granted to its users
+    ///
+    /// The URL is not resolved, thus the permissions of the Sequence it targets are returned,
+    /// which can also be a FilesContainer, or the NRS Map Container of an NRS-URL.
+    pub async fn sequence_get_permissions(&self, url: &str) -> Result<SequencePermissions> {
+        debug!("Getting permissions of Sequence at: {:?}", url);
+        let safe_url = Safe::parse_url(url)?;
+        let private = match safe_url.data_type() {
+            SafeDataType::PublicSequence => false,
+            SafeDataType::PrivateSequence => true,
+            other => {
+                return Err(Error::InvalidInput(format!(
+                    "The URL doesn't target a Sequence but a {}: {}",
+                    other, url
+                )))
+            }
+        };
+
+        let (owner, users) = self
+            .safe_client
+            .sequence_get_policy(safe_url.xorname(), safe_url.type_tag(), private)
+            .await?;
+
+        let users = users
+            .into_iter()
+            .map(|(user, read, append)| SequenceUserPermissions {
+                user: match user {
+                    SequenceUser::Anyone => "Anyone".to_string(),
+                    SequenceUser::Key(pk) => hex::encode(pk.to_bytes()),
+                },
+                read,
+                append,
+            })
+            .collect();
+
+        Ok(SequencePermissions {
+            owner: hex::encode(owner.to_bytes()),
+            private,
+            users,
+        })
+    }
+
     /// Append data to a Public Sequence on the network
     ///
     /// ## Example
@@ -203,6 +385,65 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_sequence_get_range() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.sequence_create(b"v0", None, 25_000, false).await?;
+        let _ = retry_loop!(safe.sequence_get(&xorurl));
+        safe.append_to_sequence(&xorurl, b"v1").await?;
+        safe.append_to_sequence(&xorurl, b"v2").await?;
+        safe.append_to_sequence(&xorurl, b"v3").await?;
+
+        let entries = safe.sequence_get_range(&xorurl, 0, None).await?;
+        assert_eq!(
+            entries,
+            vec![
+                (0, b"v0".to_vec()),
+                (1, b"v1".to_vec()),
+                (2, b"v2".to_vec()),
+                (3, b"v3".to_vec())
+            ]
+        );
+
+        let entries = safe.sequence_get_range(&xorurl, 1, Some(3)).await?;
+        assert_eq!(entries, vec![(1, b"v1".to_vec()), (2, b"v2".to_vec())]);
+
+        // ranges are truncated at the latest entry
+        let entries = safe.sequence_get_range(&xorurl, 3, Some(10)).await?;
+        assert_eq!(entries, vec![(3, b"v3".to_vec())]);
+        assert!(safe.sequence_get_range(&xorurl, 4, None).await?.is_empty());
+
+        assert!(safe.sequence_get_range(&xorurl, 2, Some(1)).await.is_err());
+        assert!(safe
+            .sequence_get_range(&format!("{}?v=1", xorurl), 0, None)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sequence_permissions() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let owner = hex::encode(safe.get_my_keypair().await?.public_key().to_bytes());
+        let writer = safe.generate_random_ed_keypair().public_key();
+
+        let xorurl = safe
+            .sequence_create_with_writers(b"shared", None, 25_000, false, &[writer])
+            .await?;
+        let _ = retry_loop!(safe.sequence_get(&xorurl));
+
+        let perms = safe.sequence_get_permissions(&xorurl).await?;
+        assert_eq!(perms.owner, owner);
+        assert!(!perms.private);
+        assert_eq!(perms.users.len(), 2);
+        assert!(perms.users.iter().all(|user| user.read && user.append));
+        assert!(perms
+            .users
+            .iter()
+            .any(|user| user.user == hex::encode(writer.to_bytes())));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_sequence_read_from_second_client() -> Result<()> {
         let mut client1 = new_safe_instance().await?;
@@ -0,0 +1,489 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    gen_wallet_spendable_balances_list, owner_secret_key, resolve_transfer_destination,
+    resolve_wallet_url, sk_envelope::open_sk, transfer_to_destination, TransferDestination,
+};
+use crate::{
+    api::{
+        app::{
+            helpers::parse_coins_amount,
+            safeurl::{SafeContentType, SafeUrl},
+        },
+        common::ed_sk_from_hex,
+    },
+    Error, Result, Safe,
+};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use sn_data_types::{Keypair, Token};
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+/// Strategy used to select the spendable balances of a Wallet a transfer is funded from
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoinSelection {
+    /// Spend from the spendable balances with the smallest amount of safecoins first
+    SmallestFirst,
+    /// Spend from the spendable balances with the largest amount of safecoins first
+    LargestFirst,
+    /// Spend from the spendable balances with the given friendly names, in the order given
+    Explicit(Vec<String>),
+}
+
+impl fmt::Display for CoinSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::SmallestFirst => write!(f, "smallest-first"),
+            Self::LargestFirst => write!(f, "largest-first"),
+            Self::Explicit(names) => write!(f, "{}", names.join(",")),
+        }
+    }
+}
+
+impl FromStr for CoinSelection {
+    type Err = Error;
+    fn from_str(str: &str) -> Result<Self> {
+        match str {
+            "smallest-first" => Ok(Self::SmallestFirst),
+            "largest-first" => Ok(Self::LargestFirst),
+            other => Err(Error::InvalidInput(format!(
+                "Invalid coin selection strategy: '{}'. Supported values are 'smallest-first' and 'largest-first'",
+                other
+            ))),
+        }
+    }
+}
+
+/// One of the underlying transfers a multi-source transfer is made of
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WalletTransferLeg {
+    /// Friendly name of the spendable balance, in the source Wallet, the leg relates to
+    pub balance_name: String,
+    /// XOR-URL of the SafeKey of the spendable balance
+    pub balance_xorurl: String,
+    /// Amount of safecoins transferred in this leg
+    pub amount: String,
+    /// Transfer id, or None if the leg was not performed
+    pub tx_id: Option<u64>,
+}
+
+/// Consolidated report of a transfer funded from several spendable balances of a Wallet
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct WalletTransferReport {
+    /// Legs performed, in the order they were made
+    pub legs: Vec<WalletTransferLeg>,
+    /// Error which made one of the legs fail, if any. The legs which weren't performed are not listed
+    pub error: Option<String>,
+    /// Compensating transfers, from the destination back to each spendable balance, which revert
+    /// the legs performed when one of them failed. Those with no transfer id were not performed
+    /// and need to be made by the owner of the destination.
+    pub rollback: Vec<WalletTransferLeg>,
+}
+
+impl WalletTransferReport {
+    /// Returns true if the whole amount was transferred
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+// A spendable balance selected to fund a transfer, with the amount to take from it
+struct SelectedBalance {
+    name: String,
+    xorurl: String,
+    keypair: Keypair,
+    amount: Token,
+}
+
+impl Safe {
+    /// # Transfer safecoins from several spendable balances of a Wallet
+    ///
+    /// The amount is funded from as many spendable balances of the source Wallet as needed,
+    /// which are selected according to the given strategy, with one underlying transfer per
+    /// spendable balance used. If any of them fails, the transfers already made are reverted
+    /// on a best-effort basis, which is only possible when the destination is a Wallet owned
+    /// by the current credentials, the report returned details the compensating transfers.
+    pub async fn wallet_transfer_with_selection(
+        &mut self,
+        amount: &str,
+        from_wallet_url: &str,
+        to: &str,
+        selection: CoinSelection,
+    ) -> Result<WalletTransferReport> {
+        let amount_coins = parse_coins_amount(amount)?;
+
+        let (from_safeurl, _) =
+            self.parse_and_resolve_url(&from_wallet_url)
+                .await
+                .map_err(|_| {
+                    Error::InvalidInput(format!(
+                        "Failed to parse the 'from' URL: {}",
+                        from_wallet_url
+                    ))
+                })?;
+
+        if from_safeurl.content_type() != SafeContentType::Wallet {
+            return Err(Error::InvalidInput(format!(
+                "The 'from_url' URL doesn't target a Wallet, it is: {:?} ({})",
+                from_safeurl.content_type(),
+                from_safeurl.data_type()
+            )));
+        }
+
+        let selected = select_balances(self, &from_safeurl, amount_coins, &selection).await?;
+        let destination = resolve_transfer_destination(self, to).await?;
+
+        let mut report = WalletTransferReport::default();
+        for balance in selected.iter() {
+            debug!(
+                "Transferring {} from spendable balance '{}'",
+                balance.amount, balance.name
+            );
+            match transfer_to_destination(
+                self,
+                balance.keypair.clone(),
+                &destination,
+                balance.amount,
+            )
+            .await
+            {
+                Ok(tx_id) => report.legs.push(WalletTransferLeg {
+                    balance_name: balance.name.clone(),
+                    balance_xorurl: balance.xorurl.clone(),
+                    amount: balance.amount.to_string(),
+                    tx_id: Some(tx_id),
+                }),
+                Err(err) => {
+                    warn!(
+                        "Transfer from spendable balance '{}' failed, rolling back: {}",
+                        balance.name, err
+                    );
+                    report.error = Some(format!(
+                        "Transfer from spendable balance '{}' failed: {}",
+                        balance.name, err
+                    ));
+                    report.rollback = rollback_legs(self, to, &destination, &report.legs).await;
+                    break;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+// Private helper to select the spendable balances, and the amount to take from each,
+// which fund a transfer of the given amount
+async fn select_balances(
+    safe: &Safe,
+    wallet_safeurl: &SafeUrl,
+    amount: Token,
+    selection: &CoinSelection,
+) -> Result<Vec<SelectedBalance>> {
+    let wallet_url = wallet_safeurl.to_string();
+    let balances = gen_wallet_spendable_balances_list(
+        safe,
+        wallet_safeurl.xorname(),
+        wallet_safeurl.type_tag(),
+        &wallet_url,
+    )
+    .await?;
+
+    let names: Vec<String> = match selection {
+        CoinSelection::Explicit(names) => {
+            if let Some(name) = names.iter().find(|name| !balances.contains_key(*name)) {
+                return Err(Error::InvalidInput(format!(
+                    "No spendable balance named '{}' found in Wallet: '{}'",
+                    name, wallet_url
+                )));
+            }
+            let mut seen = BTreeSet::new();
+            if let Some(name) = names.iter().find(|name| !seen.insert(name.as_str())) {
+                return Err(Error::InvalidInput(format!(
+                    "The spendable balance '{}' was provided more than once",
+                    name
+                )));
+            }
+            if let Some(name) = names.iter().find(|name| balances[*name].1.is_watch_only()) {
+                return Err(Error::InvalidInput(format!(
+                    "Cannot spend from the watch-only balance '{}' of Wallet: '{}'",
+                    name, wallet_url
+                )));
+            }
+            names.clone()
+        }
+        _ => balances
+            .iter()
+            .filter(|(_, (_, balance))| !balance.is_watch_only())
+            .map(|(name, _)| name.clone())
+            .collect(),
+    };
+
+    let owner_sk = owner_secret_key(safe).await?;
+    let mut candidates = vec![];
+    for name in names {
+        let (_, balance) = &balances[&name];
+        let sk = open_sk(
+            &owner_sk,
+            &wallet_safeurl.xorname(),
+            &balance.xorurl,
+            &balance.sk,
+        )?;
+        let keypair = Keypair::from(ed_sk_from_hex(&sk)?);
+        let current_balance = safe
+            .safe_client
+            .read_balance_from_keypair(keypair.clone())
+            .await?;
+        debug!("Spendable balance '{}' has {}", name, current_balance);
+        candidates.push((name, balance.xorurl.clone(), keypair, current_balance));
+    }
+
+    match selection {
+        CoinSelection::SmallestFirst => candidates.sort_by_key(|(_, _, _, coins)| coins.as_nano()),
+        CoinSelection::LargestFirst => {
+            candidates.sort_by_key(|(_, _, _, coins)| std::cmp::Reverse(coins.as_nano()))
+        }
+        CoinSelection::Explicit(_) => {}
+    }
+
+    let mut remaining = amount.as_nano();
+    let mut selected = vec![];
+    for (name, xorurl, keypair, coins) in candidates {
+        if remaining == 0 {
+            break;
+        }
+        let take = std::cmp::min(remaining, coins.as_nano());
+        if take == 0 {
+            continue;
+        }
+        remaining -= take;
+        selected.push(SelectedBalance {
+            name,
+            xorurl,
+            keypair,
+            amount: Token::from_nano(take),
+        });
+    }
+
+    if remaining > 0 {
+        return Err(Error::NotEnoughBalance(format!(
+            "Not enough balance for the transfer at Wallet \"{}\", {} safecoins short using coin selection '{}'",
+            wallet_url,
+            Token::from_nano(remaining),
+            selection
+        )));
+    }
+
+    Ok(selected)
+}
+
+// Private helper to revert the legs already performed, which is only possible when
+// the destination is a Wallet the current credentials can spend from. The compensating
+// transfers which couldn't be performed are returned with no transfer id.
+async fn rollback_legs(
+    safe: &mut Safe,
+    to: &str,
+    destination: &TransferDestination,
+    legs: &[WalletTransferLeg],
+) -> Vec<WalletTransferLeg> {
+    let destination_keypair = match destination {
+        TransferDestination::XorName(_, url) => match destination_wallet_keypair(safe, url).await {
+            Ok(keypair) => Some(keypair),
+            Err(err) => {
+                warn!("Cannot roll back transfers made to '{}': {}", to, err);
+                None
+            }
+        },
+        TransferDestination::PublicKey(_) => None,
+    };
+
+    let mut rollback = vec![];
+    for leg in legs.iter().rev() {
+        let mut compensation = leg.clone();
+        compensation.tx_id = None;
+
+        if let Some(keypair) = &destination_keypair {
+            let result = match (
+                SafeUrl::from_url(&leg.balance_xorurl),
+                parse_coins_amount(&leg.amount),
+            ) {
+                (Ok(source), Ok(amount)) => {
+                    safe.safe_client
+                        .safecoin_transfer_to_xorname(
+                            Some(keypair.clone()),
+                            source.xorname(),
+                            amount,
+                        )
+                        .await
+                }
+                (Err(err), _) | (_, Err(err)) => Err(err),
+            };
+            match result {
+                Ok(tx_id) => compensation.tx_id = Some(tx_id),
+                Err(err) => warn!(
+                    "Failed to roll back transfer from spendable balance '{}': {}",
+                    leg.balance_name, err
+                ),
+            }
+        }
+
+        rollback.push(compensation);
+    }
+
+    rollback
+}
+
+// Private helper to obtain the Keypair of the spendable balance a transfer to a Wallet URL was made to
+async fn destination_wallet_keypair(safe: &mut Safe, url: &str) -> Result<Keypair> {
+    let (safeurl, nrs_safeurl) = safe.parse_and_resolve_url(url).await?;
+    if safeurl.content_type() != SafeContentType::Wallet {
+        return Err(Error::InvalidInput(
+            "The destination is not a Wallet".to_string(),
+        ));
+    }
+
+    let balance = resolve_wallet_url(safe, url, safeurl, nrs_safeurl, true).await?;
+    Ok(Keypair::from(ed_sk_from_hex(&balance.sk)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{app::test_helpers::new_safe_instance, common::sk_to_hex},
+        retry_loop, retry_loop_for_pattern,
+    };
+    use anyhow::{anyhow, Result};
+
+    #[test]
+    fn test_coin_selection_from_str() -> Result<()> {
+        assert_eq!(
+            CoinSelection::from_str("smallest-first")?,
+            CoinSelection::SmallestFirst
+        );
+        assert_eq!(
+            CoinSelection::from_str("largest-first")?,
+            CoinSelection::LargestFirst
+        );
+        assert!(CoinSelection::from_str("random").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wallet_transfer_with_selection() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let from_wallet_xorurl = safe.wallet_create().await?;
+        let to_wallet_xorurl = safe.wallet_create().await?;
+
+        let (_, keypair1) = safe.keys_create_preload_test_coins("10").await?;
+        let (_, keypair2) = safe.keys_create_preload_test_coins("20").await?;
+        let (_, keypair3) = safe.keys_create_preload_test_coins("30").await?;
+        let (_, keypair4) = safe.keys_create_preload_test_coins("0").await?;
+        let _ = retry_loop!(safe.fetch(&from_wallet_xorurl, None));
+        let _ = retry_loop!(safe.fetch(&to_wallet_xorurl, None));
+
+        for (name, keypair) in &[
+            ("ten", &keypair1),
+            ("twenty", &keypair2),
+            ("thirty", &keypair3),
+        ] {
+            let sk = sk_to_hex(keypair.secret_key()?);
+            safe.wallet_insert(&from_wallet_xorurl, Some(name), false, &sk)
+                .await?;
+        }
+        let sk4 = sk_to_hex(keypair4.secret_key()?);
+        safe.wallet_insert(&to_wallet_xorurl, Some("to-balance"), true, &sk4)
+            .await?;
+        let _ = retry_loop_for_pattern!(safe.wallet_balance(&from_wallet_xorurl), Ok(balance) if balance == "60.000000000")?;
+
+        // no spendable balance covers the amount on its own
+        let report = safe
+            .wallet_transfer_with_selection(
+                "35",
+                &from_wallet_xorurl,
+                &to_wallet_xorurl,
+                CoinSelection::SmallestFirst,
+            )
+            .await?;
+        assert!(report.is_complete());
+        assert!(report.rollback.is_empty());
+        let legs: Vec<(&str, &str)> = report
+            .legs
+            .iter()
+            .map(|leg| (leg.balance_name.as_str(), leg.amount.as_str()))
+            .collect();
+        assert_eq!(
+            legs,
+            vec![
+                ("ten", "10.000000000"),
+                ("twenty", "20.000000000"),
+                ("thirty", "5.000000000")
+            ]
+        );
+        let _ = retry_loop_for_pattern!(safe.wallet_balance(&to_wallet_xorurl), Ok(balance) if balance == "35.000000000")?;
+
+        // the remaining 25 safecoins are not enough
+        match safe
+            .wallet_transfer_with_selection(
+                "26",
+                &from_wallet_xorurl,
+                &to_wallet_xorurl,
+                CoinSelection::LargestFirst,
+            )
+            .await
+        {
+            Err(Error::NotEnoughBalance(_)) => {}
+            other => return Err(anyhow!("Unexpected result: {:?}", other)),
+        }
+
+        // an explicit list of spendable balances
+        let report = safe
+            .wallet_transfer_with_selection(
+                "25",
+                &from_wallet_xorurl,
+                &to_wallet_xorurl,
+                CoinSelection::Explicit(vec!["thirty".to_string()]),
+            )
+            .await?;
+        assert!(report.is_complete());
+        assert_eq!(report.legs.len(), 1);
+        assert_eq!(report.legs[0].balance_name, "thirty");
+        assert_eq!(report.legs[0].amount, "25.000000000");
+
+        match safe
+            .wallet_transfer_with_selection(
+                "1",
+                &from_wallet_xorurl,
+                &to_wallet_xorurl,
+                CoinSelection::Explicit(vec!["unknown".to_string()]),
+            )
+            .await
+        {
+            Err(Error::InvalidInput(msg)) => assert!(msg.contains("'unknown'")),
+            other => return Err(anyhow!("Unexpected result: {:?}", other)),
+        }
+
+        // the same spendable balance cannot be debited twice
+        match safe
+            .wallet_transfer_with_selection(
+                "1",
+                &from_wallet_xorurl,
+                &to_wallet_xorurl,
+                CoinSelection::Explicit(vec!["thirty".to_string(), "thirty".to_string()]),
+            )
+            .await
+        {
+            Err(Error::InvalidInput(msg)) => assert!(msg.contains("more than once")),
+            other => return Err(anyhow!("Unexpected result: {:?}", other)),
+        }
+
+        Ok(())
+    }
+}
@@ -7,18 +7,25 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
+mod coin_selection;
+mod sk_envelope;
+
+pub use coin_selection::{CoinSelection, WalletTransferLeg, WalletTransferReport};
+
 use super::{
-    common::ed_sk_from_hex,
+    common::{ed_sk_from_hex, sk_to_hex},
     helpers::{parse_coins_amount, pk_from_hex},
+    keys::{gen_history_entries, TransferHistoryEntry},
 };
 use crate::{
     api::app::safeurl::{SafeContentType, SafeDataType, SafeUrl, XorUrl},
     Error, Result, Safe,
 };
 use hex::encode;
-use log::debug;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
-use sn_data_types::{Keypair, MapValue, Token};
+use sk_envelope::{is_encrypted, open_sk, seal_sk};
+use sn_data_types::{Keypair, MapValue, PublicKey, SecretKey, Token};
 use std::collections::BTreeMap;
 use xor_name::XorName;
 
@@ -27,15 +34,35 @@ const WALLET_TYPE_TAG: u64 = 1_000;
 
 const WALLET_DEFAULT_BYTES: &[u8] = b"_default";
 
-// Struct which is serialised and stored in Wallet MD for linking to a spendable balance (Key)
+// Prefix of the friendly names given to spendable balances derived from a mnemonic,
+// which is followed by the derivation index
+const WALLET_MNEMONIC_BALANCE_PREFIX: &str = "mnemonic-";
+
+// Struct which is serialised and stored in Wallet MD for linking to a spendable balance (Key).
+// The 'sk' is stored encrypted to the Wallet owner's key, see 'Safe::wallet_reveal_sks',
+// and it's left empty for watch-only balances, see 'Safe::wallet_insert_watch_only'
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct WalletSpendableBalance {
     pub xorurl: XorUrl,
     pub sk: String,
 }
 
+impl WalletSpendableBalance {
+    /// Watch-only balances can be checked but not spent from, as their secret key is unknown
+    pub fn is_watch_only(&self) -> bool {
+        self.sk.is_empty()
+    }
+}
+
 pub type WalletSpendableBalances = BTreeMap<String, (bool, WalletSpendableBalance)>;
 
+/// Total balance of a Wallet, keeping the watch-only balances apart from the spendable ones
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct WalletBalance {
+    pub spendable: String,
+    pub watch_only: String,
+}
+
 impl Safe {
     // Create an empty Wallet and return its XOR-URL
     pub async fn wallet_create(&mut self) -> Result<XorUrl> {
@@ -66,133 +93,227 @@ impl Safe {
         let xorname = XorName::from(keypair.public_key());
         let xorurl = SafeUrl::encode_safekey(xorname, self.xorurl_base)?;
 
+        let (safeurl, _) = self.parse_and_resolve_url(url).await?;
+        let owner_sk = owner_secret_key(self).await?;
         let value = WalletSpendableBalance {
             xorurl: xorurl.clone(),
-            sk: sk.to_string(),
+            sk: seal_sk(&owner_sk, &safeurl.xorname(), &xorurl, sk)?,
         };
 
-        let serialised_value = serde_json::to_string(&value).map_err(|err| {
-            Error::Serialisation(format!(
-                "Failed to serialise data to insert in Wallet container: {:?}",
-                err
-            ))
-        })?;
-
         let md_key = name.unwrap_or(&xorurl);
-        let (safeurl, _) = self.parse_and_resolve_url(url).await?;
-        self.safe_client
-            .map_insert(
-                safeurl.xorname(),
-                WALLET_TYPE_TAG,
-                &md_key.to_string().into_bytes(),
-                &serialised_value.into_bytes(),
-            )
-            .await
-            .map_err(|err| match err {
-                Error::EntryExists(_) => Error::EntryExists(format!(
-                    "A spendable balance already exists in the Wallet with the same name: '{}'",
-                    md_key
-                )),
-                other => other,
-            })?;
-
+        insert_spendable_balance(self, &safeurl, md_key, &value).await?;
         debug!(
             "Wallet at {} had a spendable balance added with name: {}.",
             &url, md_key
         );
 
         if default {
-            let default_key = md_key.to_string().into_bytes();
-
-            match self
-                .safe_client
-                .map_insert(
-                    safeurl.xorname(),
-                    WALLET_TYPE_TAG,
-                    WALLET_DEFAULT_BYTES,
-                    &default_key,
-                )
-                .await
-            {
-                Err(Error::EntryExists(_)) => {
-                    let (_, version) = self.wallet_get_default_balance(url).await?;
-                    self.safe_client
-                        .update_map(
-                            safeurl.xorname(),
-                            WALLET_TYPE_TAG,
-                            WALLET_DEFAULT_BYTES,
-                            &default_key,
-                            version + 1,
-                        )
-                        .await
-                }
-                other => other,
-            }?;
-
+            set_default_balance(self, &safeurl, md_key).await?;
             debug!("Default wallet set.");
         }
 
         Ok(md_key.to_string())
     }
 
-    // Check the total balance of a Wallet found at a given XOR-URL
-    pub async fn wallet_balance(&mut self, url: &str) -> Result<String> {
-        debug!("Finding total wallet balance for: {:?}", url);
-        let mut total_balance = Token::from_nano(0);
-
-        // Let's get the list of balances from the Wallet
-        let (safeurl, nrs_safeurl) = self.parse_and_resolve_url(url).await?;
-        debug!("Wallet URL was parsed and resolved to: {:?}", safeurl);
-        let url_path = if let Some(nrs_url) = nrs_safeurl {
-            nrs_url.path().to_string()
+    /// # Add a watch-only balance to a Wallet
+    ///
+    /// The balance is given as a SafeKey URL or a hex-encoded PublicKey, and since its secret
+    /// key is not stored in the Wallet it can be checked and receive transfers but it's never spent
+    /// from. The friendly name set for it is returned, which is its XOR-URL if no name was provided.
+    pub async fn wallet_insert_watch_only(
+        &mut self,
+        url: &str,
+        name: Option<&str>,
+        key: &str,
+    ) -> Result<String> {
+        let xorname = if key.starts_with("safe://") {
+            let (key_safeurl, _) = self.parse_and_resolve_url(key).await?;
+            if key_safeurl.content_type() != SafeContentType::Raw
+                || key_safeurl.data_type() != SafeDataType::SafeKey
+            {
+                return Err(Error::InvalidInput(format!(
+                    "The URL of a watch-only balance must target a SafeKey, target is: {:?} ({})",
+                    key_safeurl.content_type(),
+                    key_safeurl.data_type()
+                )));
+            }
+            key_safeurl.xorname()
         } else {
-            safeurl.path().to_string()
+            XorName::from(pk_from_hex(key)?)
         };
+        let xorurl = SafeUrl::encode_safekey(xorname, self.xorurl_base)?;
 
-        let balances = if url_path.is_empty() {
-            debug!("We'll check the total balance of the Wallet");
-            gen_wallet_spendable_balances_list(self, safeurl.xorname(), safeurl.type_tag(), url)
-                .await?
-        } else {
-            let balance_name = &url_path[1..]; // we get rid of starting '/'
-            debug!(
-                "We'll check only the balance for spendable balance named: '{}'",
-                balance_name
-            );
-            let (spendable_balance, _) = wallet_get_spendable_balance(
-                self,
+        let safeurl = resolve_wallet_for_update(self, url).await?;
+        let value = WalletSpendableBalance {
+            xorurl: xorurl.clone(),
+            sk: String::default(),
+        };
+
+        let md_key = name.unwrap_or(&xorurl);
+        insert_spendable_balance(self, &safeurl, md_key, &value).await?;
+        debug!(
+            "Wallet at {} had a watch-only balance added with name: {}.",
+            &url, md_key
+        );
+
+        Ok(md_key.to_string())
+    }
+
+    /// # Remove a spendable balance from a Wallet
+    ///
+    /// If it was the default spendable balance the Wallet is left without a default one.
+    pub async fn wallet_remove(&mut self, url: &str, name: &str) -> Result<()> {
+        let safeurl = resolve_wallet_for_update(self, url).await?;
+        let (_, version) = get_balance_for_update(self, &safeurl, url, name).await?;
+
+        // the default is unset first so it never refers to a balance which doesn't exist
+        let mut default_unset = false;
+        if let Some((default_name, default_version)) = get_default_name(self, &safeurl).await? {
+            if default_name == name {
+                self.safe_client
+                    .map_delete(
+                        safeurl.xorname(),
+                        safeurl.type_tag(),
+                        WALLET_DEFAULT_BYTES,
+                        default_version + 1,
+                    )
+                    .await?;
+                default_unset = true;
+                debug!("Default spendable balance of Wallet at {} unset", url);
+            }
+        }
+
+        if let Err(err) = self
+            .safe_client
+            .map_delete(
                 safeurl.xorname(),
                 safeurl.type_tag(),
-                balance_name.as_bytes(),
+                name.as_bytes(),
+                version + 1,
             )
             .await
-            .map_err(|_| {
-                Error::InvalidInput(format!(
-                    "No spendable balance named '{}' found in Wallet: '{}'",
-                    balance_name, url
-                ))
-            })?;
+        {
+            // the balance is still there, thus it's set back as the default
+            if default_unset {
+                if let Err(restore_err) = set_default_balance(self, &safeurl, name).await {
+                    warn!(
+                        "Failed to set '{}' back as the default spendable balance of Wallet at {}: {}",
+                        name, url, restore_err
+                    );
+                }
+            }
+            return Err(err);
+        }
+        debug!(
+            "Spendable balance '{}' removed from Wallet at {}",
+            name, url
+        );
 
-            let mut balances = WalletSpendableBalances::default();
-            balances.insert(balance_name.to_string(), (false, spendable_balance));
-            balances
-        };
+        Ok(())
+    }
+
+    /// # Rename a spendable balance of a Wallet
+    ///
+    /// It remains the default spendable balance if it was so.
+    pub async fn wallet_rename(&mut self, url: &str, name: &str, new_name: &str) -> Result<()> {
+        let safeurl = resolve_wallet_for_update(self, url).await?;
+        let (balance, version) = get_balance_for_update(self, &safeurl, url, name).await?;
+
+        // the balance is inserted with the new name before removing it with
+        // the old one, so it cannot be lost if any of the operations fail
+        insert_spendable_balance(self, &safeurl, new_name, &balance).await?;
+        if let Some((default_name, _)) = get_default_name(self, &safeurl).await? {
+            if default_name == name {
+                set_default_balance(self, &safeurl, new_name).await?;
+            }
+        }
+        self.safe_client
+            .map_delete(
+                safeurl.xorname(),
+                safeurl.type_tag(),
+                name.as_bytes(),
+                version + 1,
+            )
+            .await?;
+        debug!(
+            "Spendable balance '{}' of Wallet at {} renamed to '{}'",
+            name, url, new_name
+        );
+
+        Ok(())
+    }
+
+    /// # Set the default spendable balance of a Wallet
+    ///
+    /// The default spendable balance is the one used when transferring from or to a Wallet
+    /// without specifying any spendable balance, thus it cannot be a watch-only balance.
+    pub async fn wallet_set_default(&mut self, url: &str, name: &str) -> Result<()> {
+        let safeurl = resolve_wallet_for_update(self, url).await?;
+        let (balance, _) = get_balance_for_update(self, &safeurl, url, name).await?;
+        if balance.is_watch_only() {
+            return Err(Error::InvalidInput(format!(
+                "The watch-only balance '{}' cannot be set as the default spendable balance",
+                name
+            )));
+        }
+
+        set_default_balance(self, &safeurl, name).await?;
+        debug!(
+            "Default spendable balance of Wallet at {} set to '{}'",
+            url, name
+        );
+
+        Ok(())
+    }
+
+    // Check the total balance of a Wallet found at a given XOR-URL,
+    // which doesn't include the balance of its watch-only balances
+    pub async fn wallet_balance(&mut self, url: &str) -> Result<String> {
+        Ok(self.wallet_balance_with_watch_only(url).await?.spendable)
+    }
+
+    /// # Check the total balance of a Wallet
+    ///
+    /// The total of the watch-only balances is reported apart from the total
+    /// of the spendable balances, since only the latter can be spent.
+    pub async fn wallet_balance_with_watch_only(&mut self, url: &str) -> Result<WalletBalance> {
+        debug!("Finding total wallet balance for: {:?}", url);
+        let mut total_spendable = Token::from_nano(0);
+        let mut total_watch_only = Token::from_nano(0);
 
-        debug!("Spendable balances to check: {:?}", balances);
+        // Let's get the list of balances from the Wallet
+        let (safeurl, balances) = resolve_wallet_balances(self, url).await?;
+
+        debug!("Spendable balances to check: {:?}", balances.keys());
+        let owner_sk = owner_secret_key(self).await?;
         // Iterate through the Keys and query the balance for each
         for (name, (_, balance)) in balances.iter() {
             // Ignore the _default Wallet MD entry key
             debug!("Checking wallet of name: {:?}", name);
-            let secret_key = ed_sk_from_hex(&balance.sk)?;
-
-            let id = Keypair::from(secret_key);
-            let current_balance = self
-                .safe_client
-                .read_balance_from_keypair(id)
-                .await
-                .map_err(|_| {
-                    Error::ContentNotFound("One of the SafeKey's was not found".to_string())
-                })?;
+            let (current_balance, total_balance) = if balance.is_watch_only() {
+                let xorname = SafeUrl::from_url(&balance.xorurl)?.xorname();
+                let current_balance = self
+                    .safe_client
+                    .read_balance_from_xorname(xorname)
+                    .await
+                    .map_err(|_| {
+                        Error::ContentNotFound("One of the SafeKey's was not found".to_string())
+                    })?;
+                (current_balance, &mut total_watch_only)
+            } else {
+                let sk = open_sk(&owner_sk, &safeurl.xorname(), &balance.xorurl, &balance.sk)?;
+                let secret_key = ed_sk_from_hex(&sk)?;
+
+                let id = Keypair::from(secret_key);
+                let current_balance = self
+                    .safe_client
+                    .read_balance_from_keypair(id)
+                    .await
+                    .map_err(|_| {
+                        Error::ContentNotFound("One of the SafeKey's was not found".to_string())
+                    })?;
+                (current_balance, &mut total_spendable)
+            };
 
             debug!("{}: balance is {}", name, current_balance);
             match total_balance.checked_add(current_balance) {
@@ -202,11 +323,41 @@ impl Safe {
                         total_balance
                     )))
                 }
-                Some(new_balance_coins) => total_balance = new_balance_coins,
+                Some(new_balance_coins) => *total_balance = new_balance_coins,
             };
         }
 
-        Ok(total_balance.to_string())
+        Ok(WalletBalance {
+            spendable: total_spendable.to_string(),
+            watch_only: total_watch_only.to_string(),
+        })
+    }
+
+    // Retrieve the incoming and outgoing transfers of all spendable balances of a Wallet, or only
+    // of the spendable balance which name is provided as the path of the Wallet URL.
+    // Watch-only balances are not included since their history requires their secret key.
+    // The network doesn't report when transfers were made, so they cannot be ordered
+    // chronologically across balances: they are listed per spendable balance, each with
+    // the same ordering as in keys_history.
+    pub async fn wallet_history(&mut self, url: &str) -> Result<Vec<TransferHistoryEntry>> {
+        let (safeurl, balances) = resolve_wallet_balances(self, url).await?;
+        let owner_sk = owner_secret_key(self).await?;
+
+        let mut entries = vec![];
+        for (name, (_, balance)) in balances.iter() {
+            if balance.is_watch_only() {
+                // the history can only be retrieved with the secret key
+                debug!("Skipping history of watch-only balance: {:?}", name);
+                continue;
+            }
+            debug!("Retrieving history of spendable balance: {:?}", name);
+            let sk = open_sk(&owner_sk, &safeurl.xorname(), &balance.xorurl, &balance.sk)?;
+            let keypair = Keypair::from(ed_sk_from_hex(&sk)?);
+            let history = self.safe_client.read_history_from_keypair(keypair).await?;
+            entries.extend(gen_history_entries(history, Some(name), self.xorurl_base)?);
+        }
+
+        Ok(entries)
     }
 
     pub async fn wallet_get_default_balance(
@@ -244,6 +395,34 @@ impl Safe {
             .await
     }
 
+    /// # Check the balance of the spendable balance transfers from a Wallet URL are debited from
+    ///
+    /// That's the spendable balance which friendly name is provided as the URL path,
+    /// or the Wallet's default spendable balance otherwise, as in `wallet_transfer`.
+    pub async fn wallet_source_balance(&mut self, url: &str) -> Result<String> {
+        let xorname = self.wallet_source_xorname(url).await?;
+        let balance = self.safe_client.read_balance_from_xorname(xorname).await?;
+        Ok(balance.to_string())
+    }
+
+    /// # Find the XorName of the SafeKey of the spendable balance transfers from a Wallet URL are debited from
+    ///
+    /// The spendable balance is found as in `wallet_source_balance`, and it's the same one
+    /// whichever URL the Wallet is referenced with, e.g. an NRS-URL or its XOR-URL.
+    pub async fn wallet_source_xorname(&mut self, url: &str) -> Result<XorName> {
+        let (safeurl, nrs_safeurl) = self.parse_and_resolve_url(url).await?;
+        if safeurl.content_type() != SafeContentType::Wallet {
+            return Err(Error::InvalidInput(format!(
+                "The URL doesn't target a Wallet, it is: {:?} ({})",
+                safeurl.content_type(),
+                safeurl.data_type()
+            )));
+        }
+
+        let spendable_balance = resolve_wallet_url(self, url, safeurl, nrs_safeurl, false).await?;
+        Ok(SafeUrl::from_url(&spendable_balance.xorurl)?.xorname())
+    }
+
     /// # Transfer safecoins from one Wallet to another
     ///
     /// Using established Wallet and SpendableBalances you can send safecoins between Wallets.
@@ -312,46 +491,15 @@ impl Safe {
 
         // realise which is the source spendable balance to be used
         let from_spendable_balance =
-            resolve_wallet_url(self, from_wallet_url, from_safeurl, from_nrs_safeurl).await?;
+            resolve_wallet_url(self, from_wallet_url, from_safeurl, from_nrs_safeurl, true).await?;
         let from_sk = ed_sk_from_hex(&from_spendable_balance.sk)?;
-        let from = Some(Keypair::from(from_sk));
-
-        let result = if to.starts_with("safe://") {
-            // Now check if the 'to_url' is a valid Wallet or a SafeKey URL
-            let (to_safeurl, to_nrs_safeurl) =
-                self.parse_and_resolve_url(to).await.map_err(|_| {
-                    Error::InvalidInput(format!("Failed to parse the 'to' URL: {}", to))
-                })?;
+        let from = Keypair::from(from_sk);
 
-            let to_xorname = if to_safeurl.content_type() == SafeContentType::Wallet {
-                let to_wallet_balance =
-                    resolve_wallet_url(self, to, to_safeurl, to_nrs_safeurl).await?;
-                SafeUrl::from_url(&to_wallet_balance.xorurl)?.xorname()
-            } else if to_safeurl.content_type() == SafeContentType::Raw
-                && to_safeurl.data_type() == SafeDataType::SafeKey
-            {
-                to_safeurl.xorname()
-            } else {
-                return Err(Error::InvalidInput(format!(
-                    "The destination URL doesn't target a SafeKey or Wallet, target is: {:?} ({})",
-                    to_safeurl.content_type(),
-                    to_safeurl.data_type()
-                )));
-            };
+        // Now check if the 'to' is a valid Wallet or SafeKey URL, or a PublicKey
+        let destination = resolve_transfer_destination(self, to).await?;
 
-            // Finally, let's make the transfer
-            self.safe_client
-                .safecoin_transfer_to_xorname(from, to_xorname, amount_coins)
-                .await
-        } else {
-            // ...let's assume the 'to' is a PublicKey then
-            let to_pk = pk_from_hex(to)?;
-
-            // and let's make the transfer
-            self.safe_client
-                .safecoin_transfer_to_pk(from, to_pk, amount_coins)
-                .await
-        };
+        // Finally, let's make the transfer
+        let result = transfer_to_destination(self, from, &destination, amount_coins).await;
 
         match result {
             Err(Error::NotEnoughBalance(_)) => Err(Error::NotEnoughBalance(format!(
@@ -363,11 +511,134 @@ impl Safe {
         }
     }
 
+    /// # Validate the destination of a transfer
+    ///
+    /// Check the destination is either a Wallet or SafeKey URL (XOR-URL or NRS-URL),
+    /// which can be resolved, or otherwise a valid hex-encoded PublicKey. This allows
+    /// applications to validate destinations before making any transfer, e.g. for a batch of them.
+    pub async fn validate_transfer_destination(&mut self, to: &str) -> Result<()> {
+        let _ = resolve_transfer_destination(self, to).await?;
+        Ok(())
+    }
+
     pub async fn wallet_get(&mut self, url: &str) -> Result<WalletSpendableBalances> {
         let (safeurl, _) = self.parse_and_resolve_url(url).await?;
         self.fetch_wallet(&safeurl).await
     }
 
+    /// Decrypt the secret keys of spendable balances fetched from the Wallet with the given XorName,
+    /// which is only possible when connected with the credentials the Wallet is owned by
+    pub async fn wallet_reveal_sks(
+        &self,
+        wallet_xorname: XorName,
+        balances: &mut WalletSpendableBalances,
+    ) -> Result<()> {
+        let owner_sk = owner_secret_key(self).await?;
+        for (_, balance) in balances.values_mut().filter(|(_, b)| !b.is_watch_only()) {
+            balance.sk = open_sk(&owner_sk, &wallet_xorname, &balance.xorurl, &balance.sk)?;
+        }
+        Ok(())
+    }
+
+    /// # Insert the spendable balances derived from a mnemonic phrase into a Wallet
+    ///
+    /// All the SafeKeys derived from the mnemonic which have been used are inserted,
+    /// stopping after 'gap_limit' consecutive unused ones are found. Each of them is given
+    /// the friendly name 'mnemonic-<index>', and the first one found is set as the default.
+    /// This allows to rebuild a Wallet's spendable balances from the mnemonic phrase alone.
+    /// The derivation index and friendly name of each inserted spendable balance are returned.
+    pub async fn wallet_insert_from_mnemonic(
+        &mut self,
+        url: &str,
+        mnemonic: &str,
+        gap_limit: u32,
+    ) -> Result<Vec<(u32, String)>> {
+        let used_keys = self
+            .keys_find_used_from_mnemonic(mnemonic, gap_limit)
+            .await?;
+
+        let mut inserted = vec![];
+        for (index, keypair) in used_keys {
+            let sk = keypair.secret_key().map_err(|err| {
+                Error::InvalidInput(format!(
+                    "Failed to obtain secret key of key pair derived from mnemonic: {:?}",
+                    err
+                ))
+            })?;
+            let name = format!("{}{}", WALLET_MNEMONIC_BALANCE_PREFIX, index);
+            let name = self
+                .wallet_insert(url, Some(&name), inserted.is_empty(), &sk_to_hex(sk))
+                .await?;
+            inserted.push((index, name));
+        }
+
+        Ok(inserted)
+    }
+
+    // Re-encrypt the secret keys of all spendable balances which were stored in plaintext
+    // by previous versions, returning the friendly names of the migrated spendable balances
+    pub async fn wallet_migrate(&mut self, url: &str) -> Result<Vec<String>> {
+        let (safeurl, _) = self.parse_and_resolve_url(url).await?;
+        if safeurl.content_type() != SafeContentType::Wallet {
+            return Err(Error::InvalidInput(format!(
+                "The URL doesn't target a Wallet, it is: {:?} ({})",
+                safeurl.content_type(),
+                safeurl.data_type()
+            )));
+        }
+
+        let entries = self
+            .safe_client
+            .list_map_entries(safeurl.xorname(), safeurl.type_tag())
+            .await?;
+        let owner_sk = owner_secret_key(self).await?;
+
+        let mut migrated = vec![];
+        for (key, value) in entries.iter() {
+            if key.as_slice() == WALLET_DEFAULT_BYTES {
+                continue;
+            }
+            let mut spendable_balance: WalletSpendableBalance = serde_json::from_slice(&value.data)
+                .map_err(|_| {
+                    Error::ContentError(
+                        "Couldn't deserialise data stored in the Wallet".to_string(),
+                    )
+                })?;
+            if spendable_balance.is_watch_only() || is_encrypted(&spendable_balance.sk) {
+                continue;
+            }
+
+            spendable_balance.sk = seal_sk(
+                &owner_sk,
+                &safeurl.xorname(),
+                &spendable_balance.xorurl,
+                &spendable_balance.sk,
+            )?;
+            let serialised_value = serde_json::to_vec(&spendable_balance).map_err(|err| {
+                Error::Serialisation(format!(
+                    "Failed to serialise data to update in Wallet container: {:?}",
+                    err
+                ))
+            })?;
+
+            self.safe_client
+                .update_map(
+                    safeurl.xorname(),
+                    safeurl.type_tag(),
+                    key,
+                    &serialised_value,
+                    value.version + 1,
+                )
+                .await?;
+
+            let name = String::from_utf8_lossy(key).to_string();
+            debug!("Spendable balance '{}' of Wallet at {} migrated", name, url);
+            migrated.push(name);
+        }
+
+        Ok(migrated)
+    }
+
     /// Fetch a Wallet from a SafeUrl without performing any type of URL resolution
     pub(crate) async fn fetch_wallet(&self, safeurl: &SafeUrl) -> Result<WalletSpendableBalances> {
         gen_wallet_spendable_balances_list(
@@ -440,6 +711,51 @@ async fn gen_wallet_spendable_balances_list(
     Ok(balances)
 }
 
+// Private helper to get the list of all spendable balances of a Wallet, or only the one which
+// name is provided as the path of the Wallet URL
+async fn resolve_wallet_balances(
+    safe: &Safe,
+    url: &str,
+) -> Result<(SafeUrl, WalletSpendableBalances)> {
+    let (safeurl, nrs_safeurl) = safe.parse_and_resolve_url(url).await?;
+    debug!("Wallet URL was parsed and resolved to: {:?}", safeurl);
+    let url_path = if let Some(nrs_url) = nrs_safeurl {
+        nrs_url.path().to_string()
+    } else {
+        safeurl.path().to_string()
+    };
+
+    let balances = if url_path.is_empty() {
+        debug!("We'll use all spendable balances of the Wallet");
+        gen_wallet_spendable_balances_list(safe, safeurl.xorname(), safeurl.type_tag(), url).await?
+    } else {
+        let balance_name = &url_path[1..]; // we get rid of starting '/'
+        debug!(
+            "We'll use only the spendable balance named: '{}'",
+            balance_name
+        );
+        let (spendable_balance, _) = wallet_get_spendable_balance(
+            safe,
+            safeurl.xorname(),
+            safeurl.type_tag(),
+            balance_name.as_bytes(),
+        )
+        .await
+        .map_err(|_| {
+            Error::InvalidInput(format!(
+                "No spendable balance named '{}' found in Wallet: '{}'",
+                balance_name, url
+            ))
+        })?;
+
+        let mut balances = WalletSpendableBalances::default();
+        balances.insert(balance_name.to_string(), (false, spendable_balance));
+        balances
+    };
+
+    Ok((safeurl, balances))
+}
+
 // Private helper to fetch a specific spendable balance from a Wallet usng its assigned frienly name
 // TODO: move this out to a WalletRdf API
 async fn wallet_get_spendable_balance(
@@ -478,12 +794,206 @@ async fn wallet_get_spendable_balance(
     Ok(the_balance)
 }
 
-// Get a specific spendable balance if the URL has a path, or the default
+// Private helper to resolve the URL of a Wallet which is about to be updated
+async fn resolve_wallet_for_update(safe: &Safe, url: &str) -> Result<SafeUrl> {
+    let (safeurl, _) = safe.parse_and_resolve_url(url).await?;
+    if safeurl.content_type() != SafeContentType::Wallet {
+        return Err(Error::InvalidInput(format!(
+            "The URL doesn't target a Wallet, it is: {:?} ({})",
+            safeurl.content_type(),
+            safeurl.data_type()
+        )));
+    }
+    Ok(safeurl)
+}
+
+// Private helper to fetch a spendable balance which is about to be updated, along with its version
+async fn get_balance_for_update(
+    safe: &Safe,
+    safeurl: &SafeUrl,
+    url: &str,
+    name: &str,
+) -> Result<(WalletSpendableBalance, u64)> {
+    wallet_get_spendable_balance(safe, safeurl.xorname(), safeurl.type_tag(), name.as_bytes())
+        .await
+        .map_err(|_| {
+            Error::InvalidInput(format!(
+                "No spendable balance named '{}' found in Wallet: '{}'",
+                name, url
+            ))
+        })
+}
+
+// Private helper to insert a spendable balance with the given friendly name
+async fn insert_spendable_balance(
+    safe: &Safe,
+    safeurl: &SafeUrl,
+    name: &str,
+    value: &WalletSpendableBalance,
+) -> Result<()> {
+    if name.as_bytes() == WALLET_DEFAULT_BYTES {
+        return Err(Error::InvalidInput(format!(
+            "The friendly name '{}' is reserved",
+            name
+        )));
+    }
+
+    let serialised_value = serde_json::to_vec(value).map_err(|err| {
+        Error::Serialisation(format!(
+            "Failed to serialise data to insert in Wallet container: {:?}",
+            err
+        ))
+    })?;
+
+    safe.safe_client
+        .map_insert(
+            safeurl.xorname(),
+            safeurl.type_tag(),
+            name.as_bytes(),
+            &serialised_value,
+        )
+        .await
+        .map_err(|err| match err {
+            Error::EntryExists(_) => Error::EntryExists(format!(
+                "A spendable balance already exists in the Wallet with the same name: '{}'",
+                name
+            )),
+            other => other,
+        })
+}
+
+// Private helper to get the friendly name of the default spendable balance, if there is one,
+// along with the version of the entry which references it
+async fn get_default_name(safe: &Safe, safeurl: &SafeUrl) -> Result<Option<(String, u64)>> {
+    match safe
+        .safe_client
+        .map_get_value(safeurl.xorname(), safeurl.type_tag(), WALLET_DEFAULT_BYTES)
+        .await
+    {
+        Ok(MapValue::Seq(value)) => Ok(Some((
+            String::from_utf8_lossy(&value.data).to_string(),
+            value.version,
+        ))),
+        Ok(MapValue::Unseq(_)) => Err(Error::ContentError(
+            "Wallet could not be parsed as wallet map is unsequenced.".to_string(),
+        )),
+        Err(Error::EntryNotFound(_)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// Private helper to set the spendable balance with the given friendly name as the default
+async fn set_default_balance(safe: &Safe, safeurl: &SafeUrl, name: &str) -> Result<()> {
+    match get_default_name(safe, safeurl).await? {
+        Some((_, version)) => {
+            safe.safe_client
+                .update_map(
+                    safeurl.xorname(),
+                    safeurl.type_tag(),
+                    WALLET_DEFAULT_BYTES,
+                    name.as_bytes(),
+                    version + 1,
+                )
+                .await
+        }
+        None => {
+            safe.safe_client
+                .map_insert(
+                    safeurl.xorname(),
+                    safeurl.type_tag(),
+                    WALLET_DEFAULT_BYTES,
+                    name.as_bytes(),
+                )
+                .await
+        }
+    }
+}
+
+// Destination of a transfer, once the URL or PublicKey provided has been resolved
+#[derive(Debug, Clone)]
+enum TransferDestination {
+    // A SafeKey, or the spendable balance of a Wallet, with the XOR-URL provided
+    XorName(XorName, String),
+    // A PublicKey provided as a hex string
+    PublicKey(PublicKey),
+}
+
+// Private helper to resolve the destination of a transfer, which can be a Wallet or
+// SafeKey URL, or otherwise a PublicKey
+async fn resolve_transfer_destination(safe: &mut Safe, to: &str) -> Result<TransferDestination> {
+    if to.starts_with("safe://") {
+        let (to_safeurl, to_nrs_safeurl) = safe
+            .parse_and_resolve_url(to)
+            .await
+            .map_err(|_| Error::InvalidInput(format!("Failed to parse the 'to' URL: {}", to)))?;
+
+        if to_safeurl.content_type() == SafeContentType::Wallet {
+            let to_wallet_balance =
+                resolve_wallet_url(safe, to, to_safeurl, to_nrs_safeurl, false).await?;
+            let xorname = SafeUrl::from_url(&to_wallet_balance.xorurl)?.xorname();
+            Ok(TransferDestination::XorName(xorname, to.to_string()))
+        } else if to_safeurl.content_type() == SafeContentType::Raw
+            && to_safeurl.data_type() == SafeDataType::SafeKey
+        {
+            Ok(TransferDestination::XorName(
+                to_safeurl.xorname(),
+                to.to_string(),
+            ))
+        } else {
+            Err(Error::InvalidInput(format!(
+                "The destination URL doesn't target a SafeKey or Wallet, target is: {:?} ({})",
+                to_safeurl.content_type(),
+                to_safeurl.data_type()
+            )))
+        }
+    } else {
+        // ...let's assume the 'to' is a PublicKey then
+        let to_pk = pk_from_hex(to)?;
+        Ok(TransferDestination::PublicKey(to_pk))
+    }
+}
+
+// Private helper to transfer safecoins from a Keypair to an already resolved destination
+async fn transfer_to_destination(
+    safe: &Safe,
+    from: Keypair,
+    destination: &TransferDestination,
+    amount: Token,
+) -> Result<u64> {
+    match destination {
+        TransferDestination::XorName(xorname, _) => {
+            safe.safe_client
+                .safecoin_transfer_to_xorname(Some(from), *xorname, amount)
+                .await
+        }
+        TransferDestination::PublicKey(pk) => {
+            safe.safe_client
+                .safecoin_transfer_to_pk(Some(from), *pk, amount)
+                .await
+        }
+    }
+}
+
+// Private helper to get the secret key of the current credentials, which is the key
+// the spendable balances' secret keys are encrypted to
+async fn owner_secret_key(safe: &Safe) -> Result<SecretKey> {
+    let keypair = safe.safe_client.keypair().await?;
+    keypair.secret_key().map_err(|err| {
+        Error::InvalidInput(format!(
+            "Failed to obtain secret key of current credentials: {:?}",
+            err
+        ))
+    })
+}
+
+// Get a specific spendable balance if the URL has a path, or the default.
+// The secret key is decrypted only if requested, i.e. when the balance is to be spent
 async fn resolve_wallet_url(
     safe: &mut Safe,
     wallet_url: &str,
     safeurl: SafeUrl,
     nrs_safeurl: Option<SafeUrl>,
+    decrypt_sk: bool,
 ) -> Result<WalletSpendableBalance> {
     let url_path = if let Some(nrs_url) = nrs_safeurl {
         nrs_url.path().to_string()
@@ -491,7 +1001,7 @@ async fn resolve_wallet_url(
         safeurl.path().to_string()
     };
 
-    let (wallet_balance, _) = if url_path.is_empty() {
+    let (mut wallet_balance, _) = if url_path.is_empty() {
         // Figure out which is the default spendable balance
         safe.wallet_get_default_balance(&wallet_url).await?
     } else {
@@ -512,6 +1022,22 @@ async fn resolve_wallet_url(
         })?
     };
 
+    if decrypt_sk {
+        if wallet_balance.is_watch_only() {
+            return Err(Error::InvalidInput(format!(
+                "Cannot spend from a watch-only balance of Wallet: '{}'",
+                wallet_url
+            )));
+        }
+        let owner_sk = owner_secret_key(safe).await?;
+        wallet_balance.sk = open_sk(
+            &owner_sk,
+            &safeurl.xorname(),
+            &wallet_balance.xorurl,
+            &wallet_balance.sk,
+        )?;
+    }
+
     Ok(wallet_balance)
 }
 
@@ -520,7 +1046,10 @@ mod tests {
     use super::*;
     use crate::{
         api::{
-            app::test_helpers::{new_read_only_safe_instance, new_safe_instance, random_nrs_name},
+            app::{
+                test_helpers::{new_read_only_safe_instance, new_safe_instance, random_nrs_name},
+                TransferDirection, MNEMONIC_DEFAULT_GAP_LIMIT,
+            },
             common::sk_to_hex,
         },
         retry_loop, retry_loop_for_pattern,
@@ -578,7 +1107,20 @@ mod tests {
         safe.wallet_insert(&wallet_xorurl, Some("my-second-balance"), false, &sk2_hex)
             .await?;
 
-        let wallet_balances = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances.len() == 2)?;
+        let mut wallet_balances = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances.len() == 2)?;
+        // secret keys are not stored in plaintext
+        assert!(wallet_balances["my-first-balance"]
+            .1
+            .sk
+            .starts_with("enc1:"));
+        assert!(wallet_balances["my-second-balance"]
+            .1
+            .sk
+            .starts_with("enc1:"));
+
+        let wallet_xorname = SafeUrl::from_url(&wallet_xorurl)?.xorname();
+        safe.wallet_reveal_sks(wallet_xorname, &mut wallet_balances)
+            .await?;
         assert_eq!(wallet_balances["my-first-balance"].0, true);
         assert_eq!(wallet_balances["my-first-balance"].1.xorurl, key1_xorurl);
         assert_eq!(
@@ -619,7 +1161,10 @@ mod tests {
 
         let _ = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances.len() == 2)?;
 
-        let wallet_balances = safe.wallet_get(&wallet_xorurl).await?;
+        let mut wallet_balances = safe.wallet_get(&wallet_xorurl).await?;
+        let wallet_xorname = SafeUrl::from_url(&wallet_xorurl)?.xorname();
+        safe.wallet_reveal_sks(wallet_xorname, &mut wallet_balances)
+            .await?;
         assert_eq!(wallet_balances["my-first-balance"].0, false);
         assert_eq!(wallet_balances["my-first-balance"].1.xorurl, key1_xorurl);
         assert_eq!(
@@ -637,6 +1182,295 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_wallet_source_balance() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+        let (_, keypair1) = safe.keys_create_preload_test_coins("12.5").await?;
+        let sk1_hex = sk_to_hex(keypair1.secret_key()?);
+        let (_, keypair2) = safe.keys_create_preload_test_coins("3").await?;
+        let sk2_hex = sk_to_hex(keypair2.secret_key()?);
+        let _ = retry_loop!(safe.fetch(&wallet_xorurl, None));
+
+        safe.wallet_insert(&wallet_xorurl, Some("default-balance"), true, &sk1_hex)
+            .await?;
+        safe.wallet_insert(&wallet_xorurl, Some("other-balance"), false, &sk2_hex)
+            .await?;
+        let _ = retry_loop_for_pattern!(safe.wallet_balance(&wallet_xorurl), Ok(balance) if balance == "15.500000000")?;
+
+        // only the balance transfers are debited from is reported
+        let balance = safe.wallet_source_balance(&wallet_xorurl).await?;
+        assert_eq!(balance, "12.500000000");
+
+        let balance = safe
+            .wallet_source_balance(&format!("{}/other-balance", wallet_xorurl))
+            .await?;
+        assert_eq!(balance, "3.000000000");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wallet_remove_rename_and_set_default() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+        let (_, keypair1) = safe.keys_create_preload_test_coins("10").await?;
+        let sk1_hex = sk_to_hex(keypair1.secret_key()?);
+        let (_, keypair2) = safe.keys_create_preload_test_coins("2").await?;
+        let sk2_hex = sk_to_hex(keypair2.secret_key()?);
+
+        let _ = retry_loop!(safe.fetch(&wallet_xorurl, None));
+        safe.wallet_insert(&wallet_xorurl, Some("first"), true, &sk1_hex)
+            .await?;
+        safe.wallet_insert(&wallet_xorurl, Some("second"), false, &sk2_hex)
+            .await?;
+        let _ = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances.len() == 2)?;
+
+        // set the second one as default
+        safe.wallet_set_default(&wallet_xorurl, "second").await?;
+        let balances = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances["second"].0)?;
+        assert!(!balances["first"].0);
+
+        // renaming the default one keeps it as the default
+        safe.wallet_rename(&wallet_xorurl, "second", "savings")
+            .await?;
+        let balances = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances.contains_key("savings") && !balances.contains_key("second"))?;
+        assert!(balances["savings"].0);
+        let current_balance = safe.wallet_balance(&wallet_xorurl).await?;
+        assert_eq!("12.000000000", current_balance);
+
+        // it cannot be renamed to an existing name
+        assert!(matches!(
+            safe.wallet_rename(&wallet_xorurl, "savings", "first").await,
+            Err(Error::EntryExists(_))
+        ));
+
+        // removing the default one leaves the Wallet without default
+        safe.wallet_remove(&wallet_xorurl, "savings").await?;
+        let balances = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances.len() == 1)?;
+        assert!(!balances["first"].0);
+        assert!(safe
+            .wallet_get_default_balance(&wallet_xorurl)
+            .await
+            .is_err());
+
+        // a name which doesn't exist
+        assert!(matches!(
+            safe.wallet_remove(&wallet_xorurl, "savings").await,
+            Err(Error::InvalidInput(_))
+        ));
+        assert!(matches!(
+            safe.wallet_set_default(&wallet_xorurl, "savings").await,
+            Err(Error::InvalidInput(_))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wallet_watch_only() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+        let (_, keypair1) = safe.keys_create_preload_test_coins("10").await?;
+        let sk1_hex = sk_to_hex(keypair1.secret_key()?);
+        let (key2_xorurl, _) = safe.keys_create_preload_test_coins("2.5").await?;
+        let (_, keypair3) = safe.keys_create_preload_test_coins("4").await?;
+        let pk3_hex = encode(keypair3.public_key().to_bytes());
+
+        let _ = retry_loop!(safe.fetch(&wallet_xorurl, None));
+        safe.wallet_insert(&wallet_xorurl, Some("spendable"), true, &sk1_hex)
+            .await?;
+        safe.wallet_insert_watch_only(&wallet_xorurl, Some("cold"), &key2_xorurl)
+            .await?;
+        let name = safe
+            .wallet_insert_watch_only(&wallet_xorurl, None, &pk3_hex)
+            .await?;
+        let balances = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances.len() == 3)?;
+        assert!(balances["cold"].1.is_watch_only());
+        assert_eq!(balances["cold"].1.xorurl, key2_xorurl);
+        assert!(balances[&name].1.is_watch_only());
+        assert!(!balances["spendable"].1.is_watch_only());
+
+        // the watch-only balances are reported apart
+        let total = safe.wallet_balance_with_watch_only(&wallet_xorurl).await?;
+        assert_eq!(total.spendable, "10.000000000");
+        assert_eq!(total.watch_only, "6.500000000");
+        assert_eq!(safe.wallet_balance(&wallet_xorurl).await?, "10.000000000");
+
+        // they cannot be spent from, nor set as default
+        assert!(matches!(
+            safe.wallet_set_default(&wallet_xorurl, "cold").await,
+            Err(Error::InvalidInput(_))
+        ));
+        let (to_xorurl, _) = safe.keys_create_preload_test_coins("0.1").await?;
+        assert!(matches!(
+            safe.wallet_transfer("1", &format!("{}/cold", wallet_xorurl), &to_xorurl)
+                .await,
+            Err(Error::InvalidInput(_))
+        ));
+
+        // but they can receive transfers
+        safe.wallet_transfer("1", &wallet_xorurl, &format!("{}/cold", wallet_xorurl))
+            .await?;
+        let total = retry_loop_for_pattern!(safe.wallet_balance_with_watch_only(&wallet_xorurl), Ok(total) if total.watch_only == "7.500000000")?;
+        assert_eq!(total.spendable, "9.000000000");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wallet_history() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+        let (_, keypair1) = safe.keys_create_preload_test_coins("10").await?;
+        let sk1_hex = sk_to_hex(keypair1.secret_key()?);
+        let (_, keypair2) = safe.keys_create_preload_test_coins("5").await?;
+        let sk2_hex = sk_to_hex(keypair2.secret_key()?);
+        let (key3_xorurl, _) = safe.keys_create_preload_test_coins("0").await?;
+        let _ = retry_loop!(safe.fetch(&wallet_xorurl, None));
+
+        safe.wallet_insert(&wallet_xorurl, Some("first-balance"), true, &sk1_hex)
+            .await?;
+        safe.wallet_insert(&wallet_xorurl, Some("second-balance"), false, &sk2_hex)
+            .await?;
+        let _ = retry_loop_for_pattern!(safe.wallet_balance(&wallet_xorurl), Ok(balance) if balance == "15.000000000")?;
+
+        let tx_id = safe
+            .wallet_transfer("1.5", &wallet_xorurl, &key3_xorurl)
+            .await?;
+
+        let history = safe.wallet_history(&wallet_xorurl).await?;
+        let outgoing: Vec<&TransferHistoryEntry> = history
+            .iter()
+            .filter(|entry| entry.direction == TransferDirection::Outgoing)
+            .collect();
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].balance_name, Some("first-balance".to_string()));
+        assert_eq!(outgoing[0].amount, "1.500000000");
+        assert_eq!(outgoing[0].tx_id, tx_id.to_string());
+        assert_eq!(outgoing[0].counterparty, Some(key3_xorurl));
+
+        // only the history of the spendable balance in the URL path
+        let history = safe
+            .wallet_history(&format!("{}/second-balance", wallet_xorurl))
+            .await?;
+        assert!(history.iter().all(|entry| entry.balance_name
+            == Some("second-balance".to_string())
+            && entry.direction == TransferDirection::Incoming));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wallet_insert_from_mnemonic() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let mnemonic = safe.keys_generate_mnemonic();
+
+        // use the SafeKeys with indexes 0 and 2, leaving a gap in between
+        let keypair0 = safe.keys_derive_from_mnemonic(&mnemonic, 0)?;
+        let _ = safe.keys_preload_test_coins(&keypair0, "4.5").await?;
+        let keypair2 = safe.keys_derive_from_mnemonic(&mnemonic, 2)?;
+        let _ = safe.keys_preload_test_coins(&keypair2, "1.25").await?;
+
+        let wallet_xorurl = safe.wallet_create().await?;
+        let _ = retry_loop!(safe.fetch(&wallet_xorurl, None));
+
+        // a gap limit of 1 stops looking for SafeKeys at index 1
+        let wallet2_xorurl = safe.wallet_create().await?;
+        let _ = retry_loop!(safe.fetch(&wallet2_xorurl, None));
+        let inserted = safe
+            .wallet_insert_from_mnemonic(&wallet2_xorurl, &mnemonic, 1)
+            .await?;
+        assert_eq!(inserted, vec![(0, "mnemonic-0".to_string())]);
+
+        let inserted = safe
+            .wallet_insert_from_mnemonic(&wallet_xorurl, &mnemonic, MNEMONIC_DEFAULT_GAP_LIMIT)
+            .await?;
+        assert_eq!(
+            inserted,
+            vec![(0, "mnemonic-0".to_string()), (2, "mnemonic-2".to_string())]
+        );
+
+        let _ = retry_loop_for_pattern!(safe.wallet_balance(&wallet_xorurl), Ok(balance) if balance == "5.750000000")?;
+        let (default_balance, _) = safe.wallet_get_default_balance(&wallet_xorurl).await?;
+        assert_eq!(
+            default_balance.xorurl,
+            SafeUrl::encode_safekey(XorName::from(keypair0.public_key()), safe.xorurl_base)?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wallet_migrate() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+        let (key1_xorurl, keypair1) = safe.keys_create_preload_test_coins("12.23").await?;
+        let sk1_hex = sk_to_hex(keypair1.secret_key()?);
+        let (_, keypair2) = safe.keys_create_preload_test_coins("1.53").await?;
+        let sk2_hex = sk_to_hex(keypair2.secret_key()?);
+        let _ = retry_loop!(safe.fetch(&wallet_xorurl, None));
+
+        // insert a spendable balance the way previous versions did, i.e. in plaintext
+        let legacy_value = serde_json::to_vec(&WalletSpendableBalance {
+            xorurl: key1_xorurl,
+            sk: sk1_hex.clone(),
+        })?;
+        let wallet_xorname = SafeUrl::from_url(&wallet_xorurl)?.xorname();
+        safe.safe_client
+            .map_insert(
+                wallet_xorname,
+                WALLET_TYPE_TAG,
+                b"legacy-balance",
+                &legacy_value,
+            )
+            .await?;
+        safe.wallet_insert(&wallet_xorurl, Some("new-balance"), false, &sk2_hex)
+            .await?;
+
+        // legacy entries can still be spent from before migrating them
+        let _ = retry_loop_for_pattern!(safe.wallet_balance(&wallet_xorurl), Ok(balance) if balance == "13.760000000")?;
+
+        let migrated = safe.wallet_migrate(&wallet_xorurl).await?;
+        assert_eq!(migrated, vec!["legacy-balance".to_string()]);
+
+        let mut wallet_balances = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances["legacy-balance"].1.sk.starts_with("enc1:"))?;
+        safe.wallet_reveal_sks(wallet_xorname, &mut wallet_balances)
+            .await?;
+        assert_eq!(wallet_balances["legacy-balance"].1.sk, sk1_hex);
+        assert_eq!(wallet_balances["new-balance"].1.sk, sk2_hex);
+
+        // nothing left to migrate
+        let migrated = safe.wallet_migrate(&wallet_xorurl).await?;
+        assert!(migrated.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wallet_reveal_sks_with_other_credentials() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+        let (_, keypair) = safe.keys_create_preload_test_coins("1.53").await?;
+        let sk_hex = sk_to_hex(keypair.secret_key()?);
+        let _ = retry_loop!(safe.fetch(&wallet_xorurl, None));
+        safe.wallet_insert(&wallet_xorurl, Some("my-balance"), true, &sk_hex)
+            .await?;
+
+        let mut wallet_balances = retry_loop_for_pattern!(safe.wallet_get(&wallet_xorurl), Ok(balances) if balances.len() == 1)?;
+        let wallet_xorname = SafeUrl::from_url(&wallet_xorurl)?.xorname();
+
+        // a different client cannot decrypt the secret keys even if it got hold of them
+        let read_only_safe = new_read_only_safe_instance().await?;
+        match read_only_safe
+            .wallet_reveal_sks(wallet_xorname, &mut wallet_balances)
+            .await
+        {
+            Err(Error::DecryptionError(_)) => Ok(()),
+            Err(err) => Err(anyhow!("Error returned is not the expected one: {}", err)),
+            Ok(()) => Err(anyhow!("Secret keys were unexpectedly revealed")),
+        }
+    }
+
     #[tokio::test]
     async fn test_wallet_transfer_no_default() -> Result<()> {
         let mut safe = new_safe_instance().await?;
@@ -896,6 +1730,53 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_wallet_validate_transfer_destination() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+        let (safekey_xorurl, keypair) = safe.keys_create_preload_test_coins("1").await?;
+        let _ = retry_loop!(safe.fetch(&wallet_xorurl, None));
+        safe.wallet_insert(
+            &wallet_xorurl,
+            Some("my-balance"),
+            true,
+            &sk_to_hex(keypair.secret_key()?),
+        )
+        .await?;
+
+        safe.validate_transfer_destination(&wallet_xorurl).await?;
+        safe.validate_transfer_destination(&safekey_xorurl).await?;
+        safe.validate_transfer_destination(&encode(keypair.public_key().to_bytes()))
+            .await?;
+
+        // neither a URL nor a PublicKey
+        assert!(safe
+            .validate_transfer_destination("not-a-public-key")
+            .await
+            .is_err());
+
+        // a Wallet without a default spendable balance
+        let empty_wallet_xorurl = safe.wallet_create().await?;
+        let _ = retry_loop!(safe.fetch(&empty_wallet_xorurl, None));
+        assert!(safe
+            .validate_transfer_destination(&empty_wallet_xorurl)
+            .await
+            .is_err());
+
+        // a URL which doesn't target a Wallet or SafeKey
+        let files_container_xorurl = SafeUrl::encode_mutable_data(
+            rand::random(),
+            1_100,
+            SafeContentType::FilesContainer,
+            safe.xorurl_base,
+        )?;
+        assert!(safe
+            .validate_transfer_destination(&files_container_xorurl)
+            .await
+            .is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_wallet_transfer_from_safekey() -> Result<()> {
         let mut safe = new_safe_instance().await?;
@@ -0,0 +1,236 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+// Envelope used to store the secret key of a spendable balance inside a Wallet.
+//
+// The envelope is a string with the format "enc<version>:<hex(nonce || ciphertext)>".
+// Version 1 uses AES-SIV (128 bits) with a key derived with HMAC-SHA3-256 from the
+// Wallet owner's secret key and the Wallet's XorName, and with the spendable balance's
+// XOR-URL as associated data, so an envelope cannot be moved to a different Wallet
+// or be made to look like it belongs to a different SafeKey.
+//
+// Any value not starting with the "enc" prefix is considered a legacy plaintext secret key.
+
+use crate::{Error, Result};
+use aes_siv::{
+    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+    Aes128SivAead,
+};
+use hmac::{Hmac, Mac, NewMac};
+use rand::{rngs::OsRng, RngCore};
+use sha3::Sha3_256;
+use sn_data_types::SecretKey;
+use xor_name::XorName;
+
+const ENVELOPE_PREFIX: &str = "enc";
+const ENVELOPE_VERSION_1: u64 = 1;
+const ENVELOPE_V1_NONCE_LEN: usize = 16;
+const ENVELOPE_V1_KDF_CONTEXT: &[u8] = b"sn_api/wallet/spendable-balance-sk/v1";
+
+// Returns true if the value stored for the secret key is an encrypted envelope
+pub(crate) fn is_encrypted(sk: &str) -> bool {
+    sk.starts_with(ENVELOPE_PREFIX)
+}
+
+// Encrypt a spendable balance secret key (hex encoded) to the Wallet owner's secret key
+pub(crate) fn seal_sk(
+    owner_sk: &SecretKey,
+    wallet: &XorName,
+    balance_xorurl: &str,
+    sk: &str,
+) -> Result<String> {
+    let cipher = v1_cipher(owner_sk, wallet)?;
+    let mut nonce = [0; ENVELOPE_V1_NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = cipher
+        .encrypt(
+            GenericArray::from_slice(&nonce),
+            Payload {
+                msg: sk.as_bytes(),
+                aad: balance_xorurl.as_bytes(),
+            },
+        )
+        .map_err(|_| {
+            Error::Serialisation("Failed to encrypt the spendable balance secret key".to_string())
+        })?;
+
+    let mut bytes = nonce.to_vec();
+    bytes.extend_from_slice(&ciphertext);
+
+    Ok(format!(
+        "{}{}:{}",
+        ENVELOPE_PREFIX,
+        ENVELOPE_VERSION_1,
+        hex::encode(bytes)
+    ))
+}
+
+// Decrypt the secret key of a spendable balance, legacy plaintext values are returned as they are
+pub(crate) fn open_sk(
+    owner_sk: &SecretKey,
+    wallet: &XorName,
+    balance_xorurl: &str,
+    sk: &str,
+) -> Result<String> {
+    if !is_encrypted(sk) {
+        return Ok(sk.to_string());
+    }
+
+    let mut parts = sk[ENVELOPE_PREFIX.len()..].splitn(2, ':');
+    let (version, payload) = match (parts.next(), parts.next()) {
+        (Some(version), Some(payload)) => (version, payload),
+        _ => {
+            return Err(Error::DecryptionError(
+                "Malformed spendable balance secret key envelope".to_string(),
+            ))
+        }
+    };
+
+    match version.parse::<u64>() {
+        Ok(ENVELOPE_VERSION_1) => {}
+        _ => {
+            return Err(Error::DecryptionError(format!(
+                "Unsupported spendable balance secret key envelope version: '{}'",
+                version
+            )))
+        }
+    }
+
+    let bytes = hex::decode(payload).map_err(|_| {
+        Error::DecryptionError("Malformed spendable balance secret key envelope".to_string())
+    })?;
+    if bytes.len() <= ENVELOPE_V1_NONCE_LEN {
+        return Err(Error::DecryptionError(
+            "Malformed spendable balance secret key envelope".to_string(),
+        ));
+    }
+    let (nonce, ciphertext) = bytes.split_at(ENVELOPE_V1_NONCE_LEN);
+
+    let cipher = v1_cipher(owner_sk, wallet)?;
+    let plaintext = cipher
+        .decrypt(
+            GenericArray::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: balance_xorurl.as_bytes(),
+            },
+        )
+        .map_err(|_| {
+            Error::DecryptionError(
+                "Failed to decrypt the spendable balance secret key, the Wallet may not be owned by the current credentials".to_string(),
+            )
+        })?;
+
+    String::from_utf8(plaintext).map_err(|_| {
+        Error::DecryptionError(
+            "The decrypted spendable balance secret key is not valid UTF-8".to_string(),
+        )
+    })
+}
+
+// Derive the AES-SIV cipher used by version 1 envelopes
+fn v1_cipher(owner_sk: &SecretKey, wallet: &XorName) -> Result<Aes128SivAead> {
+    let owner_sk_bytes = match owner_sk {
+        SecretKey::Ed25519(sk) => sk.to_bytes(),
+        SecretKey::BlsShare(_) => {
+            return Err(Error::InvalidInput(
+                "Cannot encrypt Wallet secret keys with a BlsShare key at this time".to_string(),
+            ))
+        }
+    };
+
+    let mut mac = Hmac::<Sha3_256>::new_varkey(&owner_sk_bytes).map_err(|_| {
+        Error::InvalidInput("Failed to derive the Wallet encryption key".to_string())
+    })?;
+    mac.update(ENVELOPE_V1_KDF_CONTEXT);
+    mac.update(&wallet.0);
+    let key = mac.finalize().into_bytes();
+
+    Ok(Aes128SivAead::new(GenericArray::clone_from_slice(&key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{anyhow, Result};
+    use sn_data_types::Keypair;
+
+    fn random_sk() -> Result<SecretKey> {
+        Keypair::new_ed25519(&mut OsRng)
+            .secret_key()
+            .map_err(|err| anyhow!("{:?}", err))
+    }
+
+    #[test]
+    fn test_sk_envelope_roundtrip() -> Result<()> {
+        let owner_sk = random_sk()?;
+        let wallet = rand::random::<XorName>();
+        let xorurl = "safe://hbyyyyd6cuf5m5z3xkxbgdtuo5z9bdqz1x8igdmz6gnnf8gxppfbrzwb4e";
+        let sk = "6b9ea5a0c8c2b6e1b6c0f5cbb0c1fb11f3a6c7b1f2b8cf1f7bb0c1c2d3e4f5a6";
+
+        let envelope = seal_sk(&owner_sk, &wallet, xorurl, sk)?;
+        assert!(is_encrypted(&envelope));
+        assert!(envelope.starts_with("enc1:"));
+        assert!(!envelope.contains(sk));
+        assert_eq!(open_sk(&owner_sk, &wallet, xorurl, &envelope)?, sk);
+
+        // the same key is sealed with a different nonce every time
+        assert_ne!(seal_sk(&owner_sk, &wallet, xorurl, sk)?, envelope);
+
+        // legacy plaintext values are returned as they are
+        assert_eq!(open_sk(&owner_sk, &wallet, xorurl, sk)?, sk);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sk_envelope_authentication() -> Result<()> {
+        let owner_sk = random_sk()?;
+        let wallet = rand::random::<XorName>();
+        let xorurl = "safe://hbyyyyd6cuf5m5z3xkxbgdtuo5z9bdqz1x8igdmz6gnnf8gxppfbrzwb4e";
+        let sk = "6b9ea5a0c8c2b6e1b6c0f5cbb0c1fb11f3a6c7b1f2b8cf1f7bb0c1c2d3e4f5a6";
+        let envelope = seal_sk(&owner_sk, &wallet, xorurl, sk)?;
+
+        // a different owner
+        let other_sk = random_sk()?;
+        assert!(matches!(
+            open_sk(&other_sk, &wallet, xorurl, &envelope),
+            Err(Error::DecryptionError(_))
+        ));
+
+        // a different Wallet
+        assert!(matches!(
+            open_sk(&owner_sk, &rand::random::<XorName>(), xorurl, &envelope),
+            Err(Error::DecryptionError(_))
+        ));
+
+        // a different SafeKey XOR-URL
+        assert!(matches!(
+            open_sk(&owner_sk, &wallet, "safe://hbyyyyotherkey", &envelope),
+            Err(Error::DecryptionError(_))
+        ));
+
+        // a tampered ciphertext
+        let mut tampered = envelope.clone();
+        let last = if tampered.ends_with('0') { "1" } else { "0" };
+        tampered.replace_range(tampered.len() - 1.., last);
+        assert!(matches!(
+            open_sk(&owner_sk, &wallet, xorurl, &tampered),
+            Err(Error::DecryptionError(_))
+        ));
+
+        // an unknown envelope version
+        let unknown_version = envelope.replacen("enc1:", "enc9:", 1);
+        assert!(matches!(
+            open_sk(&owner_sk, &wallet, xorurl, &unknown_version),
+            Err(Error::DecryptionError(_))
+        ));
+        Ok(())
+    }
+}
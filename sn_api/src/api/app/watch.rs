@@ -0,0 +1,348 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    fetch::SafeData,
+    safeurl::{SafeDataType, SafeUrl},
+    Safe,
+};
+use crate::{Error, Result};
+use futures::{future, stream, Stream, StreamExt};
+use log::{debug, warn};
+use sn_data_types::MapSeqValue;
+use std::{collections::BTreeMap, time::Duration};
+
+/// Polling settings used when watching content for new versions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WatchOptions {
+    /// Interval between polls right after a new version is found
+    pub interval: Duration,
+    /// Maximum interval between polls, the interval is doubled every time
+    /// no new version is found until it reaches this maximum
+    pub max_interval: Duration,
+    /// Yield the version found on the first poll, rather than only taking it as the
+    /// starting point, so no version is missed between reading the content and watching it
+    pub yield_current: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(30),
+            yield_current: false,
+        }
+    }
+}
+
+// What identifies a version of the content being watched. Maps don't keep a version
+// for their entries as a whole, thus the entries themselves are compared instead.
+#[derive(Debug, PartialEq)]
+enum Revision {
+    Sequence(u64),
+    Map(BTreeMap<Vec<u8>, MapSeqValue>),
+}
+
+struct Watcher {
+    safe: Safe,
+    url: String,
+    safe_url: SafeUrl,
+    options: WatchOptions,
+    interval: Duration,
+    last_revision: Option<Revision>,
+    polled: bool,
+}
+
+impl Safe {
+    /// Watch the content at a URL for new versions, using the default polling settings
+    ///
+    /// See `watch_with_options` for details.
+    pub fn watch(&self, url: &str) -> impl Stream<Item = Result<SafeData>> {
+        self.watch_with_options(url, WatchOptions::default())
+    }
+
+    /// Watch the content at a URL for new versions
+    ///
+    /// The Sequence or Map the URL targets is polled, which can be a FilesContainer, an NRS
+    /// Map Container, a Wallet, or a raw Sequence or Map. An NRS-URL is resolved when the
+    /// watch starts, so the content its NRS name links to is watched, rather than the NRS Map
+    /// Container, and any later change of the link itself is not followed. Every time a new
+    /// version is found, its content is fetched and yielded by the returned stream. The polling
+    /// interval is doubled every time no new version is found, up to `options.max_interval`.
+    ///
+    /// Errors found while polling are yielded as well, and polling continues after them,
+    /// unless the URL cannot be watched at all, in which case the stream ends.
+    pub fn watch_with_options(
+        &self,
+        url: &str,
+        options: WatchOptions,
+    ) -> impl Stream<Item = Result<SafeData>> {
+        let safe = self.clone();
+        let url = url.to_string();
+        stream::once(async move { Watcher::new(safe, &url, options).await }).flat_map(|watcher| {
+            match watcher {
+                Ok(watcher) => stream::unfold(watcher, |mut watcher| async move {
+                    let item = watcher.next_version().await;
+                    Some((item, watcher))
+                })
+                .left_stream(),
+                Err(err) => stream::once(future::ready(Err(err))).right_stream(),
+            }
+        })
+    }
+
+    // Fetch what identifies the current version of a Sequence or Map, bypassing the cache
+    async fn fetch_revision(&self, safe_url: &SafeUrl) -> Result<Revision> {
+        let xorname = safe_url.xorname();
+        let type_tag = safe_url.type_tag();
+        match safe_url.data_type() {
+            SafeDataType::PublicSequence | SafeDataType::PrivateSequence => {
+                let private = safe_url.data_type() == SafeDataType::PrivateSequence;
+                self.safe_client
+                    .forget_sequence_last_entry(xorname, type_tag, private)
+                    .await;
+                let (version, _) = self
+                    .safe_client
+                    .sequence_get_last_entry(xorname, type_tag, private)
+                    .await?;
+                Ok(Revision::Sequence(version))
+            }
+            SafeDataType::SeqMap => {
+                let entries = self.safe_client.list_map_entries(xorname, type_tag).await?;
+                Ok(Revision::Map(entries))
+            }
+            other => Err(Error::InvalidInput(format!(
+                "Content stored as {} cannot be watched as it's not mutable",
+                other
+            ))),
+        }
+    }
+}
+
+impl Watcher {
+    async fn new(safe: Safe, url: &str, options: WatchOptions) -> Result<Self> {
+        if Safe::parse_url(url)?.content_version().is_some() {
+            return Err(Error::InvalidInput(format!(
+                "The URL cannot contain a version as it could never change: {}",
+                url
+            )));
+        }
+
+        // An NRS-URL is resolved so we watch the content its NRS name links to. The link
+        // may be to a specific version of it, but it's its new versions we are after.
+        let (mut safe_url, _) = safe.parse_and_resolve_url(url).await?;
+        safe_url.set_content_version(None);
+        let data_type = safe_url.data_type();
+        if !matches!(
+            data_type,
+            SafeDataType::PublicSequence | SafeDataType::PrivateSequence | SafeDataType::SeqMap
+        ) {
+            return Err(Error::InvalidInput(format!(
+                "Content stored as {} cannot be watched as it's not mutable: {}",
+                data_type, url
+            )));
+        }
+
+        Ok(Self {
+            safe,
+            url: url.to_string(),
+            safe_url,
+            options,
+            interval: options.interval,
+            last_revision: None,
+            polled: false,
+        })
+    }
+
+    // Poll until a version different from the last one seen is found, and fetch the content
+    // at the resolved URL. The first version found is only taken as the starting point, unless it was
+    // requested to be yielded as well.
+    async fn next_version(&mut self) -> Result<SafeData> {
+        loop {
+            if self.polled {
+                tokio::time::sleep(self.interval).await;
+            }
+            self.polled = true;
+
+            let revision = match self.safe.fetch_revision(&self.safe_url).await {
+                Ok(revision) => revision,
+                Err(err) => {
+                    warn!("Failed to poll {} for new versions: {}", self.url, err);
+                    self.back_off();
+                    return Err(err);
+                }
+            };
+
+            match &self.last_revision {
+                Some(last) if *last == revision => self.back_off(),
+                Some(_) => {
+                    debug!("New version found for {}: {:?}", self.url, revision);
+                    self.last_revision = Some(revision);
+                    self.interval = self.options.interval;
+                    return self.safe.fetch(&self.safe_url.to_string(), None).await;
+                }
+                None => {
+                    self.last_revision = Some(revision);
+                    if self.options.yield_current {
+                        return self.safe.fetch(&self.safe_url.to_string(), None).await;
+                    }
+                }
+            }
+        }
+    }
+
+    fn back_off(&mut self) {
+        self.interval = std::cmp::min(self.interval * 2, self.options.max_interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{api::app::test_helpers::new_safe_instance, retry_loop};
+    use anyhow::{anyhow, bail, Result};
+    use futures::StreamExt;
+    use rand::{distributions::Alphanumeric, thread_rng, Rng};
+
+    fn fast_polling() -> WatchOptions {
+        WatchOptions {
+            interval: Duration::from_millis(100),
+            max_interval: Duration::from_millis(400),
+            yield_current: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_sequence() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.sequence_create(b"v0", None, 25_000, false).await?;
+        let _ = retry_loop!(safe.sequence_get(&xorurl));
+
+        let mut watch = Box::pin(safe.watch_with_options(&xorurl, fast_polling()));
+        let mut writer = safe.clone();
+        let xorurl_clone = xorurl.clone();
+        let _ = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            writer.append_to_sequence(&xorurl_clone, b"v1").await
+        });
+
+        match watch.next().await {
+            Some(Ok(SafeData::PublicSequence { version, data, .. })) => {
+                assert_eq!(version, 1);
+                assert_eq!(data, b"v1".to_vec());
+                Ok(())
+            }
+            other => Err(anyhow!("Unexpected event from watch: {:?}", other)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_sequence_yield_current() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.sequence_create(b"v0", None, 25_000, false).await?;
+        let _ = retry_loop!(safe.sequence_get(&xorurl));
+
+        let options = WatchOptions {
+            yield_current: true,
+            ..fast_polling()
+        };
+        let mut watch = Box::pin(safe.watch_with_options(&xorurl, options));
+        match watch.next().await {
+            Some(Ok(SafeData::PublicSequence { version, data, .. })) => {
+                assert_eq!(version, 0);
+                assert_eq!(data, b"v0".to_vec());
+            }
+            other => bail!("Unexpected event from watch: {:?}", other),
+        }
+
+        safe.append_to_sequence(&xorurl, b"v1").await?;
+        match watch.next().await {
+            Some(Ok(SafeData::PublicSequence { version, .. })) => {
+                assert_eq!(version, 1);
+                Ok(())
+            }
+            other => Err(anyhow!("Unexpected event from watch: {:?}", other)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_sequence_nrs_url() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.sequence_create(b"v0", None, 25_000, false).await?;
+        let _ = retry_loop!(safe.sequence_get(&xorurl));
+
+        let site_name: String = thread_rng().sample_iter(&Alphanumeric).take(15).collect();
+        let mut safe_url = SafeUrl::from_url(&xorurl)?;
+        safe_url.set_content_version(Some(0));
+        let _ = safe
+            .nrs_map_container_create(&site_name, &safe_url.to_string(), true, true, false)
+            .await?;
+        let nrs_url = format!("safe://{}", site_name);
+        let _ = retry_loop!(safe.fetch(&nrs_url, None));
+
+        // the Sequence the NRS name links to is watched, rather than its NRS Map Container
+        let options = WatchOptions {
+            yield_current: true,
+            ..fast_polling()
+        };
+        let mut watch = Box::pin(safe.watch_with_options(&nrs_url, options));
+        match watch.next().await {
+            Some(Ok(SafeData::PublicSequence { version, .. })) => assert_eq!(version, 0),
+            other => bail!("Unexpected event from watch: {:?}", other),
+        }
+
+        safe.append_to_sequence(&xorurl, b"v1").await?;
+        match watch.next().await {
+            Some(Ok(SafeData::PublicSequence { version, data, .. })) => {
+                assert_eq!(version, 1);
+                assert_eq!(data, b"v1".to_vec());
+                Ok(())
+            }
+            other => Err(anyhow!("Unexpected event from watch: {:?}", other)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_map() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.map_create(None, 30_000).await?;
+        let _ = retry_loop!(safe.map_list(&xorurl));
+
+        let mut watch = Box::pin(safe.watch_with_options(&xorurl, fast_polling()));
+        let mut writer = safe.clone();
+        let xorurl_clone = xorurl.clone();
+        let _ = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            writer.map_insert(&xorurl_clone, b"key", b"value").await
+        });
+
+        match watch.next().await {
+            Some(Ok(SafeData::Map { entries, .. })) => {
+                assert_eq!(entries.len(), 1);
+                assert_eq!(entries[0].key, b"key".to_vec());
+                Ok(())
+            }
+            other => Err(anyhow!("Unexpected event from watch: {:?}", other)),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_invalid_url() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let xorurl = safe.sequence_create(b"v0", None, 25_000, false).await?;
+
+        let mut watch = Box::pin(safe.watch(&format!("{}?v=0", xorurl)));
+        match watch.next().await {
+            Some(Err(Error::InvalidInput(_))) => {}
+            other => bail!("Unexpected event from watch: {:?}", other),
+        }
+        assert!(watch.next().await.is_none());
+        Ok(())
+    }
+}
@@ -60,6 +60,19 @@ pub fn ed_sk_from_hex(hex_str: &str) -> Result<ed25519_dalek::SecretKey> {
     })
 }
 
+// Get the PublicKey of a SafeKey from its XorName. We assume Ed25519 key for now,
+// which is 32 bytes long, just like a xorname.
+// TODO: support for BLS keys which are longer.
+pub fn ed_pk_from_xorname(xorname: xor_name::XorName) -> Result<sn_data_types::PublicKey> {
+    let pk = ed25519_dalek::PublicKey::from_bytes(xorname.as_ref()).map_err(|err| {
+        Error::NetDataError(format!(
+            "Failed to derive Ed25519 PublicKey from Xorname '{}': {:?}",
+            xorname, err
+        ))
+    })?;
+    Ok(pk.into())
+}
+
 // Get hex string of a SecretKey
 pub fn sk_to_hex(sk: sn_data_types::SecretKey) -> String {
     match sk {
@@ -60,6 +60,12 @@ pub enum Error {
     /// VersionNotFound
     #[error("VersionNotFound: {0}")]
     VersionNotFound(String),
+    /// VersionConflict
+    #[error("VersionConflict: {0}")]
+    VersionConflict(String),
+    /// KvConflict
+    #[error("KvConflict: {0}")]
+    KvConflict(KvConflict),
     /// EntryNotFound
     #[error("EntryNotFound: {0}")]
     EntryNotFound(String),
@@ -87,4 +93,50 @@ pub enum Error {
     /// FileSystemError
     #[error("FileSystemError: {0}")]
     FileSystemError(String),
+    /// DecryptionError
+    #[error("DecryptionError: {0}")]
+    DecryptionError(String),
+    /// InvalidSignature
+    #[error("InvalidSignature: {0}")]
+    InvalidSignature(String),
+}
+
+/// Conflict found when writing or deleting a document of a `KvStore`
+#[derive(Debug, Clone, PartialEq)]
+pub enum KvConflict {
+    /// The document is not at the version the caller expected
+    Version {
+        key: String,
+        /// Version the document was expected to be at, `None` if it was expected not to exist
+        expected: Option<u64>,
+        /// Version the document is currently at, `None` if it doesn't exist
+        current: Option<u64>,
+    },
+    /// The content of the document is not the one the caller expected
+    Content {
+        key: String,
+        /// Version the document is currently at, `None` if it doesn't exist
+        current: Option<u64>,
+    },
+}
+
+impl std::fmt::Display for KvConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (key, current) = match self {
+            Self::Version { key, current, .. } | Self::Content { key, current } => (key, current),
+        };
+        write!(f, "Document '{}' ", key)?;
+        match current {
+            Some(version) => write!(f, "is at version {}", version)?,
+            None => write!(f, "doesn't exist")?,
+        }
+        match self {
+            Self::Version {
+                expected: Some(version),
+                ..
+            } => write!(f, " but version {} was expected", version),
+            Self::Version { expected: None, .. } => write!(f, " but it was expected not to exist"),
+            Self::Content { .. } => write!(f, " and its content is not the expected one"),
+        }
+    }
 }
@@ -8,17 +8,21 @@
 // Software.
 
 use crate::{
-    operations::safe_net::connect,
+    operations::{
+        config::{active_profile, read_cache_dir_setting, Profile, PROFILE_ENV_VAR},
+        safe_net::connect,
+    },
     shell,
     subcommands::{
-        auth::auth_commander, cat::cat_commander, config::config_commander, dog::dog_commander,
-        files::files_commander, keys::key_commander, networks::networks_commander,
-        node::node_commander, nrs::nrs_commander, seq::seq_commander, setup::setup_commander,
-        update::update_commander, wallet::wallet_commander, xorurl::xorurl_commander, OutputFmt,
+        auth::auth_commander, cache::cache_commander, cat::cat_commander, config::config_commander,
+        dog::dog_commander, files::files_commander, keys::key_commander, map::map_commander,
+        networks::networks_commander, node::node_commander, nrs::nrs_commander, pay::pay_commander,
+        seq::seq_commander, setup::setup_commander, update::update_commander,
+        wallet::wallet_commander, watch::watch_commander, xorurl::xorurl_commander, OutputFmt,
         SubCommands,
     },
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use log::debug;
 use sn_api::{safeurl::XorUrlBase, Safe};
 use structopt::{clap::AppSettings::ColoredHelp, StructOpt};
@@ -30,7 +34,7 @@ pub struct CmdArgs {
     /// subcommands
     #[structopt(subcommand)]
     pub cmd: Option<SubCommands>,
-    /// Output data serialisation: [json, jsoncompact, yaml]
+    /// Output data serialisation: [json, jsoncompact, yaml, csv]. The csv format is only supported by the history and batch transfer commands
     #[structopt(short = "o", long = "output", global(true))]
     output_fmt: Option<OutputFmt>,
     /// Sets JSON as output serialisation format (alias of '--output json')
@@ -42,12 +46,15 @@ pub struct CmdArgs {
     /// Dry run of command. No data will be written. No coins spent
     #[structopt(short = "n", long = "dry-run", global(true))]
     dry: bool,
-    /// Base encoding to be used for XOR-URLs generated. Currently supported: base32z (default), base32 and base64
+    /// Base encoding to be used for XOR-URLs generated. Currently supported: base32z (default), base32, base64, base58btc, base36, base16 and base64url
     #[structopt(long = "xorurl", global(true))]
     xorurl_base: Option<XorUrlBase>,
     /// Endpoint of the Authenticator daemon where to send requests to. If not provided, https://localhost:33000 is assumed.
     #[structopt(long = "endpoint", global(true))]
     pub endpoint: Option<String>,
+    /// The profile to use, i.e. its credentials, network, and default settings. It can also be set with the SAFE_PROFILE env var
+    #[structopt(long = "profile", global(true))]
+    profile: Option<String>,
 }
 
 pub async fn run() -> Result<()> {
@@ -62,21 +69,64 @@ pub async fn run_with(cmd_args: Option<&[&str]>, safe: &mut Safe) -> Result<()>
         Some(cmd_args) => CmdArgs::from_iter_safe(cmd_args)?,
     };
 
+    // The profile selected is made available to the rest of the commands through the env var
+    let prev_profile = std::env::var(PROFILE_ENV_VAR).ok();
+    if let Some(name) = &args.profile {
+        std::env::set_var(PROFILE_ENV_VAR, name);
+    }
+
+    let result = run_cmd(args, safe).await;
+
+    match prev_profile {
+        Some(name) => std::env::set_var(PROFILE_ENV_VAR, name),
+        None => std::env::remove_var(PROFILE_ENV_VAR),
+    }
+    result
+}
+
+async fn run_cmd(args: CmdArgs, safe: &mut Safe) -> Result<()> {
+    // Settings of the active profile are used unless they are provided as arguments.
+    // Config commands don't need them, and they are used to manage the profiles themselves.
+    let profile = match args.cmd {
+        Some(SubCommands::Config { .. }) => Profile::default(),
+        _ => match active_profile()? {
+            Some((name, profile)) => {
+                debug!("Using profile '{}': {:?}", name, profile);
+                profile
+            }
+            None => Profile::default(),
+        },
+    };
+
     let prev_base = safe.xorurl_base;
     if let Some(base) = args.xorurl_base {
         safe.xorurl_base = base;
+    } else if let Some(base) = &profile.xorurl_base {
+        safe.xorurl_base = base
+            .parse()
+            .map_err(|err| anyhow!("Invalid XOR-URL base in profile: {}", err))?;
     }
 
     let output_fmt = if args.output_json {
         OutputFmt::Json
     } else {
-        match args.output_fmt {
-            Some(fmt) => fmt,
-            None => OutputFmt::Pretty,
+        match (args.output_fmt, &profile.output_fmt) {
+            (Some(fmt), _) => fmt,
+            (None, Some(fmt)) => fmt
+                .parse()
+                .map_err(|err| anyhow!("Invalid output format in profile: {}", err))?,
+            (None, None) => OutputFmt::Pretty,
         }
     };
 
-    debug!("Processing command: {:?}", args);
+    if OutputFmt::Csv == output_fmt && !matches!(&args.cmd, Some(cmd) if cmd.supports_csv_output())
+    {
+        bail!("The 'csv' output format is only supported by the 'keys history', 'wallet history', and 'wallet transfer --batch' commands");
+    }
+
+    let endpoint = args.endpoint.or(profile.authd_endpoint);
+
+    debug!("Processing command: {:?}", args.cmd);
 
     let result = match args.cmd {
         Some(SubCommands::Config { cmd }) => config_commander(cmd).await,
@@ -99,7 +149,8 @@ pub async fn run_with(cmd_args: Option<&[&str]>, safe: &mut Safe) -> Result<()>
             follow_links,
         }) => xorurl_commander(cmd, location, recursive, follow_links, output_fmt, safe).await,
         Some(SubCommands::Node { cmd }) => node_commander(cmd).await,
-        Some(SubCommands::Auth { cmd }) => auth_commander(cmd, args.endpoint, safe).await,
+        Some(SubCommands::Auth { cmd }) => auth_commander(cmd, endpoint, safe).await,
+        Some(SubCommands::Cache { cmd }) => cache_commander(cmd, output_fmt, safe).await,
         Some(other) => {
             // We treat these commands separatelly since we use the credentials if they are
             // available to connect to the network with them (unless dry-run was set),
@@ -109,14 +160,22 @@ pub async fn run_with(cmd_args: Option<&[&str]>, safe: &mut Safe) -> Result<()>
                 connect(safe).await?;
             }
 
+            // Content fetched is also kept on disk if the cache was enabled in the config
+            if let Some(cache_dir) = read_cache_dir_setting() {
+                safe.set_cache_dir(Some(cache_dir.as_path())).await?;
+            }
+
             match other {
                 SubCommands::Keys(cmd) => key_commander(cmd, output_fmt, safe).await,
                 SubCommands::Cat(cmd) => cat_commander(cmd, output_fmt, safe).await,
                 SubCommands::Dog(cmd) => dog_commander(cmd, output_fmt, safe).await,
+                SubCommands::Watch(cmd) => watch_commander(cmd, output_fmt, safe).await,
                 SubCommands::Wallet(cmd) => wallet_commander(cmd, output_fmt, safe).await,
                 SubCommands::Files(cmd) => files_commander(cmd, output_fmt, args.dry, safe).await,
                 SubCommands::Nrs(cmd) => nrs_commander(cmd, output_fmt, args.dry, safe).await,
                 SubCommands::Seq(cmd) => seq_commander(cmd, output_fmt, safe).await,
+                SubCommands::Map(cmd) => map_commander(cmd, output_fmt, safe).await,
+                SubCommands::Pay(cmd) => pay_commander(cmd, output_fmt, safe).await,
                 _ => Err(anyhow!("Unknown safe subcommand")),
             }
         }
@@ -7,21 +7,42 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use super::config::read_current_network_conn_info;
+use super::{
+    config::{active_profile_name, profile_data_path, read_network_conn_info},
+    keystore::{
+        cache_password, forget_password, get_password, no_password_msg, wipe_file,
+        write_private_file, EncryptedSecret, PasswordOf,
+    },
+};
 use crate::{APP_ID, APP_NAME, APP_VENDOR};
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
 use sn_api::{Keypair, Safe};
 use std::{
     fs::{create_dir_all, File},
-    io::{Read, Write},
+    io::Read,
     path::PathBuf,
 };
 
-const AUTH_CREDENTIALS_FILENAME: &str = "credentials";
+pub const AUTH_CREDENTIALS_FILENAME: &str = "credentials";
+
+// Associated data used when encrypting the credentials
+const AUTH_CREDENTIALS_AAD: &str = "sn_cli/credentials";
+
+// Format of the credentials file, the keypair can be stored either encrypted with a password,
+// or in plaintext as done by previous versions or when the user chooses not to set a password
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StoredCredentials {
+    Encrypted { encrypted_keypair: EncryptedSecret },
+    Plaintext(Keypair),
+}
 
 pub async fn authorise_cli(endpoint: Option<String>, is_self_authing: bool) -> Result<()> {
-    let (mut file, file_path) = create_credentials_file()?;
+    // make sure we can write the credentials before sending the request
+    let (credentials_folder, _) = get_credentials_file_path()?;
+    create_dir_all(credentials_folder).context("Couldn't create project's local data folder")?;
     println!("Authorising CLI application...");
     if !is_self_authing {
         println!("Note you can use this CLI from another console to authorise it with 'auth allow' command. Alternativelly, you can also use '--self-auth' flag with 'auth unlock' command to automatically self authorise the CLI app.");
@@ -31,11 +52,7 @@ pub async fn authorise_cli(endpoint: Option<String>, is_self_authing: bool) -> R
         .await
         .context("Application authorisation failed")?;
 
-    let serialised_keypair = serde_json::to_string(&app_keypair)
-        .context("Unable to serialise the credentials obtained")?;
-
-    file.write_all(serialised_keypair.as_bytes())
-        .with_context(|| format!("Unable to write credentials in {}", file_path.display(),))?;
+    let file_path = write_credentials(&app_keypair)?;
 
     println!("Safe CLI app was successfully authorised");
     println!("Credentials were stored in {}", file_path.display());
@@ -46,21 +63,19 @@ pub async fn authorise_cli(endpoint: Option<String>, is_self_authing: bool) -> R
 // otherwise it creates a read only connection.
 // Returns the app's keypair if connection was succesfully made with credentials,
 // otherwise it returns 'None' if conneciton is read only.
+// If the credentials are encrypted and cannot be decrypted, e.g. the password
+// entered is wrong, an error is returned rather than connecting with read-only access.
 pub async fn connect(safe: &mut Safe) -> Result<Option<Keypair>> {
     debug!("Connecting...");
 
-    let app_keypair = if let Ok((_, keypair)) = read_credentials() {
-        keypair
-    } else {
-        None
-    };
+    let (_, app_keypair) = read_credentials()?;
 
     let found_app_keypair = app_keypair.is_some();
     if !found_app_keypair {
         info!("No credentials found for CLI, connecting with read-only access...");
     }
 
-    let (_, bootstrap_contacts) = read_current_network_conn_info()?;
+    let bootstrap_contacts = read_network_conn_info().await?;
     let client_cfg = client_config_path();
     match safe
         .connect(
@@ -70,8 +85,9 @@ pub async fn connect(safe: &mut Safe) -> Result<Option<Keypair>> {
         )
         .await
     {
-        Err(_) if found_app_keypair => {
-            warn!("Credentials found for CLI are invalid, connecting with read-only access...");
+        Err(err) if found_app_keypair => {
+            warn!("Failed to connect with the CLI credentials: {}", err);
+            eprintln!("Credentials found for CLI are invalid, connecting with read-only access...");
             safe.connect(None, None, Some(bootstrap_contacts))
                 .await
                 .context("Failed to connect with read-only access")?;
@@ -83,17 +99,44 @@ pub async fn connect(safe: &mut Safe) -> Result<Option<Keypair>> {
     }
 }
 
-pub fn create_credentials_file() -> Result<(File, PathBuf)> {
+// Store the keypair to be used by CLI, encrypting it with a password unless the user
+// chooses not to set one, returning the path of the credentials file
+pub fn write_credentials(keypair: &Keypair) -> Result<PathBuf> {
     let (credentials_folder, file_path) = get_credentials_file_path()?;
     if !credentials_folder.exists() {
         println!("Creating '{}' folder", credentials_folder.display());
         create_dir_all(credentials_folder)
             .context("Couldn't create project's local data folder")?;
     }
-    let file = File::create(&file_path)
-        .with_context(|| format!("Unable to open credentials file at {}", file_path.display()))?;
 
-    Ok((file, file_path))
+    let serialised_keypair =
+        serde_json::to_string(keypair).context("Unable to serialise the credentials")?;
+    let credentials = match get_password(
+        PasswordOf::Credentials,
+        "Enter a password to encrypt the credentials with (leave empty to store them unencrypted): ",
+        true,
+    )? {
+        Some(password) => {
+            let encrypted_keypair = EncryptedSecret::seal(
+                &password,
+                AUTH_CREDENTIALS_AAD,
+                serialised_keypair.as_bytes(),
+            )?;
+            cache_password(PasswordOf::Credentials, &password);
+            StoredCredentials::Encrypted { encrypted_keypair }
+        }
+        None => {
+            warn!("No password was provided, credentials are stored unencrypted");
+            StoredCredentials::Plaintext(keypair.clone())
+        }
+    };
+
+    let serialised_credentials =
+        serde_json::to_string(&credentials).context("Unable to serialise the credentials")?;
+    write_private_file(&file_path, serialised_credentials.as_bytes())
+        .with_context(|| format!("Unable to write credentials in {}", file_path.display()))?;
+
+    Ok(file_path)
 }
 
 pub fn read_credentials() -> Result<(PathBuf, Option<Keypair>)> {
@@ -104,13 +147,14 @@ pub fn read_credentials() -> Result<(PathBuf, Option<Keypair>)> {
         match file.read_to_string(&mut credentials) {
             Ok(_) if credentials.is_empty() => None,
             Ok(_) => {
-                let keypair = serde_json::from_str(&credentials).with_context(|| {
-                    format!(
-                        "Unable to parse the credentials read from {}",
-                        file_path.display(),
-                    )
-                })?;
-                Some(keypair)
+                let stored: StoredCredentials =
+                    serde_json::from_str(&credentials).with_context(|| {
+                        format!(
+                            "Unable to parse the credentials read from {}",
+                            file_path.display(),
+                        )
+                    })?;
+                Some(decrypt_credentials(stored)?)
             }
             Err(err) => {
                 debug!(
@@ -129,7 +173,8 @@ pub fn read_credentials() -> Result<(PathBuf, Option<Keypair>)> {
 }
 
 pub fn clear_credentials() -> Result<()> {
-    let (_, file_path) = create_credentials_file().context("Failed to clear credentials")?;
+    let (_, file_path) = get_credentials_file_path()?;
+    wipe_file(&file_path).context("Failed to clear credentials")?;
 
     println!(
         "Credentials were succesfully cleared from {}",
@@ -140,14 +185,35 @@ pub fn clear_credentials() -> Result<()> {
 
 // Private helpers
 
-fn get_credentials_file_path() -> Result<(PathBuf, PathBuf)> {
-    let mut project_data_path =
-        dirs_next::home_dir().ok_or_else(|| anyhow!("Failed to obtain user's home path"))?;
-
-    project_data_path.push(".safe");
-    project_data_path.push("cli");
+fn decrypt_credentials(stored: StoredCredentials) -> Result<Keypair> {
+    match stored {
+        StoredCredentials::Plaintext(keypair) => Ok(keypair),
+        StoredCredentials::Encrypted { encrypted_keypair } => {
+            let password = get_password(
+                PasswordOf::Credentials,
+                "Enter the password to decrypt the credentials: ",
+                false,
+            )?
+            .ok_or_else(|| anyhow!(no_password_msg("the CLI credentials")))?;
+            let serialised_keypair = match encrypted_keypair.open(&password, AUTH_CREDENTIALS_AAD) {
+                Ok(serialised_keypair) => {
+                    cache_password(PasswordOf::Credentials, &password);
+                    serialised_keypair
+                }
+                Err(_) => {
+                    forget_password(PasswordOf::Credentials);
+                    bail!("Wrong password, failed to decrypt the CLI credentials");
+                }
+            };
+            serde_json::from_slice(&serialised_keypair)
+                .context("Unable to parse the decrypted credentials")
+        }
+    }
+}
 
-    let credentials_folder = project_data_path;
+// Each profile has its own credentials, kept in the profile's data folder
+fn get_credentials_file_path() -> Result<(PathBuf, PathBuf)> {
+    let credentials_folder = profile_data_path(active_profile_name().as_deref())?;
 
     let file_path = credentials_folder.join(AUTH_CREDENTIALS_FILENAME);
     Ok((credentials_folder, file_path))
@@ -0,0 +1,229 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+// Batch of transfers read from a CSV or JSON file, and the journal kept alongside it
+// which records the outcome of each transfer, so the batch can be safely re-run
+// after an interruption or a failure without paying anyone twice.
+
+use anyhow::{anyhow, bail, Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+use xor_name::XorName;
+
+const JOURNAL_EXTENSION: &str = "journal";
+const RESULTS_SUFFIX: &str = "results";
+
+// A transfer as listed in the batch file
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct BatchTransfer {
+    pub to: String,
+    pub amount: String,
+    #[serde(default)]
+    pub memo: Option<String>,
+}
+
+// Format of the batch file, which is also used for the results file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BatchFormat {
+    Csv,
+    Json,
+}
+
+impl BatchFormat {
+    // The format is realised from the file extension, CSV being the default
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(OsStr::to_str) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Self::Json,
+            _ => Self::Csv,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+// Read the list of transfers from a batch file, i.e. a CSV file with a header row
+// with 'to', 'amount', and optionally 'memo' columns, or a JSON array of objects with those fields
+pub fn read_batch_file(path: &Path) -> Result<Vec<BatchTransfer>> {
+    let content = fs::read(path)
+        .with_context(|| format!("Failed to read batch file '{}'", path.display()))?;
+
+    let transfers: Vec<BatchTransfer> = match BatchFormat::from_path(path) {
+        BatchFormat::Json => serde_json::from_slice(&content)
+            .with_context(|| format!("Failed to parse JSON batch file '{}'", path.display()))?,
+        BatchFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(content.as_slice());
+            reader
+                .deserialize()
+                .enumerate()
+                .map(|(i, record)| {
+                    record.with_context(|| {
+                        format!(
+                            "Failed to parse row {} of CSV batch file '{}'",
+                            i + 1,
+                            path.display()
+                        )
+                    })
+                })
+                .collect::<Result<_>>()?
+        }
+    };
+
+    if transfers.is_empty() {
+        bail!("No transfers found in batch file '{}'", path.display());
+    }
+
+    Ok(transfers)
+}
+
+// Default location of the results file, next to the batch file, e.g. 'payouts.results.csv'
+pub fn default_results_path(batch_path: &Path) -> PathBuf {
+    let format = BatchFormat::from_path(batch_path);
+    let stem = batch_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default();
+    batch_path.with_file_name(format!(
+        "{}.{}.{}",
+        stem,
+        RESULTS_SUFFIX,
+        format.extension()
+    ))
+}
+
+// Outcome of a transfer, as recorded in the results file
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    // The transfer was made in this run
+    Done,
+    // The transfer was already made in a previous run
+    Skipped,
+    // The transfer failed, it will be retried when re-running the batch
+    Failed,
+    // A previous run was interrupted while the transfer was being made
+    Unknown,
+}
+
+// Result of a transfer of the batch, rows are numbered from 1 as listed in the batch file
+#[derive(Serialize, Debug, Clone)]
+pub struct BatchResult {
+    pub row: usize,
+    pub to: String,
+    pub amount: String,
+    pub memo: Option<String>,
+    pub status: BatchStatus,
+    pub tx_id: Option<u64>,
+    pub error: Option<String>,
+}
+
+// State of a transfer as recorded in the journal
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum JournalEntry {
+    // The transfer is being made, if found when loading the journal
+    // the previous run was interrupted and its outcome is unknown
+    Pending,
+    Done { tx_id: u64 },
+    Failed { error: String },
+}
+
+// Journal of the transfers of a batch made from a source Wallet, it's kept next
+// to the batch file and it's written every time the state of a transfer changes
+#[derive(Debug)]
+pub struct BatchJournal {
+    file_path: PathBuf,
+    entries: BTreeMap<String, JournalEntry>,
+}
+
+impl BatchJournal {
+    pub fn load(batch_path: &Path) -> Result<Self> {
+        let mut file_name = batch_path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".");
+        file_name.push(JOURNAL_EXTENSION);
+        let file_path = batch_path.with_file_name(file_name);
+
+        let entries = if file_path.exists() {
+            let content = fs::read(&file_path)
+                .with_context(|| format!("Failed to read journal '{}'", file_path.display()))?;
+            serde_json::from_slice(&content)
+                .with_context(|| format!("Failed to parse journal '{}'", file_path.display()))?
+        } else {
+            BTreeMap::default()
+        };
+        debug!(
+            "Batch journal loaded from '{}': {:?}",
+            file_path.display(),
+            entries
+        );
+
+        Ok(Self { file_path, entries })
+    }
+
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    pub fn get(&self, id: &str) -> Option<&JournalEntry> {
+        self.entries.get(id)
+    }
+
+    // Record the new state of a transfer, and persist the whole journal
+    pub fn record(&mut self, id: &str, entry: JournalEntry) -> Result<()> {
+        let _ = self.entries.insert(id.to_string(), entry);
+
+        // write it to a temporary file first so the journal is never left half written
+        let serialised = serde_json::to_string_pretty(&self.entries)
+            .context("Failed to serialise the batch journal")?;
+        let tmp_path = self.file_path.with_extension("tmp");
+        fs::write(&tmp_path, serialised)
+            .with_context(|| format!("Failed to write journal '{}'", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.file_path).map_err(|err| {
+            anyhow!(
+                "Failed to write journal '{}': {}",
+                self.file_path.display(),
+                err
+            )
+        })
+    }
+}
+
+// Identifier of a transfer in the journal, derived from the spendable balance it's debited
+// from and the transfer's content, thus editing a row makes it a different transfer. The
+// source is identified by the XorName of its SafeKey, so it's the same whichever URL the
+// Wallet is referenced with. Rows with identical content are told apart by their occurrence,
+// i.e. the number of identical rows listed before them, so rows can be inserted or reordered
+// in the batch file without changing the existing ids.
+pub fn journal_entry_id(source: &XorName, transfer: &BatchTransfer, occurrence: usize) -> String {
+    let mut hasher = Sha3_256::new();
+    hasher.update(source.0);
+    hasher.update(transfer.to.as_bytes());
+    hasher.update([0]);
+    hasher.update(transfer.amount.as_bytes());
+    hasher.update([0]);
+    if let Some(memo) = &transfer.memo {
+        hasher.update(memo.as_bytes());
+    }
+    hasher.update([0]);
+    hasher.update((occurrence as u64).to_be_bytes());
+    hex::encode(hasher.finalize())
+}
@@ -21,6 +21,11 @@ use std::{
 
 const CONFIG_FILENAME: &str = "config.json";
 const CONFIG_NETWORKS_DIRNAME: &str = "networks";
+const CONFIG_CACHE_DIRNAME: &str = "cache";
+const CONFIG_PROFILES_DIRNAME: &str = "profiles";
+
+// Environment variable which selects the profile to use, it's also set by the '--profile' argument
+pub const PROFILE_ENV_VAR: &str = "SAFE_PROFILE";
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub enum NetworkInfo {
@@ -51,10 +56,34 @@ impl NetworkInfo {
     }
 }
 
+/// A bundle of settings used when the profile is selected, each of them overriding
+/// the corresponding default, while still being overridable with the command arguments
+#[derive(Deserialize, Debug, Serialize, Clone, Default)]
+pub struct Profile {
+    /// Name of the network in the config to connect to, rather than the current network
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    /// Endpoint of the Authenticator daemon where to send requests to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authd_endpoint: Option<String>,
+    /// Base encoding to be used for XOR-URLs generated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub xorurl_base: Option<String>,
+    /// Output data serialisation format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_fmt: Option<String>,
+}
+
 #[derive(Deserialize, Debug, Serialize, Default)]
 pub struct Settings {
     networks: BTreeMap<String, NetworkInfo>,
     // contacts: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cache_dir: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profiles: BTreeMap<String, Profile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    current_profile: Option<String>,
 }
 
 #[derive(Debug)]
@@ -184,6 +213,71 @@ impl Config {
         Ok(())
     }
 
+    pub fn cache_dir(&self) -> Option<&PathBuf> {
+        self.settings.cache_dir.as_ref()
+    }
+
+    pub fn set_cache_dir(&mut self, dir: Option<PathBuf>) -> Result<()> {
+        self.settings.cache_dir = dir;
+        self.write_settings_to_file()?;
+        debug!(
+            "On-disk cache location set in config: {:?}",
+            self.settings.cache_dir
+        );
+        Ok(())
+    }
+
+    pub fn profiles_iter(&self) -> impl Iterator<Item = (&String, &Profile)> {
+        self.settings.profiles.iter()
+    }
+
+    pub fn current_profile(&self) -> Option<&String> {
+        self.settings.current_profile.as_ref()
+    }
+
+    pub fn add_profile(&mut self, name: &str, profile: Profile) -> Result<()> {
+        validate_profile_name(name)?;
+        if let Some(network) = &profile.network {
+            if !self.settings.networks.contains_key(network) {
+                bail!("No network with name '{}' was found in the config. Please use the networks 'add'/'set' subcommand to add it", network);
+            }
+        }
+
+        self.settings.profiles.insert(name.to_string(), profile);
+        self.write_settings_to_file()?;
+        debug!("Profile '{}' added to settings", name);
+        Ok(())
+    }
+
+    // Set the profile to use when none is selected with '--profile' or the SAFE_PROFILE
+    // env var, or go back to not using any profile if no name is provided
+    pub fn use_profile(&mut self, name: Option<&str>) -> Result<()> {
+        if let Some(name) = name {
+            if !self.settings.profiles.contains_key(name) {
+                bail!("No profile with name '{}' was found in the config", name);
+            }
+        }
+
+        self.settings.current_profile = name.map(|name| name.to_string());
+        self.write_settings_to_file()?;
+        debug!("Current profile set to: {:?}", name);
+        Ok(())
+    }
+
+    // Remove a profile from the config, returning the folder where its data was kept
+    pub fn remove_profile(&mut self, name: &str) -> Result<PathBuf> {
+        if self.settings.profiles.remove(name).is_none() {
+            bail!("No profile with name '{}' was found in the config", name);
+        }
+        if self.settings.current_profile.as_deref() == Some(name) {
+            self.settings.current_profile = None;
+        }
+
+        self.write_settings_to_file()?;
+        debug!("Profile '{}' removed from config", name);
+        profile_data_path(Some(name))
+    }
+
     pub fn clear(&mut self) -> Result<()> {
         self.settings = Settings::default();
         self.write_settings_to_file()
@@ -233,6 +327,32 @@ impl Config {
         table.printstd();
     }
 
+    pub fn print_profiles(&self) {
+        let mut table = Table::new();
+        table.add_row(row![bFg->"Profiles"]);
+        table.add_row(
+            row![bFg->"Current", bFg->"Name", bFg->"Network", bFg->"Authd endpoint", bFg->"XOR-URL base", bFg->"Output format"],
+        );
+        let active_profile = active_profile_name();
+        for (name, profile) in self.profiles_iter() {
+            let current = if active_profile.as_ref() == Some(name) {
+                "*"
+            } else {
+                ""
+            };
+            table.add_row(row![
+                current,
+                name,
+                profile.network.as_deref().unwrap_or(""),
+                profile.authd_endpoint.as_deref().unwrap_or(""),
+                profile.xorurl_base.as_deref().unwrap_or(""),
+                profile.output_fmt.as_deref().unwrap_or(""),
+            ]);
+        }
+
+        table.printstd();
+    }
+
     // Private helpers
 
     fn write_settings_to_file(&self) -> Result<()> {
@@ -275,6 +395,106 @@ pub fn read_current_network_conn_info() -> Result<(PathBuf, HashSet<SocketAddr>)
     Ok((file_path, contacts))
 }
 
+// Read the on-disk cache location from the config file, without creating
+// the config file if it doesn't exist yet
+pub fn read_cache_dir_setting() -> Option<PathBuf> {
+    let file_path = get_cli_config_path().ok()?.join(CONFIG_FILENAME);
+    let file = fs::File::open(&file_path).ok()?;
+    let settings: Settings = serde_json::from_reader(file).ok()?;
+    settings.cache_dir
+}
+
+// Name of the profile to use, either selected with the '--profile' argument or SAFE_PROFILE
+// env var, or set as the current profile in the config file
+pub fn active_profile_name() -> Option<String> {
+    match std::env::var(PROFILE_ENV_VAR) {
+        Ok(name) if !name.is_empty() => Some(name),
+        _ => {
+            let file_path = get_cli_config_path().ok()?.join(CONFIG_FILENAME);
+            let file = fs::File::open(&file_path).ok()?;
+            let settings: Settings = serde_json::from_reader(file).ok()?;
+            settings.current_profile
+        }
+    }
+}
+
+// Name and settings of the profile to use, if any
+pub fn active_profile() -> Result<Option<(String, Profile)>> {
+    match active_profile_name() {
+        Some(name) => {
+            let file_path = get_cli_config_path()?.join(CONFIG_FILENAME);
+            let settings: Settings = fs::File::open(&file_path)
+                .ok()
+                .and_then(|file| serde_json::from_reader(file).ok())
+                .unwrap_or_default();
+            match settings.profiles.get(&name) {
+                Some(profile) => Ok(Some((name, profile.clone()))),
+                None => bail!("No profile with name '{}' was found in the config. Please use the 'config profile add' subcommand to add it", name),
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+// Folder where the data of a profile, e.g. its credentials, is kept.
+// If no profile is provided, the CLI's data folder is returned.
+pub fn profile_data_path(profile: Option<&str>) -> Result<PathBuf> {
+    let cli_path = get_cli_config_path()?;
+    match profile {
+        Some(name) => Ok(cli_path.join(CONFIG_PROFILES_DIRNAME).join(name)),
+        None => Ok(cli_path),
+    }
+}
+
+// Network connection information to use, which is the one of the network set in the
+// active profile, if any, otherwise the network currently set up in the system
+pub async fn read_network_conn_info() -> Result<HashSet<SocketAddr>> {
+    match active_profile()? {
+        Some((
+            _,
+            Profile {
+                network: Some(network),
+                ..
+            },
+        )) => {
+            // we don't use 'get_network_info' as nothing should be printed out at this point
+            let config = Config::read()?;
+            match config.settings.networks.get(&network) {
+                Some(NetworkInfo::ConnInfoUrl(config_location)) => {
+                    retrieve_conn_info(&config_location).await
+                }
+                Some(NetworkInfo::Addresses(addresses)) => Ok(addresses.clone()),
+                None => bail!(
+                    "No network with name '{}', as set in the active profile, was found in the config",
+                    network
+                ),
+            }
+        }
+        _ => {
+            let (_, contacts) = read_current_network_conn_info()?;
+            Ok(contacts)
+        }
+    }
+}
+
+pub fn default_cache_dir() -> Result<PathBuf> {
+    Ok(get_cli_config_path()?.join(CONFIG_CACHE_DIRNAME))
+}
+
+fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!(
+            "Invalid profile name '{}', only alphanumeric characters, '-' and '_' are allowed",
+            name
+        );
+    }
+    Ok(())
+}
+
 fn config_file_path() -> Result<PathBuf> {
     let config_local_path = get_cli_config_path()?;
     let file_path = config_local_path.join(CONFIG_FILENAME);
@@ -0,0 +1,451 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use aes_siv::{
+    aead::{generic_array::GenericArray, Aead, NewAead, Payload},
+    Aes128SivAead,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use hmac::Hmac;
+use log::debug;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha3::Sha3_256;
+use sn_api::{ed_sk_from_hex, Keypair, PublicKey};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs::{self, create_dir_all, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+const KEYSTORE_FILENAME: &str = "keystore.json";
+
+// Environment variable which allows to use a keystore file other than the default one
+const KEYSTORE_PATH_ENV_VAR: &str = "SAFE_KEYSTORE_PATH";
+const KEYSTORE_VERSION: u64 = 1;
+
+// Environment variables which allow to provide the keystore password without being prompted,
+// either directly or with a command which outputs it, e.g. a password manager or agent
+const KEYSTORE_PASSWORD_ENV_VAR: &str = "SAFE_KEYSTORE_PASSWORD";
+const KEYSTORE_PASSWORD_CMD_ENV_VAR: &str = "SAFE_KEYSTORE_PASSWORD_CMD";
+
+const KDF_PBKDF2_HMAC_SHA3_256: &str = "pbkdf2-hmac-sha3-256";
+const KDF_DEFAULT_ITERATIONS: u32 = 100_000;
+const KDF_SALT_LEN: usize = 16;
+const CIPHER_NONCE_LEN: usize = 16;
+
+// Prefix used to reference a secret key stored in the keystore by its alias, e.g. '@ops'
+const ALIAS_PREFIX: char = '@';
+
+thread_local! {
+    // Passwords provided by the user, kept in memory once they were found to be the right
+    // ones so they are not prompted for again, e.g. when using the interactive shell
+    static PASSWORD_CACHE: RefCell<BTreeMap<PasswordOf, String>> = RefCell::new(BTreeMap::new());
+}
+
+// Secrets which are encrypted with a password, each of them can have a different one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PasswordOf {
+    Keystore,
+    Credentials,
+}
+
+// Parameters of the password-based key derivation function
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct KdfParams {
+    algorithm: String,
+    salt: String,
+    iterations: u32,
+}
+
+// A secret encrypted with a key derived from a password. The ciphertext is the
+// hex encoding of the AES-SIV nonce followed by the encrypted secret.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EncryptedSecret {
+    kdf: KdfParams,
+    ciphertext: String,
+}
+
+impl EncryptedSecret {
+    // Encrypt a secret with the password, binding it to the associated data provided
+    pub fn seal(password: &str, associated_data: &str, secret: &[u8]) -> Result<Self> {
+        let mut salt = [0; KDF_SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let kdf = KdfParams {
+            algorithm: KDF_PBKDF2_HMAC_SHA3_256.to_string(),
+            salt: hex::encode(salt),
+            iterations: KDF_DEFAULT_ITERATIONS,
+        };
+
+        let cipher = derive_cipher(password, &kdf)?;
+        let mut nonce = [0; CIPHER_NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(
+                GenericArray::from_slice(&nonce),
+                Payload {
+                    msg: secret,
+                    aad: associated_data.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to encrypt secret"))?;
+
+        let mut bytes = nonce.to_vec();
+        bytes.extend_from_slice(&ciphertext);
+        Ok(Self {
+            kdf,
+            ciphertext: hex::encode(bytes),
+        })
+    }
+
+    // Decrypt the secret with the password, it fails if the password or associated data don't match
+    pub fn open(&self, password: &str, associated_data: &str) -> Result<Vec<u8>> {
+        let bytes = hex::decode(&self.ciphertext).context("Malformed encrypted secret")?;
+        if bytes.len() <= CIPHER_NONCE_LEN {
+            bail!("Malformed encrypted secret");
+        }
+        let (nonce, ciphertext) = bytes.split_at(CIPHER_NONCE_LEN);
+
+        let cipher = derive_cipher(password, &self.kdf)?;
+        cipher
+            .decrypt(
+                GenericArray::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: associated_data.as_bytes(),
+                },
+            )
+            .map_err(|_| anyhow!("Failed to decrypt secret, the password may be incorrect"))
+    }
+}
+
+// An entry of the keystore, only the secret key is stored encrypted
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KeystoreEntry {
+    pub public_key: String,
+    secret_key: EncryptedSecret,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct KeystoreContent {
+    version: u64,
+    entries: BTreeMap<String, KeystoreEntry>,
+}
+
+impl Default for KeystoreContent {
+    fn default() -> Self {
+        Self {
+            version: KEYSTORE_VERSION,
+            entries: BTreeMap::default(),
+        }
+    }
+}
+
+// Local keystore where secret keys are stored encrypted with a password, referenced by an alias
+#[derive(Debug)]
+pub struct Keystore {
+    content: KeystoreContent,
+    file_path: PathBuf,
+}
+
+impl Keystore {
+    pub fn read() -> Result<Self> {
+        let file_path = keystore_file_path()?;
+        let content = if file_path.exists() {
+            let file = fs::File::open(&file_path).with_context(|| {
+                format!("Error opening keystore file from '{}'", file_path.display())
+            })?;
+            let content: KeystoreContent = serde_json::from_reader(file).with_context(|| {
+                format!(
+                    "Format of the keystore file at '{}' is not valid and couldn't be parsed",
+                    file_path.display()
+                )
+            })?;
+            if content.version != KEYSTORE_VERSION {
+                bail!(
+                    "Version {} of the keystore file at '{}' is not supported",
+                    content.version,
+                    file_path.display()
+                );
+            }
+            content
+        } else {
+            KeystoreContent::default()
+        };
+
+        Ok(Self { content, file_path })
+    }
+
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    pub fn entries_iter(&self) -> impl Iterator<Item = (&String, &KeystoreEntry)> {
+        self.content.entries.iter()
+    }
+
+    // Store a secret key (hex encoded) with the given alias, returning its public key
+    pub fn add(&mut self, alias: &str, sk_hex: &str) -> Result<String> {
+        validate_alias(alias)?;
+        if self.content.entries.contains_key(alias) {
+            bail!(
+                "A key with alias '{}' already exists in the keystore",
+                alias
+            );
+        }
+
+        let sk = ed_sk_from_hex(sk_hex).context("Invalid secret key")?;
+        let keypair = Keypair::from(sk);
+        let public_key = match keypair.public_key() {
+            PublicKey::Ed25519(pk) => hex::encode(pk.to_bytes()),
+            _ => bail!("Only Ed25519 keys can be stored in the keystore"),
+        };
+
+        let is_new_keystore = self.content.entries.is_empty();
+        let prompt = if is_new_keystore {
+            "Enter a password to encrypt the keystore with: "
+        } else {
+            "Enter the keystore password: "
+        };
+        let password = get_password(PasswordOf::Keystore, prompt, is_new_keystore)?
+            .ok_or_else(|| anyhow!(no_password_msg("the keystore")))?;
+        if let Some(existing_alias) = self.content.entries.keys().next() {
+            // make sure all keys are encrypted with the same password
+            let _ = self.decrypt_entry(&password, existing_alias)?;
+        }
+
+        let entry = KeystoreEntry {
+            public_key: public_key.clone(),
+            secret_key: EncryptedSecret::seal(&password, alias, sk_hex.as_bytes())?,
+        };
+        self.content.entries.insert(alias.to_string(), entry);
+        self.write()?;
+        cache_password(PasswordOf::Keystore, &password);
+
+        debug!("Key with alias '{}' added to the keystore", alias);
+        Ok(public_key)
+    }
+
+    pub fn remove(&mut self, alias: &str) -> Result<()> {
+        if self.content.entries.remove(alias).is_none() {
+            bail!("No key with alias '{}' was found in the keystore", alias);
+        }
+        self.write()?;
+
+        debug!("Key with alias '{}' removed from the keystore", alias);
+        Ok(())
+    }
+
+    // Decrypt and return the secret key (hex encoded) stored with the given alias
+    pub fn export(&self, alias: &str) -> Result<String> {
+        if !self.content.entries.contains_key(alias) {
+            bail!("No key with alias '{}' was found in the keystore", alias);
+        }
+
+        let password = get_password(PasswordOf::Keystore, "Enter the keystore password: ", false)?
+            .ok_or_else(|| anyhow!(no_password_msg("the keystore")))?;
+        self.decrypt_entry(&password, alias)
+    }
+
+    // Private helpers
+
+    fn decrypt_entry(&self, password: &str, alias: &str) -> Result<String> {
+        let entry =
+            self.content.entries.get(alias).ok_or_else(|| {
+                anyhow!("No key with alias '{}' was found in the keystore", alias)
+            })?;
+        let sk_bytes = match entry.secret_key.open(password, alias) {
+            Ok(sk_bytes) => {
+                cache_password(PasswordOf::Keystore, password);
+                sk_bytes
+            }
+            Err(_) => {
+                forget_password(PasswordOf::Keystore);
+                bail!("Wrong password, failed to decrypt the keystore");
+            }
+        };
+
+        String::from_utf8(sk_bytes)
+            .map_err(|_| anyhow!("The secret key with alias '{}' is corrupted", alias))
+    }
+
+    fn write(&self) -> Result<()> {
+        let serialised =
+            serde_json::to_string(&self.content).context("Failed to serialise the keystore")?;
+        write_private_file(&self.file_path, serialised.as_bytes())
+            .with_context(|| format!("Unable to write keystore to '{}'", self.file_path.display()))
+    }
+}
+
+// If the value references a key in the keystore by its alias, e.g. '@ops', return the
+// corresponding secret key decrypted, otherwise the value is returned as it is
+pub fn resolve_sk_alias(value: &str) -> Result<String> {
+    if value.starts_with(ALIAS_PREFIX) {
+        let alias = &value[ALIAS_PREFIX.len_utf8()..];
+        Keystore::read()?.export(alias)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+// Obtain the password to encrypt/decrypt a secret, from the cache, the environment, a command
+// set in the environment, or by prompting the user if there is a terminal. It returns 'None' if
+// the password cannot be obtained, or the user didn't enter any when prompted. The password is
+// not cached until it's known to be the right one, see 'cache_password'.
+pub fn get_password(of: PasswordOf, prompt: &str, confirm: bool) -> Result<Option<String>> {
+    if let Some(password) = PASSWORD_CACHE.with(|cache| cache.borrow().get(&of).cloned()) {
+        return Ok(Some(password));
+    }
+
+    let password = if let Ok(password) = std::env::var(KEYSTORE_PASSWORD_ENV_VAR) {
+        Some(password)
+    } else if let Ok(cmd) = std::env::var(KEYSTORE_PASSWORD_CMD_ENV_VAR) {
+        Some(password_from_cmd(&cmd)?)
+    } else if isatty::stdin_isatty() {
+        let password =
+            rpassword::read_password_from_tty(Some(prompt)).context("Failed to read password")?;
+        if confirm && !password.is_empty() {
+            let confirmation = rpassword::read_password_from_tty(Some("Confirm password: "))
+                .context("Failed to read password")?;
+            if confirmation != password {
+                bail!("The passwords entered don't match");
+            }
+        }
+        Some(password)
+    } else {
+        None
+    };
+
+    match password {
+        Some(password) if !password.is_empty() => Ok(Some(password)),
+        _ => Ok(None),
+    }
+}
+
+// Keep a password in memory once it encrypted or decrypted a secret successfully
+pub fn cache_password(of: PasswordOf, password: &str) {
+    PASSWORD_CACHE.with(|cache| {
+        let _ = cache.borrow_mut().insert(of, password.to_string());
+    });
+}
+
+// Drop a password which failed to decrypt a secret, so the user is prompted for it again
+pub fn forget_password(of: PasswordOf) {
+    PASSWORD_CACHE.with(|cache| {
+        let _ = cache.borrow_mut().remove(&of);
+    });
+}
+
+pub fn no_password_msg(what: &str) -> String {
+    format!(
+        "A password is needed to access {}, it can be provided by setting the {} or {} environment variables, or entering it when prompted",
+        what, KEYSTORE_PASSWORD_ENV_VAR, KEYSTORE_PASSWORD_CMD_ENV_VAR
+    )
+}
+
+// Write a file which only the user can read, as it contains secrets
+pub fn write_private_file(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(folder) = path.parent() {
+        if !folder.exists() {
+            create_dir_all(folder).context("Couldn't create project's local data folder")?;
+        }
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    Ok(())
+}
+
+// Overwrite the content of a file with zeros before removing it
+pub fn wipe_file(path: &Path) -> Result<()> {
+    let len = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => return Ok(()),
+    };
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Unable to open '{}' to wipe it", path.display()))?;
+    file.write_all(&vec![0; len as usize])
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("Unable to wipe '{}'", path.display()))?;
+    drop(file);
+
+    fs::remove_file(path).with_context(|| format!("Unable to remove '{}'", path.display()))
+}
+
+// Private helpers
+
+fn derive_cipher(password: &str, kdf: &KdfParams) -> Result<Aes128SivAead> {
+    if kdf.algorithm != KDF_PBKDF2_HMAC_SHA3_256 {
+        bail!("Unsupported key derivation function: '{}'", kdf.algorithm);
+    }
+    let salt = hex::decode(&kdf.salt).context("Malformed key derivation salt")?;
+
+    let mut key = [0; 32];
+    pbkdf2::pbkdf2::<Hmac<Sha3_256>>(password.as_bytes(), &salt, kdf.iterations, &mut key);
+    Ok(Aes128SivAead::new(GenericArray::clone_from_slice(&key)))
+}
+
+fn password_from_cmd(cmd: &str) -> Result<String> {
+    let mut args = cmd.split_whitespace();
+    let program = args
+        .next()
+        .ok_or_else(|| anyhow!("The {} variable is empty", KEYSTORE_PASSWORD_CMD_ENV_VAR))?;
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run command to obtain password: '{}'", cmd))?;
+    if !output.status.success() {
+        bail!("Command to obtain password failed: '{}'", cmd);
+    }
+
+    let password = String::from_utf8(output.stdout)
+        .context("Password obtained from command contains invalid UTF-8 characters")?;
+    Ok(password.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+fn validate_alias(alias: &str) -> Result<()> {
+    if alias.is_empty()
+        || !alias
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        bail!(
+            "Invalid alias '{}', only alphanumeric characters, '-', '_' and '.' are allowed",
+            alias
+        );
+    }
+    Ok(())
+}
+
+fn keystore_file_path() -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os(KEYSTORE_PATH_ENV_VAR) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let mut project_data_path =
+        dirs_next::home_dir().ok_or_else(|| anyhow!("Couldn't find user's home directory"))?;
+    project_data_path.push(".safe");
+    project_data_path.push("cli");
+
+    Ok(project_data_path.join(KEYSTORE_FILENAME))
+}
@@ -9,8 +9,10 @@
 
 pub mod auth_and_connect;
 pub mod auth_daemon;
+pub mod batch_transfer;
 pub mod config;
 mod helpers;
+pub mod keystore;
 pub mod node;
 
 pub mod safe_net {
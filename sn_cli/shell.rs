@@ -138,6 +138,12 @@ pub fn shell_run() -> Result<()> {
         0,
         |io, (safe, _sn_authd_client), args| call_cli("nrs", args, safe, io),
     );
+    shell.new_command(
+        "pay",
+        "Pay a payment request URL",
+        0,
+        |io, (safe, _sn_authd_client), args| call_cli("pay", args, safe, io),
+    );
     shell.new_command(
         "setup",
         "Perform setup tasks",
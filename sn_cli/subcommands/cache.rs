@@ -0,0 +1,102 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{helpers::serialise_output, OutputFmt};
+use crate::operations::config::{default_cache_dir, Config};
+use anyhow::Result;
+use prettytable::Table;
+use sn_api::Safe;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub enum CacheSubCommands {
+    #[structopt(name = "stats")]
+    /// Show statistics about the content cached on disk
+    Stats,
+    #[structopt(name = "clear")]
+    /// Remove all content from the on-disk cache
+    Clear,
+    #[structopt(name = "enable")]
+    /// Enable the on-disk cache so content is kept across CLI invocations
+    Enable {
+        /// The folder where to store the cached content (by default it's '~/.safe/cli/cache')
+        #[structopt(parse(from_os_str))]
+        dir: Option<PathBuf>,
+    },
+    #[structopt(name = "disable")]
+    /// Disable the on-disk cache, the content currently cached is not removed
+    Disable,
+}
+
+pub async fn cache_commander(
+    cmd: Option<CacheSubCommands>,
+    output_fmt: OutputFmt,
+    safe: &mut Safe,
+) -> Result<()> {
+    let mut config = Config::read()?;
+    match cmd {
+        Some(CacheSubCommands::Enable { dir }) => {
+            let dir = match dir {
+                Some(dir) => dir,
+                None => default_cache_dir()?,
+            };
+            // Make sure the folder can be used before saving it in the config
+            safe.set_cache_dir(Some(dir.as_path())).await?;
+            config.set_cache_dir(Some(dir.clone()))?;
+            if OutputFmt::Pretty == output_fmt {
+                println!("On-disk cache enabled at '{}'", dir.display());
+            }
+        }
+        Some(CacheSubCommands::Disable) => {
+            config.set_cache_dir(None)?;
+            if OutputFmt::Pretty == output_fmt {
+                println!("On-disk cache disabled");
+            }
+        }
+        Some(CacheSubCommands::Clear) => match config.cache_dir() {
+            Some(dir) => {
+                safe.set_cache_dir(Some(dir.as_path())).await?;
+                safe.cache_clear().await?;
+                if OutputFmt::Pretty == output_fmt {
+                    println!("On-disk cache at '{}' cleared", dir.display());
+                }
+            }
+            None => {
+                if OutputFmt::Pretty == output_fmt {
+                    println!("On-disk cache is not enabled, nothing to clear");
+                }
+            }
+        },
+        Some(CacheSubCommands::Stats) | None => {
+            if let Some(dir) = config.cache_dir() {
+                safe.set_cache_dir(Some(dir.as_path())).await?;
+            }
+            let stats = safe.cache_stats().await;
+            if OutputFmt::Pretty == output_fmt {
+                let mut table = Table::new();
+                match &stats.disk_dir {
+                    Some(dir) => {
+                        table.add_row(row!["Location", dir.display()]);
+                        table.add_row(row!["Entries", stats.disk_entries]);
+                        table.add_row(row!["Size (bytes)", stats.disk_size]);
+                    }
+                    None => {
+                        table.add_row(row!["Location", "<disabled>"]);
+                    }
+                }
+                table.printstd();
+            } else {
+                println!("{}", serialise_output(&stats, output_fmt));
+            }
+        }
+    }
+
+    Ok(())
+}
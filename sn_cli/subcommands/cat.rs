@@ -8,13 +8,15 @@
 // Software.
 
 use super::{
-    helpers::{get_from_arg_or_stdin, print_nrs_map, serialise_output},
+    helpers::{
+        get_from_arg_or_stdin, print_nrs_map, redact_or_reveal_wallet_sks, serialise_output,
+    },
     OutputFmt,
 };
 use anyhow::{Context, Result};
 use log::debug;
 use prettytable::Table;
-use sn_api::{fetch::SafeData, Safe};
+use sn_api::{fetch::SafeData, safeurl::SafeUrl, Safe};
 use std::io::{self, Write};
 use structopt::StructOpt;
 
@@ -22,16 +24,27 @@ use structopt::StructOpt;
 pub struct CatCommands {
     /// The safe:// location to retrieve
     location: Option<String>,
+    /// Base URL the location is resolved against, when the location is a relative URL (e.g. '../img/logo.png')
+    #[structopt(long = "base")]
+    base: Option<String>,
     /// Renders file output as hex
     #[structopt(short = "x", long = "hexdump")]
     hexdump: bool,
+    /// Show the secret keys of a Wallet's spendable balances, which are redacted otherwise
+    #[structopt(long = "show-sk")]
+    show_sk: bool,
 }
 
 pub async fn cat_commander(cmd: CatCommands, output_fmt: OutputFmt, safe: &mut Safe) -> Result<()> {
     let url = get_from_arg_or_stdin(cmd.location, None)?;
+    let url = match cmd.base {
+        Some(base) => SafeUrl::from_url(&base)?.join(&url)?.to_string(),
+        None => url,
+    };
     debug!("Running cat for: {:?}", &url);
 
-    let content = safe.fetch(&url, None).await?;
+    let mut content = safe.fetch(&url, None).await?;
+    redact_or_reveal_wallet_sks(safe, &mut content, cmd.show_sk).await?;
     match &content {
         SafeData::FilesContainer {
             version, files_map, ..
@@ -77,10 +90,20 @@ pub async fn cat_commander(cmd: CatCommands, output_fmt: OutputFmt, safe: &mut S
             if OutputFmt::Pretty == output_fmt {
                 println!("Spendable balances of Wallet at \"{}\":", url);
                 let mut table = Table::new();
-                table.add_row(row![bFg->"Default", bFg->"Friendly Name", bFg->"SafeKey URL"]);
+                if cmd.show_sk {
+                    table.add_row(
+                        row![bFg->"Default", bFg->"Friendly Name", bFg->"SafeKey URL", bFg->"Secret Key"],
+                    );
+                } else {
+                    table.add_row(row![bFg->"Default", bFg->"Friendly Name", bFg->"SafeKey URL"]);
+                }
                 balances.iter().for_each(|(name, (default, balance))| {
                     let def = if *default { "*" } else { "" };
-                    table.add_row(row![def, name, balance.xorurl]);
+                    if cmd.show_sk {
+                        table.add_row(row![def, name, balance.xorurl, balance.sk]);
+                    } else {
+                        table.add_row(row![def, name, balance.xorurl]);
+                    }
                 });
                 table.printstd();
             } else {
@@ -136,6 +159,31 @@ pub async fn cat_commander(cmd: CatCommands, output_fmt: OutputFmt, safe: &mut S
                 println!("{}", serialise_output(&(url, data), output_fmt));
             }
         }
+        SafeData::Map { entries, .. } => {
+            if OutputFmt::Pretty == output_fmt {
+                println!("Map at \"{}\":", url);
+                let mut table = Table::new();
+                table.add_row(row![bFg->"Key", bFg->"Value", bFg->"Version"]);
+                for entry in entries.iter() {
+                    if cmd.hexdump {
+                        table.add_row(row![
+                            hex::encode(&entry.key),
+                            hex::encode(&entry.value),
+                            entry.version
+                        ]);
+                    } else {
+                        table.add_row(row![
+                            String::from_utf8_lossy(&entry.key),
+                            String::from_utf8_lossy(&entry.value),
+                            entry.version
+                        ]);
+                    }
+                }
+                table.printstd();
+            } else {
+                println!("{}", serialise_output(&(url, entries), output_fmt));
+            }
+        }
     }
 
     Ok(())
@@ -7,9 +7,16 @@
 // specific language governing permissions and limitations relating to use of the SAFE Network
 // Software.
 
-use crate::operations::config::{Config, NetworkInfo};
-use anyhow::Result;
+use super::OutputFmt;
+use crate::operations::{
+    auth_and_connect::AUTH_CREDENTIALS_FILENAME,
+    config::{Config, NetworkInfo, Profile},
+    keystore::wipe_file,
+};
+use anyhow::{anyhow, Result};
 use log::debug;
+use sn_api::safeurl::XorUrlBase;
+use std::fs;
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
@@ -23,6 +30,46 @@ pub enum ConfigSubCommands {
     #[structopt(name = "clear")]
     /// Remove all config settings
     Clear,
+    #[structopt(name = "profile")]
+    /// Manage profiles, each of them with its own credentials, network and default settings
+    Profile(ProfileSubCommands),
+}
+
+#[derive(StructOpt, Debug)]
+pub enum ProfileSubCommands {
+    #[structopt(name = "add")]
+    /// Add a profile, or replace the settings of an existing one
+    Add {
+        /// Profile name
+        name: String,
+        /// Name of the network (as added with 'config add network') to connect to
+        #[structopt(long = "network")]
+        network: Option<String>,
+        /// Endpoint of the Authenticator daemon where to send requests to
+        #[structopt(long = "authd-endpoint")]
+        authd_endpoint: Option<String>,
+        /// Base encoding to be used for XOR-URLs generated: [base32z, base32, base64, base58btc, base36, base16, base64url]
+        #[structopt(long = "xorurl-base")]
+        xorurl_base: Option<String>,
+        /// Output data serialisation: [json, jsoncompact, yaml, csv]
+        #[structopt(long = "output-format")]
+        output_fmt: Option<String>,
+    },
+    #[structopt(name = "use")]
+    /// Set the profile to use when none is selected with '--profile' or the SAFE_PROFILE env var
+    Use {
+        /// Profile name. If not provided, no profile is used by default
+        name: Option<String>,
+    },
+    #[structopt(name = "ls")]
+    /// List the profiles
+    Ls,
+    #[structopt(name = "rm")]
+    /// Remove a profile, together with its credentials
+    Rm {
+        /// Profile name
+        name: String,
+    },
 }
 
 #[derive(StructOpt, Debug)]
@@ -75,8 +122,62 @@ pub async fn config_commander(cmd: Option<ConfigSubCommands>) -> Result<()> {
             config.clear()?;
             debug!("Config settings cleared out");
         }
+        Some(ConfigSubCommands::Profile(cmd)) => profile_commander(cmd, &mut config)?,
         None => config.print_networks().await,
     }
 
     Ok(())
 }
+
+fn profile_commander(cmd: ProfileSubCommands, config: &mut Config) -> Result<()> {
+    match cmd {
+        ProfileSubCommands::Add {
+            name,
+            network,
+            authd_endpoint,
+            xorurl_base,
+            output_fmt,
+        } => {
+            // settings are kept as the strings provided, once validated
+            if let Some(base) = &xorurl_base {
+                base.parse::<XorUrlBase>()?;
+            }
+            if let Some(fmt) = &output_fmt {
+                fmt.parse::<OutputFmt>().map_err(|err| anyhow!(err))?;
+            }
+            let profile = Profile {
+                network,
+                authd_endpoint,
+                xorurl_base,
+                output_fmt,
+            };
+            config.add_profile(&name, profile)?;
+            println!("Profile '{}' was added to the config", name);
+        }
+        ProfileSubCommands::Use { name } => {
+            config.use_profile(name.as_deref())?;
+            match name {
+                Some(name) => println!("Profile '{}' is now used by default", name),
+                None => println!("No profile is now used by default"),
+            }
+        }
+        ProfileSubCommands::Ls => config.print_profiles(),
+        ProfileSubCommands::Rm { name } => {
+            let profile_dir = config.remove_profile(&name)?;
+            if profile_dir.exists() {
+                // make sure the credentials are not left on disk
+                wipe_file(&profile_dir.join(AUTH_CREDENTIALS_FILENAME))?;
+                fs::remove_dir_all(&profile_dir).map_err(|err| {
+                    anyhow!(
+                        "Failed to remove profile's folder at '{}': {}",
+                        profile_dir.display(),
+                        err
+                    )
+                })?;
+            }
+            println!("Profile '{}' was removed from the config", name);
+        }
+    }
+
+    Ok(())
+}
@@ -8,13 +8,16 @@
 // Software.
 
 use super::{
-    helpers::{get_from_arg_or_stdin, print_nrs_map, serialise_output, xorname_to_hex},
+    helpers::{
+        get_from_arg_or_stdin, print_nrs_map, redact_or_reveal_wallet_sks, serialise_output,
+        xorname_to_hex,
+    },
     OutputFmt,
 };
 use anyhow::Result;
 use log::debug;
 use sn_api::{
-    fetch::{SafeContentType, SafeData},
+    fetch::{ResolutionRule, ResolutionStep, SafeContentType, SafeData},
     safeurl::SafeUrl,
     Safe,
 };
@@ -24,15 +27,60 @@ use structopt::StructOpt;
 pub struct DogCommands {
     /// The safe:// location to inspect
     location: Option<String>,
+    /// Base URL the location is resolved against, when the location is a relative URL (e.g. '../img/logo.png')
+    #[structopt(long = "base")]
+    base: Option<String>,
+    /// Show the secret keys of a Wallet's spendable balances, which are redacted otherwise
+    #[structopt(long = "show-sk")]
+    show_sk: bool,
+    /// Trace each of the URL resolution steps, showing the rule applied, the network calls made, the bytes transferred and the time taken
+    #[structopt(long = "trace")]
+    trace: bool,
 }
 
 pub async fn dog_commander(cmd: DogCommands, output_fmt: OutputFmt, safe: &mut Safe) -> Result<()> {
     let url = get_from_arg_or_stdin(cmd.location, None)?;
+    let url = match cmd.base {
+        Some(base) => SafeUrl::from_url(&base)?.join(&url)?.to_string(),
+        None => url,
+    };
     debug!("Running dog for: {:?}", &url);
 
-    let resolved_content = safe.inspect(&url).await?;
+    let (resolved_content, trace) = if cmd.trace {
+        safe.inspect_with_trace(&url).await
+    } else {
+        (safe.inspect(&url).await, vec![])
+    };
+    let mut resolved_content = match resolved_content {
+        Ok(resolved_content) => resolved_content,
+        Err(err) => {
+            // show how far the resolution went, and which step it failed at
+            if cmd.trace {
+                if OutputFmt::Pretty == output_fmt {
+                    trace.iter().for_each(print_resolution_step);
+                } else {
+                    let no_content: Vec<SafeData> = vec![];
+                    println!(
+                        "{}",
+                        serialise_output(&(url, no_content, trace), output_fmt)
+                    );
+                }
+            }
+            return Err(err.into());
+        }
+    };
+    for content in resolved_content.iter_mut() {
+        redact_or_reveal_wallet_sks(safe, content, cmd.show_sk).await?;
+    }
     if OutputFmt::Pretty != output_fmt {
-        println!("{}", serialise_output(&(url, resolved_content), output_fmt));
+        if cmd.trace {
+            println!(
+                "{}",
+                serialise_output(&(url, resolved_content, trace), output_fmt)
+            );
+        } else {
+            println!("{}", serialise_output(&(url, resolved_content), output_fmt));
+        }
     } else {
         for (i, ref content) in resolved_content.iter().enumerate() {
             println!();
@@ -166,6 +214,26 @@ pub async fn dog_commander(cmd: DogCommands, output_fmt: OutputFmt, safe: &mut S
                     println!("XOR name: 0x{}", xorname_to_hex(xorname));
                     println!("Native data type: PrivateSequence");
                 }
+                SafeData::Map {
+                    xorurl,
+                    xorname,
+                    type_tag,
+                    resolved_from,
+                    ..
+                } => {
+                    if resolved_from != xorurl {
+                        println!("Resolved from: {}", resolved_from);
+                    }
+                    println!("= Map =");
+                    println!("XOR-URL: {}", xorurl);
+                    println!("Type tag: {}", type_tag);
+                    println!("XOR name: 0x{}", xorname_to_hex(xorname));
+                    println!("Native data type: SeqMap");
+                }
+            }
+
+            if let Some(step) = trace.get(i) {
+                print_resolution_step(step);
             }
         }
         println!();
@@ -173,3 +241,45 @@ pub async fn dog_commander(cmd: DogCommands, output_fmt: OutputFmt, safe: &mut S
 
     Ok(())
 }
+
+fn print_resolution_step(step: &ResolutionStep) {
+    println!("- Trace -");
+    match &step.rule {
+        Some(ResolutionRule::NrsSubNames { sub_names, target }) => {
+            println!(
+                "Rule: NRS subnames lookup (\"{}\") -> {}",
+                sub_names.join("."),
+                target
+            );
+        }
+        Some(ResolutionRule::NrsDefault { target }) => {
+            println!("Rule: NRS default link -> {}", target);
+        }
+        Some(ResolutionRule::FilesContainerPath {
+            path,
+            realpath,
+            symlinks_followed,
+            target,
+        }) => {
+            println!(
+                "Rule: FilesContainer path \"{}\" -> {}",
+                path,
+                target.as_deref().unwrap_or("(folder)")
+            );
+            if path != realpath {
+                println!(
+                    "Real path: \"{}\" ({} symlink/s followed)",
+                    realpath, symlinks_followed
+                );
+            }
+        }
+        None if step.error.is_some() => println!("Rule: none, the resolution failed"),
+        None => println!("Rule: none, target content reached"),
+    }
+    println!("Network calls: {}", step.network_calls);
+    println!("Bytes transferred: {}", step.bytes_transferred);
+    println!("Elapsed: {} ms", step.elapsed.as_millis());
+    if let Some(error) = &step.error {
+        println!("Error: {}", error);
+    }
+}
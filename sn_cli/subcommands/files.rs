@@ -11,7 +11,7 @@ use super::{
     files_get::{process_get_command, FileExistsAction, ProgressIndicator},
     helpers::{
         gen_processed_files_table, get_from_arg_or_stdin, get_from_stdin, if_tty, notice_dry_run,
-        parse_stdin_arg, pluralize, serialise_output,
+        parse_stdin_arg, parse_writer, pluralize, serialise_output,
     },
     OutputFmt,
 };
@@ -24,7 +24,7 @@ use sn_api::{
     fetch::SafeData,
     files::{FilesMap, ProcessedFiles},
     safeurl::{SafeUrl, XorUrl},
-    Safe,
+    PublicKey, Safe,
 };
 use std::{
     collections::{BTreeMap, HashMap},
@@ -110,6 +110,9 @@ pub enum FilesSubCommands {
         /// Follow symlinks
         #[structopt(short = "l", long = "follow-links")]
         follow_links: bool,
+        /// Grant another user permission to sync files to the FilesContainer, given its public key or SafeKey URL. It can be set multiple times
+        #[structopt(long = "writer", number_of_values = 1, parse(try_from_str = parse_writer))]
+        writers: Vec<PublicKey>,
     },
     /// Get a file or folder from the SAFE Network
     Get {
@@ -211,18 +214,20 @@ pub async fn files_commander(
             dest,
             recursive,
             follow_links,
+            writers,
         } => {
             // create FilesContainer from a given path to local files/folders
             if dry_run && OutputFmt::Pretty == output_fmt {
                 notice_dry_run();
             }
             let (files_container_xorurl, processed_files, _files_map) = safe
-                .files_container_create(
+                .files_container_create_with_writers(
                     Some(&location),
                     dest.as_deref(),
                     recursive,
                     follow_links,
                     dry_run,
+                    &writers,
                 )
                 .await?;
 
@@ -8,13 +8,20 @@
 // Software.
 
 use super::OutputFmt;
+use crate::operations::keystore::resolve_sk_alias;
 use ansi_term::Style;
 use anyhow::{anyhow, bail, Context, Result};
 use log::debug;
 use num_traits::Float;
 use prettytable::{format::FormatBuilder, Table};
 use serde::ser::Serialize;
-use sn_api::nrs::NrsMap;
+use sn_api::{
+    ed_pk_from_xorname,
+    fetch::SafeData,
+    nrs::NrsMap,
+    safeurl::{SafeDataType, SafeUrl},
+    PublicKey, Safe,
+};
 use std::{
     collections::BTreeMap,
     io::{stdin, stdout, Read, Write},
@@ -22,6 +29,7 @@ use std::{
 use xor_name::{XorName, XOR_NAME_LEN};
 
 const UNKNOWN_PUBLIC_NAME: &str = "<unknown>";
+const REDACTED_SECRET_KEY: &str = "<redacted>";
 
 // Warn the user about a dry-run being performed
 pub fn notice_dry_run() {
@@ -99,6 +107,24 @@ pub fn get_from_stdin(message: Option<&str>) -> Result<Vec<u8>> {
     }
 }
 
+// Read a secret, e.g. a mnemonic phrase, without echoing it when there is a terminal,
+// otherwise from a line of stdin. Secrets are not taken as arguments since they would
+// end up in the shell history and in the list of running processes.
+pub fn read_secret(what: &str) -> Result<String> {
+    let secret = if isatty::stdin_isatty() {
+        rpassword::read_password_from_tty(Some(&format!("Enter the {}: ", what)))
+            .with_context(|| format!("Failed to read the {}", what))?
+    } else {
+        read_stdin_response()?
+    };
+
+    let secret = secret.trim();
+    if secret.is_empty() {
+        bail!("No {} was provided", what);
+    }
+    Ok(secret.to_string())
+}
+
 // Prompt the user with the message provided
 pub fn prompt_user(prompt_msg: &str, error_msg: &str) -> Result<String> {
     print!("{}", prompt_msg);
@@ -111,7 +137,8 @@ pub fn prompt_user(prompt_msg: &str, error_msg: &str) -> Result<String> {
     }
 }
 
-// Unwrap secret key string provided, otherwise prompt user to provide it
+// Unwrap secret key string provided, otherwise prompt user to provide it.
+// A key stored in the keystore can be referenced by its alias, e.g. '@ops'
 pub fn get_secret_key(key_xorurl: &str, sk: Option<String>, msg: &str) -> Result<String> {
     let mut sk = sk.unwrap_or_else(|| String::from(""));
 
@@ -127,7 +154,23 @@ pub fn get_secret_key(key_xorurl: &str, sk: Option<String>, msg: &str) -> Result
         sk = prompt_user(&msg, "Invalid input")?;
     }
 
-    Ok(sk)
+    resolve_sk_alias(&sk)
+}
+
+// Parse a user to grant permissions to, given its public key or SafeKey URL
+pub fn parse_writer(value: &str) -> Result<PublicKey> {
+    if value.starts_with("safe://") {
+        let safeurl = SafeUrl::from_url(value)?;
+        if safeurl.data_type() != SafeDataType::SafeKey {
+            bail!("The URL of a writer must target a SafeKey: {}", value);
+        }
+        ed_pk_from_xorname(safeurl.xorname())
+            .with_context(|| format!("Invalid SafeKey URL of writer: {}", value))
+    } else {
+        PublicKey::ed25519_from_hex(value)
+            .or_else(|_| PublicKey::bls_from_hex(value))
+            .map_err(|_| anyhow!("Invalid public key of writer: {}", value))
+    }
 }
 
 pub fn gen_processed_files_table(
@@ -176,12 +219,33 @@ where
             .unwrap_or_else(|_| "Failed to serialise output to json".to_string()),
         OutputFmt::JsonCompact => serde_json::to_string(&value)
             .unwrap_or_else(|_| "Failed to serialise output to compact json".to_string()),
+        OutputFmt::Csv => {
+            "OutputFmt::Csv' not handled by caller, in serialise_output()".to_string()
+        }
         OutputFmt::Pretty => {
             "OutputFmt::Pretty' not handled by caller, in serialise_output()".to_string()
         }
     }
 }
 
+// Serialise a list of records as CSV, with a header row taken from the
+// fields' names when the records are structs
+pub fn serialise_csv_records<T>(records: &[T]) -> Result<String>
+where
+    T: Serialize,
+{
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for record in records.iter() {
+        writer
+            .serialize(record)
+            .context("Failed to serialise output to csv")?;
+    }
+    let bytes = writer
+        .into_inner()
+        .context("Failed to serialise output to csv")?;
+    String::from_utf8(bytes).context("Failed to serialise output to csv")
+}
+
 // Pretty print an NRS Map
 pub fn print_nrs_map(nrs_map: &NrsMap, public_name: &Option<String>) {
     let mut table = Table::new();
@@ -203,6 +267,31 @@ pub fn print_nrs_map(nrs_map: &NrsMap, public_name: &Option<String>) {
     table.printstd();
 }
 
+// Redact the secret keys of a Wallet's spendable balances before they are output,
+// unless they were requested to be shown, in which case they are decrypted
+pub async fn redact_or_reveal_wallet_sks(
+    safe: &Safe,
+    content: &mut SafeData,
+    show_sk: bool,
+) -> Result<()> {
+    if let SafeData::Wallet {
+        xorname, balances, ..
+    } = content
+    {
+        if show_sk {
+            safe.wallet_reveal_sks(*xorname, balances)
+                .await
+                .context("Failed to decrypt the secret keys of the Wallet")?;
+        } else {
+            // watch-only balances have no secret key to redact
+            for (_, balance) in balances.values_mut().filter(|(_, b)| !b.is_watch_only()) {
+                balance.sk = REDACTED_SECRET_KEY.to_string();
+            }
+        }
+    }
+    Ok(())
+}
+
 // returns singular or plural version of string, based on count.
 pub fn pluralize<'a>(singular: &'a str, plural: &'a str, count: u64) -> &'a str {
     if count == 1 {
@@ -8,21 +8,33 @@
 // Software.
 
 use super::{
-    helpers::{get_from_arg_or_stdin, get_secret_key, serialise_output},
+    helpers::{
+        get_from_arg_or_stdin, get_secret_key, read_secret, serialise_csv_records, serialise_output,
+    },
     OutputFmt,
 };
 use crate::operations::{
-    auth_and_connect::{create_credentials_file, read_credentials},
+    auth_and_connect::{read_credentials, write_credentials},
+    keystore::{resolve_sk_alias, write_private_file, Keystore},
     safe_net::connect,
 };
 use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
 use hex::encode;
 use log::{debug, warn};
+use prettytable::Table;
+use serde::Serialize;
 use sn_api::{
+    ed_sk_from_hex,
     fetch::{SafeData, SafeUrl},
-    sk_to_hex, Keypair, PublicKey, Safe, SecretKey, XorName,
+    sk_to_hex, DataSignature, Error as ApiError, KeyFormat, Keypair, PublicKey, Safe, SecretKey,
+    TransferHistoryEntry, XorName,
+};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
 };
-use std::io::Write;
 use structopt::StructOpt;
 
 const PRELOAD_DEFAULT_AMOUNT: &str = "0.000000001";
@@ -53,6 +65,12 @@ pub enum KeysSubCommands {
         /// Set the newly created keys to be used by CLI
         #[structopt(long = "for-cli")]
         for_cli: bool,
+        /// Derive the key pair from a mnemonic phrase rather than generating a random one, e.g. one obtained with 'safe keys derive'. The mnemonic phrase is prompted for, or read from stdin
+        #[structopt(long = "from-mnemonic")]
+        from_mnemonic: bool,
+        /// The index of the key pair to derive from the mnemonic phrase, 0 by default
+        #[structopt(long = "index", requires = "from-mnemonic")]
+        index: Option<u32>,
     },
     #[structopt(name = "balance")]
     /// Query a SafeKey's current balance
@@ -76,6 +94,155 @@ pub enum KeysSubCommands {
         #[structopt(long = "to")]
         to: Option<String>,
     },
+    #[structopt(name = "request")]
+    /// Generate a payment request URL, which can be paid with 'safe pay'
+    Request {
+        /// Number of safecoins to request
+        #[structopt(long = "amount")]
+        amount: String,
+        /// The Wallet/SafeKey URL to be paid to. If not provided, the SafeKey owned by CLI will be used
+        #[structopt(long = "to")]
+        to: Option<String>,
+        /// A reference for the payment, e.g. an invoice number
+        #[structopt(long = "memo")]
+        memo: Option<String>,
+        /// Date and time the request expires at, in RFC 3339 format (e.g. '2021-04-01T00:00:00Z')
+        #[structopt(long = "expires", parse(try_from_str = parse_expiry_time))]
+        expires: Option<DateTime<Utc>>,
+        /// Time the request is valid for, with 's', 'm', 'h', or 'd' units (e.g. '30m' or '7d')
+        #[structopt(long = "expires-in", parse(try_from_str = parse_validity), conflicts_with = "expires")]
+        expires_in: Option<Duration>,
+    },
+    #[structopt(name = "derive")]
+    /// Derive key pairs from a mnemonic phrase, generating a new mnemonic phrase unless '--mnemonic' is passed. No SafeKeys are created on the network
+    Derive {
+        /// Derive the key pairs from an existing mnemonic phrase, which is prompted for, or read from stdin
+        #[structopt(long = "mnemonic")]
+        mnemonic: bool,
+        /// The index of the first key pair to derive
+        #[structopt(long = "index", default_value = "0")]
+        index: u32,
+        /// The number of consecutive key pairs to derive
+        #[structopt(long = "count", default_value = "1")]
+        count: u32,
+    },
+    #[structopt(name = "sign")]
+    /// Sign a file with a SafeKey, outputting a detached signature which includes the public key and the SafeKey URL
+    Sign {
+        /// The file to sign, or '-' to read the data from stdin
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// The secret key to sign with, or the alias of a key in the keystore as '@<alias>'. CLI application's SafeKey will be used by default, otherwise it will be prompted
+        #[structopt(long = "sk")]
+        secret: Option<String>,
+    },
+    #[structopt(name = "verify")]
+    /// Verify a detached signature of a file, as output by 'safe keys sign'
+    Verify {
+        /// The signed file, or '-' to read the data from stdin
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// The file containing the signature
+        #[structopt(parse(from_os_str))]
+        signature: PathBuf,
+        /// The SafeKey XOR-URL or public key the file is expected to be signed with. It's required since anyone can sign a file with their own SafeKey, thus a signature alone doesn't prove who made it
+        #[structopt(long = "signer")]
+        signer: String,
+    },
+    #[structopt(name = "export")]
+    /// Export the key pair of a SafeKey in a standard format, to use the same identity with other tools
+    Export {
+        /// The format to export the key pair in
+        #[structopt(long = "format", possible_values = &["pem", "pkcs8", "openssh", "jwk"], default_value = "pem")]
+        format: KeyFormat,
+        /// The secret key to export, or the alias of a key in the keystore as '@<alias>'. CLI application's SafeKey will be used by default, otherwise it will be prompted
+        #[structopt(long = "sk")]
+        secret: Option<String>,
+        /// Protect the exported key with a password, which will be prompted. Only supported by the 'pem' and 'pkcs8' formats
+        #[structopt(long = "password")]
+        password: bool,
+        /// The file to write the exported key to, otherwise it's written to stdout
+        #[structopt(long = "out", parse(from_os_str))]
+        out: Option<PathBuf>,
+    },
+    #[structopt(name = "import")]
+    /// Import an Ed25519 key pair from a PKCS#8 (PEM or DER), OpenSSH or JWK file as a SafeKey
+    Import {
+        /// The file containing the key, or '-' to read it from stdin
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+        /// Prompt for the password the key is protected with. It's also prompted if the key turns out to be password-protected
+        #[structopt(long = "password")]
+        password: bool,
+        /// Show Secret Key as well
+        #[structopt(long = "show-sk")]
+        show_sk: bool,
+        /// Store the imported secret key in the keystore with the given alias
+        #[structopt(long = "alias")]
+        alias: Option<String>,
+        /// Set the imported key pair to be used by CLI
+        #[structopt(long = "for-cli")]
+        for_cli: bool,
+    },
+    #[structopt(name = "store")]
+    /// Manage the secret keys stored encrypted in the local keystore. They can be referenced by their alias, as '@<alias>', wherever a secret key is expected, e.g. '--pay-with @ops'
+    Store(KeystoreSubCommands),
+    #[structopt(name = "history")]
+    /// List the incoming and outgoing transfers of a SafeKey
+    History {
+        /// The target SafeKey's safe://xor-url to verify it matches/corresponds to the secret key provided. The corresponding secret key will be prompted if not provided with '--sk'
+        #[structopt(long = "keyurl")]
+        keyurl: Option<String>,
+        /// The secret key which corresponds to the target SafeKey. CLI application's default SafeKey will be used by default, otherwise if the CLI has not been given a keypair (authorised), it will be prompted
+        #[structopt(long = "sk")]
+        secret: Option<String>,
+        #[structopt(flatten)]
+        pagination: HistoryPagination,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum KeystoreSubCommands {
+    #[structopt(name = "add")]
+    /// Encrypt a secret key and store it in the keystore with the given alias. The secret key is prompted for, or read from stdin
+    Add {
+        /// The alias to reference the secret key with
+        alias: String,
+    },
+    #[structopt(name = "list")]
+    /// List the aliases and public keys of the secret keys stored in the keystore
+    List,
+    #[structopt(name = "remove")]
+    /// Remove a secret key from the keystore
+    Remove {
+        /// The alias of the secret key to remove
+        alias: String,
+    },
+    #[structopt(name = "export")]
+    /// Decrypt and show a secret key stored in the keystore
+    Export {
+        /// The alias of the secret key to export
+        alias: String,
+    },
+}
+
+// Key pair derived from a mnemonic phrase, as output by 'keys derive'
+#[derive(Debug, Serialize)]
+struct DerivedKey {
+    index: u32,
+    xorurl: String,
+    public_key: String,
+    secret_key: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct HistoryPagination {
+    /// The page of the list of transfers to show, starting from 1
+    #[structopt(long = "page", default_value = "1")]
+    page: usize,
+    /// The number of transfers to show per page
+    #[structopt(long = "page-size", default_value = "20")]
+    page_size: usize,
 }
 
 pub async fn key_commander(
@@ -140,7 +307,8 @@ pub async fn key_commander(
             pay_with,
             test_coins,
             for_cli,
-            ..
+            from_mnemonic,
+            index,
         } => {
             if test_coins && pay_with.is_some() {
                 // We don't support this arg with --test-coins
@@ -152,20 +320,19 @@ pub async fn key_commander(
                 connect(safe).await?;
             }
 
+            let keypair = if from_mnemonic {
+                let mnemonic = read_secret("mnemonic phrase")?;
+                Some(safe.keys_derive_from_mnemonic(&mnemonic, index.unwrap_or(0))?)
+            } else {
+                None
+            };
             let (xorurl, key_pair, amount) =
-                create_new_key(safe, test_coins, pay_with, preload).await?;
+                create_new_key(safe, test_coins, pay_with, preload, keypair).await?;
             print_new_key_output(output_fmt, xorurl, Some(&key_pair), amount, test_coins);
 
             if for_cli {
                 println!("Setting new SafeKey to be used by CLI...");
-                let (mut file, file_path) = create_credentials_file()?;
-                let serialised_keypair = serde_json::to_string(&key_pair)
-                    .context("Unable to serialise the credentials created")?;
-
-                file.write_all(serialised_keypair.as_bytes())
-                    .with_context(|| {
-                        format!("Unable to write credentials in {}", file_path.display(),)
-                    })?;
+                let file_path = write_credentials(&key_pair)?;
 
                 println!(
                     "New credentials were successfully stored in {}",
@@ -178,22 +345,16 @@ pub async fn key_commander(
         }
         KeysSubCommands::Balance { keyurl, secret } => {
             let target = keyurl.unwrap_or_else(|| "".to_string());
-            let sk = match connect(safe).await? {
-                Some(keypair) if secret.is_none() => {
-                    // we then use the secret from CLI's given credentials
-                    println!("Checking balance of CLI's assigned keypair...");
-                    keypair
-                        .secret_key()
-                        .context("Failed to obtain the secret key from app's assigned keypair")?
-                }
-                Some(_) | None => {
-                    // prompt the user for a SK
-                    let secret_key =
-                        get_secret_key(&target, secret, "the SafeKey to query the balance from")?;
-
-                    SecretKey::ed25519_from_hex(&secret_key)?
-                }
-            };
+            let (sk, is_cli_sk) = get_target_sk(
+                safe,
+                &target,
+                secret,
+                "the SafeKey to query the balance from",
+            )
+            .await?;
+            if is_cli_sk {
+                println!("Checking balance of CLI's assigned keypair...");
+            }
 
             let current_balance = if target.is_empty() {
                 safe.keys_balance_from_sk(&sk).await
@@ -217,6 +378,10 @@ pub async fn key_commander(
                 Some("...awaiting destination Wallet/SafeKey URL, or public key, from STDIN stream..."),
             )?;
 
+            let from = match from {
+                Some(sk) => Some(resolve_sk_alias(&sk)?),
+                None => None,
+            };
             let tx_id = safe
                 .keys_transfer(&amount, from.as_deref(), &destination)
                 .await?;
@@ -229,16 +394,481 @@ pub async fn key_commander(
 
             Ok(())
         }
+        KeysSubCommands::Request {
+            amount,
+            to,
+            memo,
+            expires,
+            expires_in,
+        } => {
+            let to = match to {
+                Some(to) => to,
+                None => match read_credentials()? {
+                    (_, Some(keypair)) => SafeUrl::encode_safekey(
+                        XorName::from(keypair.public_key()),
+                        safe.xorurl_base,
+                    )?,
+                    (_, None) => bail!("No SafeKey found for CLI, please provide the Wallet/SafeKey URL to be paid to with '--to'"),
+                },
+            };
+            let expires = match (expires, expires_in) {
+                (Some(time), _) => Some(time),
+                (None, Some(validity)) => Some(
+                    Utc::now()
+                        .checked_add_signed(validity)
+                        .ok_or_else(|| anyhow!("The validity time of the request is too long"))?,
+                ),
+                (None, None) => None,
+            };
+
+            let url = safe.payment_request_create(&to, &amount, memo.as_deref(), expires)?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!("Payment request URL: {}", url);
+            } else {
+                println!("{}", serialise_output(&url, output_fmt));
+            }
+
+            Ok(())
+        }
+        KeysSubCommands::Sign { file, secret } => {
+            let data = read_input_bytes(&file)?;
+            let sk = match secret {
+                Some(sk) => resolve_sk_alias(&sk)?,
+                None => match read_credentials()? {
+                    (_, Some(keypair)) => keypair_to_hex_strings(&keypair)?.1,
+                    (_, None) => get_secret_key("", None, "the SafeKey to sign with")?,
+                },
+            };
+
+            let signature = safe.sign_data(&sk, &data)?;
+
+            if OutputFmt::Pretty == output_fmt {
+                // the signature is output as JSON anyway so it can be saved to a file
+                println!("{}", serde_json::to_string_pretty(&signature)?);
+            } else {
+                println!("{}", serialise_output(&signature, output_fmt));
+            }
+
+            Ok(())
+        }
+        KeysSubCommands::Verify {
+            file,
+            signature,
+            signer,
+        } => {
+            let data = read_input_bytes(&file)?;
+            let content = fs::read(&signature).with_context(|| {
+                format!("Failed to read signature file '{}'", signature.display())
+            })?;
+            let signature: DataSignature = serde_json::from_slice(&content).with_context(|| {
+                format!("Failed to parse signature file '{}'", signature.display())
+            })?;
+
+            safe.verify_data(&data, &signature)?;
+            let expected = if signer.starts_with("safe://") {
+                SafeUrl::from_url(&signer)?.xorname()
+                    == SafeUrl::from_url(&signature.xorurl)?.xorname()
+            } else {
+                signer.eq_ignore_ascii_case(&signature.public_key)
+            };
+            if !expected {
+                bail!(
+                    "The signature was made by SafeKey {} rather than the expected signer {}",
+                    signature.xorurl,
+                    signer
+                );
+            }
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Signature is valid, the data was signed by SafeKey {} (public key: {})",
+                    signature.xorurl, signature.public_key
+                );
+            } else {
+                println!("{}", serialise_output(&signature, output_fmt));
+            }
+
+            Ok(())
+        }
+        KeysSubCommands::Export {
+            format,
+            secret,
+            password,
+            out,
+        } => {
+            if format == KeyFormat::Pkcs8 && out.is_none() && isatty::stdout_isatty() {
+                bail!("The 'pkcs8' format is binary, please provide a file to write the key to with '--out', or redirect the output");
+            }
+            if password && format != KeyFormat::Pem && format != KeyFormat::Pkcs8 {
+                bail!("Password protection is only supported by the 'pem' and 'pkcs8' formats");
+            }
+            let keypair = match secret {
+                Some(sk) => Keypair::from(ed_sk_from_hex(&resolve_sk_alias(&sk)?)?),
+                None => match read_credentials()? {
+                    (_, Some(keypair)) => keypair,
+                    (_, None) => {
+                        let sk = get_secret_key("", None, "the SafeKey to export")?;
+                        Keypair::from(ed_sk_from_hex(&sk)?)
+                    }
+                },
+            };
+            let password = if password {
+                Some(read_key_password(true)?)
+            } else {
+                None
+            };
+
+            let exported = safe.keys_export(&keypair, format, password.as_deref())?;
+
+            match out {
+                Some(path) => {
+                    write_private_file(&path, &exported).with_context(|| {
+                        format!("Failed to write the key to '{}'", path.display())
+                    })?;
+                    if OutputFmt::Pretty == output_fmt {
+                        println!("Key pair exported to '{}'", path.display());
+                    }
+                }
+                None => std::io::stdout()
+                    .write_all(&exported)
+                    .context("Failed to write the key to stdout")?,
+            }
+
+            Ok(())
+        }
+        KeysSubCommands::Import {
+            file,
+            password,
+            show_sk,
+            alias,
+            for_cli,
+        } => {
+            let data = read_input_bytes(&file)?;
+            let password = if password {
+                Some(read_key_password(false)?)
+            } else {
+                None
+            };
+            let keypair = match safe.keys_import(&data, password.as_deref()) {
+                Err(ApiError::DecryptionError(_)) if password.is_none() => {
+                    // the key turned out to be password-protected
+                    let password = read_key_password(false)?;
+                    safe.keys_import(&data, Some(&password))?
+                }
+                other => other?,
+            };
+
+            let xorurl =
+                SafeUrl::encode_safekey(XorName::from(keypair.public_key()), safe.xorurl_base)?;
+            let (pk_hex, sk_hex) = keypair_to_hex_strings(&keypair)?;
+            if OutputFmt::Pretty == output_fmt {
+                println!("Key pair imported:");
+                println!("XOR-URL: {}", xorurl);
+                println!("Public Key: {}", pk_hex);
+                if show_sk {
+                    println!("Secret Key: {}", sk_hex);
+                }
+            } else {
+                let sk = if show_sk { Some(sk_hex.clone()) } else { None };
+                println!("{}", serialise_output(&(xorurl, pk_hex, sk), output_fmt));
+            }
+
+            if let Some(alias) = alias {
+                let mut keystore = Keystore::read()?;
+                let _ = keystore.add(&alias, &sk_hex)?;
+                if OutputFmt::Pretty == output_fmt {
+                    println!("Secret key stored with alias '{}' in the keystore", alias);
+                }
+            }
+            if for_cli {
+                let file_path = write_credentials(&keypair)?;
+                if OutputFmt::Pretty == output_fmt {
+                    println!(
+                        "Imported key pair set to be used by CLI, credentials stored in {}",
+                        file_path.display()
+                    );
+                }
+            }
+
+            Ok(())
+        }
+        KeysSubCommands::Store(cmd) => keystore_commander(cmd, output_fmt, safe),
+        KeysSubCommands::Derive {
+            mnemonic,
+            index,
+            count,
+        } => {
+            let (mnemonic, generated) = if mnemonic {
+                (read_secret("mnemonic phrase")?, false)
+            } else {
+                (safe.keys_generate_mnemonic(), true)
+            };
+
+            let mut derived_keys = vec![];
+            for i in 0..count {
+                let key_index = index.checked_add(i).ok_or_else(|| {
+                    anyhow!("The range of indexes to derive key pairs from is too large")
+                })?;
+                let keypair = safe.keys_derive_from_mnemonic(&mnemonic, key_index)?;
+                let xorurl =
+                    SafeUrl::encode_safekey(XorName::from(keypair.public_key()), safe.xorurl_base)?;
+                let (public_key, secret_key) = keypair_to_hex_strings(&keypair)?;
+                derived_keys.push(DerivedKey {
+                    index: key_index,
+                    xorurl,
+                    public_key,
+                    secret_key,
+                });
+            }
+
+            if OutputFmt::Pretty == output_fmt {
+                if generated {
+                    println!("New mnemonic phrase generated, write it down and keep it safe as it's all that's needed to recover the key pairs derived from it:");
+                    println!("{}", mnemonic);
+                    println!();
+                }
+                let mut table = Table::new();
+                table.add_row(
+                    row![bFg->"Index", bFg->"SafeKey URL", bFg->"Public Key", bFg->"Secret Key"],
+                );
+                for key in derived_keys.iter() {
+                    table.add_row(row![key.index, key.xorurl, key.public_key, key.secret_key]);
+                }
+                println!("Key pairs derived:");
+                table.printstd();
+            } else {
+                let mnemonic = if generated { Some(mnemonic) } else { None };
+                println!(
+                    "{}",
+                    serialise_output(&(mnemonic, derived_keys), output_fmt)
+                );
+            }
+
+            Ok(())
+        }
+        KeysSubCommands::History {
+            keyurl,
+            secret,
+            pagination,
+        } => {
+            let target = keyurl.unwrap_or_else(|| "".to_string());
+            let (sk, is_cli_sk) =
+                get_target_sk(safe, &target, secret, "the SafeKey to query the history of").await?;
+            if is_cli_sk && OutputFmt::Pretty == output_fmt {
+                println!("Retrieving history of CLI's assigned keypair...");
+            }
+            if !target.is_empty() {
+                let _ = safe.validate_sk_for_url(&sk, &target).await?;
+            }
+
+            let history = safe.keys_history(&sk).await?;
+            print_transfer_history(&history, &pagination, output_fmt)
+        }
     }
 }
 
+fn keystore_commander(cmd: KeystoreSubCommands, output_fmt: OutputFmt, safe: &Safe) -> Result<()> {
+    let mut keystore = Keystore::read()?;
+    match cmd {
+        KeystoreSubCommands::Add { alias } => {
+            let sk = read_secret("secret key to store")?;
+            let public_key = keystore.add(&alias, &sk)?;
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Secret key of public key {} stored with alias '{}' in {}",
+                    public_key,
+                    alias,
+                    keystore.file_path().display()
+                );
+            } else {
+                println!("{}", serialise_output(&(alias, public_key), output_fmt));
+            }
+        }
+        KeystoreSubCommands::List => {
+            let mut entries = vec![];
+            for (alias, entry) in keystore.entries_iter() {
+                let xorurl = match ed25519_dalek::PublicKey::from_bytes(
+                    &hex::decode(&entry.public_key).unwrap_or_default(),
+                ) {
+                    Ok(pk) => {
+                        let xorname = XorName::from(PublicKey::Ed25519(pk));
+                        SafeUrl::encode_safekey(xorname, safe.xorurl_base)?
+                    }
+                    Err(_) => "<invalid public key>".to_string(),
+                };
+                entries.push((alias.clone(), xorurl, entry.public_key.clone()));
+            }
+
+            if OutputFmt::Pretty == output_fmt {
+                if entries.is_empty() {
+                    println!("No keys found in the keystore");
+                } else {
+                    let mut table = Table::new();
+                    table.add_row(row![bFg->"Alias", bFg->"SafeKey URL", bFg->"Public Key"]);
+                    for (alias, xorurl, public_key) in entries.iter() {
+                        table.add_row(row![alias, xorurl, public_key]);
+                    }
+                    table.printstd();
+                }
+            } else {
+                println!("{}", serialise_output(&entries, output_fmt));
+            }
+        }
+        KeystoreSubCommands::Remove { alias } => {
+            keystore.remove(&alias)?;
+            if OutputFmt::Pretty == output_fmt {
+                println!("Key with alias '{}' removed from the keystore", alias);
+            }
+        }
+        KeystoreSubCommands::Export { alias } => {
+            let sk = keystore.export(&alias)?;
+            if OutputFmt::Pretty == output_fmt {
+                println!("Secret Key = {}", sk);
+            } else {
+                println!("{}", serialise_output(&sk, output_fmt));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Prompt for the password a key to be exported or imported is protected with. The keystore's
+// password isn't used as they are usually different, and it's prompted for every time.
+fn read_key_password(confirm: bool) -> Result<String> {
+    if !isatty::stdin_isatty() {
+        bail!("The key's password can only be entered from a terminal");
+    }
+    let password = rpassword::read_password_from_tty(Some("Enter the key's password: "))
+        .context("Failed to read password")?;
+    if password.is_empty() {
+        bail!("No password was entered");
+    }
+    if confirm {
+        let confirmation = rpassword::read_password_from_tty(Some("Confirm password: "))
+            .context("Failed to read password")?;
+        if confirmation != password {
+            bail!("The passwords entered don't match");
+        }
+    }
+    Ok(password)
+}
+
+// Read the data to be signed, verified or imported, from stdin if the path is '-'
+fn read_input_bytes(path: &Path) -> Result<Vec<u8>> {
+    if path == Path::new("-") {
+        let mut data = vec![];
+        std::io::stdin()
+            .read_to_end(&mut data)
+            .context("Failed to read data from stdin")?;
+        Ok(data)
+    } else {
+        fs::read(path).with_context(|| format!("Failed to read file '{}'", path.display()))
+    }
+}
+
+// Get the secret key of the target SafeKey, using the one from CLI's given credentials
+// if none was provided. It also returns whether CLI's credentials were used.
+async fn get_target_sk(
+    safe: &mut Safe,
+    target: &str,
+    secret: Option<String>,
+    msg: &str,
+) -> Result<(SecretKey, bool)> {
+    match connect(safe).await? {
+        Some(keypair) if secret.is_none() => {
+            // we then use the secret from CLI's given credentials
+            let sk = keypair
+                .secret_key()
+                .context("Failed to obtain the secret key from app's assigned keypair")?;
+            Ok((sk, true))
+        }
+        Some(_) | None => {
+            // prompt the user for a SK
+            let secret_key = get_secret_key(target, secret, msg)?;
+            Ok((SecretKey::ed25519_from_hex(&secret_key)?, false))
+        }
+    }
+}
+
+// Print the requested page of a list of transfers
+pub fn print_transfer_history(
+    history: &[TransferHistoryEntry],
+    pagination: &HistoryPagination,
+    output_fmt: OutputFmt,
+) -> Result<()> {
+    if pagination.page == 0 || pagination.page_size == 0 {
+        bail!("Both '--page' and '--page-size' must be greater than zero");
+    }
+
+    let total_pages = std::cmp::max(
+        1,
+        (history.len() + pagination.page_size - 1) / pagination.page_size,
+    );
+    let entries: Vec<&TransferHistoryEntry> = history
+        .iter()
+        .skip((pagination.page - 1) * pagination.page_size)
+        .take(pagination.page_size)
+        .collect();
+
+    match output_fmt {
+        OutputFmt::Pretty => {
+            let with_balances = history.iter().any(|entry| entry.balance_name.is_some());
+            let mut table = Table::new();
+            if with_balances {
+                table.add_row(row![bFg->"Spendable balance", bFg->"Direction", bFg->"Amount", bFg->"Counterparty", bFg->"TX_ID"]);
+            } else {
+                table.add_row(
+                    row![bFg->"Direction", bFg->"Amount", bFg->"Counterparty", bFg->"TX_ID"],
+                );
+            }
+            for entry in entries.iter() {
+                let direction = format!("{:?}", entry.direction);
+                let counterparty = entry
+                    .counterparty
+                    .clone()
+                    .unwrap_or_else(|| "<unknown>".to_string());
+                if with_balances {
+                    table.add_row(row![
+                        entry.balance_name.clone().unwrap_or_default(),
+                        direction,
+                        entry.amount,
+                        counterparty,
+                        entry.tx_id
+                    ]);
+                } else {
+                    table.add_row(row![direction, entry.amount, counterparty, entry.tx_id]);
+                }
+            }
+            println!(
+                "Transfers (page {} of {}, {} transfers in total):",
+                pagination.page,
+                total_pages,
+                history.len()
+            );
+            table.printstd();
+        }
+        OutputFmt::Csv => print!("{}", serialise_csv_records(&entries)?),
+        other => println!("{}", serialise_output(&entries, other)),
+    }
+
+    Ok(())
+}
+
 #[cfg(feature = "simulated-payouts")]
 pub async fn create_new_key(
     safe: &mut Safe,
     test_coins: bool,
     pay_with: Option<String>,
     preload: Option<String>,
+    keypair: Option<Keypair>,
 ) -> Result<(String, Keypair, String)> {
+    let pay_with = match pay_with {
+        Some(sk) => Some(resolve_sk_alias(&sk)?),
+        None => None,
+    };
+
     if test_coins {
         warn!("Note that the SafeKey to be created will be preloaded with **test coins** rather than real coins");
         let amount = match preload {
@@ -251,7 +881,13 @@ pub async fn create_new_key(
             Some(n) => n,
         };
 
-        let (xorurl, key_pair) = safe.keys_create_preload_test_coins(&amount).await?;
+        let (xorurl, key_pair) = match keypair {
+            Some(keypair) => {
+                let xorurl = safe.keys_preload_test_coins(&keypair, &amount).await?;
+                (xorurl, keypair)
+            }
+            None => safe.keys_create_preload_test_coins(&amount).await?,
+        };
 
         Ok((xorurl, key_pair, amount))
     } else {
@@ -268,15 +904,21 @@ pub async fn create_new_key(
         // '--pay-with' is either a Wallet XOR-URL, or a secret key
         // TODO: support Wallet XOR-URL, we now support only secret key
         // If the --pay-with is not provided the API will use the application's default wallet/sk
-        let (xorurl, key_pair) = match pay_with {
-            Some(payee) => {
+        if pay_with.is_none() {
+            debug!("Missing the '--pay-with' argument, using app's wallet for funds");
+        }
+        let (xorurl, key_pair) = match (keypair, pay_with) {
+            (Some(keypair), payee) => {
+                let xorurl = safe
+                    .keys_preload(&keypair, payee.as_deref(), &amount)
+                    .await?;
+                (xorurl, keypair)
+            }
+            (None, Some(payee)) => {
                 safe.keys_create_and_preload_from_sk_string(&payee, &amount)
                     .await?
             }
-            None => {
-                debug!("Missing the '--pay-with' argument, using app's wallet for funds");
-                safe.keys_create_and_preload(&amount).await?
-            }
+            (None, None) => safe.keys_create_and_preload(&amount).await?,
         };
 
         Ok((xorurl, key_pair, amount))
@@ -334,3 +976,36 @@ pub fn keypair_to_hex_strings(keypair: &Keypair) -> Result<(String, String)> {
 
     Ok((pk_hex, sk_hex))
 }
+
+fn parse_expiry_time(src: &str) -> Result<DateTime<Utc>> {
+    let time = DateTime::parse_from_rfc3339(src)
+        .map_err(|err| anyhow!("Invalid expiry time '{}': {}", src, err))?;
+    Ok(time.with_timezone(&Utc))
+}
+
+// Parse a duration made of a number and its unit, e.g. '90s', '30m', '24h', or '7d'
+fn parse_validity(src: &str) -> Result<Duration> {
+    let invalid = || {
+        anyhow!("Invalid validity time '{}', it must be a number followed by one of the units 's', 'm', 'h', or 'd' (e.g. '30m')", src)
+    };
+    // the unit may not be a single byte character, so split on its char boundary
+    let (value, unit) = match src.char_indices().last() {
+        Some((index, _)) => src.split_at(index),
+        None => return Err(invalid()),
+    };
+    let value = value.parse::<i64>().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => Some(value),
+        "m" => value.checked_mul(60),
+        "h" => value.checked_mul(60 * 60),
+        "d" => value.checked_mul(24 * 60 * 60),
+        _ => None,
+    };
+    match seconds {
+        Some(seconds) if seconds > 0 => {
+            Duration::from_std(std::time::Duration::from_secs(seconds as u64))
+                .map_err(|_| invalid())
+        }
+        _ => Err(invalid()),
+    }
+}
@@ -0,0 +1,317 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    helpers::{get_from_stdin, hex_to_xorname, serialise_output},
+    OutputFmt,
+};
+use anyhow::{anyhow, Result};
+use log::warn;
+use prettytable::Table;
+use serde::Serialize;
+use sn_api::{MapEntry, Safe};
+use std::str::FromStr;
+use structopt::StructOpt;
+
+// Default type tag to use for the Map
+const DEFAULT_MAP_TYPE_TAG: u64 = 1_300;
+
+#[derive(StructOpt, Debug)]
+pub enum MapSubCommands {
+    #[structopt(name = "create")]
+    /// Create a new empty Map on the Safe Network
+    Create {
+        /// The type tag to be set (by default is set to 1300)
+        #[structopt(long = "type")]
+        type_tag: Option<u64>,
+        /// The Xor name address (in Hex) where to store the Map (by default is a random location)
+        #[structopt(long = "xorname")]
+        xorname: Option<String>,
+    },
+    #[structopt(name = "insert")]
+    /// Insert a new entry into a Map, failing if the key already exists
+    Insert {
+        /// The target Map to insert the entry into
+        target: String,
+        /// The key of the new entry
+        key: String,
+        /// The value of the new entry. Specify '-' to read from stdin
+        value: String,
+        #[structopt(flatten)]
+        encoding: MapEncoding,
+    },
+    #[structopt(name = "get")]
+    /// Get the value and version of an entry of a Map
+    Get {
+        /// The target Map to get the entry from
+        target: String,
+        /// The key of the entry
+        key: String,
+        #[structopt(flatten)]
+        encoding: MapEncoding,
+    },
+    #[structopt(name = "update")]
+    /// Update the value of an existing entry of a Map
+    Update {
+        /// The target Map where the entry is
+        target: String,
+        /// The key of the entry
+        key: String,
+        /// The new value of the entry. Specify '-' to read from stdin
+        value: String,
+        /// The current version of the entry, the update is rejected if it doesn't match. By default the entry is updated regardless of its current version
+        #[structopt(long = "version")]
+        version: Option<u64>,
+        #[structopt(flatten)]
+        encoding: MapEncoding,
+    },
+    #[structopt(name = "delete")]
+    /// Delete an entry from a Map
+    Delete {
+        /// The target Map where the entry is
+        target: String,
+        /// The key of the entry
+        key: String,
+        /// The current version of the entry, the deletion is rejected if it doesn't match. By default the entry is deleted regardless of its current version
+        #[structopt(long = "version")]
+        version: Option<u64>,
+        #[structopt(flatten)]
+        encoding: MapEncoding,
+    },
+    #[structopt(name = "ls")]
+    /// List the entries of a Map
+    Ls {
+        /// The target Map to list the entries of
+        target: String,
+        #[structopt(flatten)]
+        encoding: MapEncoding,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub struct MapEncoding {
+    /// The encoding of the keys, either provided as arguments or output
+    #[structopt(long = "key-encoding", possible_values = &["text", "hex", "json"], default_value = "text")]
+    key_encoding: DataEncoding,
+    /// The encoding of the values, either provided as arguments or output
+    #[structopt(long = "value-encoding", possible_values = &["text", "hex", "json"], default_value = "text")]
+    value_encoding: DataEncoding,
+}
+
+// How the keys and values of a Map's entries are read from, and rendered to, strings
+#[derive(Debug, Clone, Copy)]
+pub enum DataEncoding {
+    Text,
+    Hex,
+    Json,
+}
+
+impl FromStr for DataEncoding {
+    type Err = String;
+    fn from_str(str: &str) -> Result<Self, String> {
+        match str {
+            "text" => Ok(Self::Text),
+            "hex" => Ok(Self::Hex),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "'{}' not supported. Supported values are text, hex, and json",
+                other
+            )),
+        }
+    }
+}
+
+impl DataEncoding {
+    fn encode(self, str: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Text => Ok(str.as_bytes().to_vec()),
+            Self::Hex => hex::decode(str).map_err(|err| anyhow!("Invalid hex string: {}", err)),
+            Self::Json => {
+                let value: serde_json::Value = serde_json::from_str(str)
+                    .map_err(|err| anyhow!("Invalid JSON string: {}", err))?;
+                Ok(serde_json::to_vec(&value)?)
+            }
+        }
+    }
+
+    fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Text => String::from_utf8_lossy(bytes).to_string(),
+            Self::Hex => hex::encode(bytes),
+            Self::Json => match serde_json::from_slice::<serde_json::Value>(bytes) {
+                Ok(value) => value.to_string(),
+                Err(err) => {
+                    warn!("Data stored in the Map is not valid JSON: {}", err);
+                    String::from_utf8_lossy(bytes).to_string()
+                }
+            },
+        }
+    }
+}
+
+// Entry of a Map as output by the 'get' and 'ls' subcommands
+#[derive(Debug, Serialize)]
+struct MapEntryOutput {
+    key: String,
+    value: String,
+    version: u64,
+}
+
+pub async fn map_commander(
+    cmd: MapSubCommands,
+    output_fmt: OutputFmt,
+    safe: &mut Safe,
+) -> Result<()> {
+    match cmd {
+        MapSubCommands::Create { type_tag, xorname } => {
+            let tag = type_tag.unwrap_or(DEFAULT_MAP_TYPE_TAG);
+            let xorname = match xorname.as_ref() {
+                Some(hex_str) => Some(hex_to_xorname(hex_str)?),
+                None => None,
+            };
+            let xorurl = safe.map_create(xorname, tag).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!("Map created at: \"{}\"", xorurl);
+            } else {
+                println!("{}", serialise_output(&xorurl, output_fmt));
+            }
+
+            Ok(())
+        }
+        MapSubCommands::Insert {
+            target,
+            key,
+            value,
+            encoding,
+        } => {
+            let key_bytes = encoding.key_encoding.encode(&key)?;
+            let value = read_value(&value, encoding.value_encoding)?;
+            safe.map_insert(&target, &key_bytes, &value).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!("Entry '{}' inserted into the Map at \"{}\"", key, target);
+            } else {
+                println!("{}", serialise_output(&(target, key), output_fmt));
+            }
+
+            Ok(())
+        }
+        MapSubCommands::Get {
+            target,
+            key,
+            encoding,
+        } => {
+            let key_bytes = encoding.key_encoding.encode(&key)?;
+            let (version, value) = safe.map_get(&target, &key_bytes).await?;
+            let value = encoding.value_encoding.decode(&value);
+
+            if OutputFmt::Pretty == output_fmt {
+                println!("[{}] {}", version, value);
+            } else {
+                let entry = MapEntryOutput {
+                    key,
+                    value,
+                    version,
+                };
+                println!("{}", serialise_output(&entry, output_fmt));
+            }
+
+            Ok(())
+        }
+        MapSubCommands::Update {
+            target,
+            key,
+            value,
+            version,
+            encoding,
+        } => {
+            let key_bytes = encoding.key_encoding.encode(&key)?;
+            let value = read_value(&value, encoding.value_encoding)?;
+            let new_version = safe
+                .map_update(&target, &key_bytes, &value, version)
+                .await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Entry '{}' of the Map at \"{}\" updated to version {}",
+                    key, target, new_version
+                );
+            } else {
+                println!("{}", serialise_output(&(key, new_version), output_fmt));
+            }
+
+            Ok(())
+        }
+        MapSubCommands::Delete {
+            target,
+            key,
+            version,
+            encoding,
+        } => {
+            let key_bytes = encoding.key_encoding.encode(&key)?;
+            safe.map_delete(&target, &key_bytes, version).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!("Entry '{}' deleted from the Map at \"{}\"", key, target);
+            } else {
+                println!("{}", serialise_output(&(target, key), output_fmt));
+            }
+
+            Ok(())
+        }
+        MapSubCommands::Ls { target, encoding } => {
+            let entries = safe.map_list(&target).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!("Map at \"{}\" contains {} entries:", target, entries.len());
+                print_map_entries(&entries, &encoding);
+            } else {
+                let entries: Vec<MapEntryOutput> = entries
+                    .iter()
+                    .map(|entry| MapEntryOutput {
+                        key: encoding.key_encoding.decode(&entry.key),
+                        value: encoding.value_encoding.decode(&entry.value),
+                        version: entry.version,
+                    })
+                    .collect();
+                println!("{}", serialise_output(&entries, output_fmt));
+            }
+
+            Ok(())
+        }
+    }
+}
+
+// Read the value of an entry from the argument, or from stdin if it's '-'
+fn read_value(value: &str, encoding: DataEncoding) -> Result<Vec<u8>> {
+    if value == "-" {
+        let bytes = get_from_stdin(Some("...awaiting the entry's value from STDIN"))?;
+        match encoding {
+            // stdin is taken as is, there is no need for it to be valid UTF-8
+            DataEncoding::Text => Ok(bytes),
+            _ => encoding.encode(String::from_utf8_lossy(&bytes).trim_end()),
+        }
+    } else {
+        encoding.encode(value)
+    }
+}
+
+fn print_map_entries(entries: &[MapEntry], encoding: &MapEncoding) {
+    let mut table = Table::new();
+    table.add_row(row![bFg->"Key", bFg->"Value", bFg->"Version"]);
+    for entry in entries.iter() {
+        table.add_row(row![
+            encoding.key_encoding.decode(&entry.key),
+            encoding.value_encoding.decode(&entry.value),
+            entry.version
+        ]);
+    }
+    table.printstd();
+}
@@ -8,6 +8,7 @@
 // Software.
 
 pub mod auth;
+pub mod cache;
 pub mod cat;
 pub mod config;
 pub mod dog;
@@ -15,14 +16,17 @@ pub mod files;
 mod files_get;
 mod helpers;
 pub mod keys;
+pub mod map;
 pub mod networks;
 pub mod node;
 pub mod nrs;
+pub mod pay;
 pub mod safe_id;
 pub mod seq;
 pub mod setup;
 pub mod update;
 pub mod wallet;
+pub mod watch;
 pub mod xorurl;
 
 use structopt::{clap::AppSettings, StructOpt};
@@ -33,6 +37,7 @@ pub enum OutputFmt {
     Json,
     JsonCompact,
     Yaml,
+    Csv,
 }
 
 impl std::str::FromStr for OutputFmt {
@@ -42,9 +47,10 @@ impl std::str::FromStr for OutputFmt {
             "json" => Ok(Self::Json),
             "jsoncompact" => Ok(Self::JsonCompact),
             "yaml" => Ok(Self::Yaml),
+            "csv" => Ok(Self::Csv),
             other => {
                 Err(format!(
-                    "Output serialisation format '{}' not supported. Supported values are json, jsoncompact, and yaml",
+                    "Output serialisation format '{}' not supported. Supported values are json, jsoncompact, yaml, and csv",
                     other
                 ))
             }
@@ -87,6 +93,17 @@ pub enum SubCommands {
         #[structopt(subcommand)]
         cmd: Option<auth::AuthSubCommands>,
     },
+    #[structopt(
+        name = "cache",
+        no_version,
+        global_settings(&[AppSettings::DisableVersion]),
+    )]
+    /// Manage the local cache of content fetched from the Safe Network
+    Cache {
+        /// subcommands
+        #[structopt(subcommand)]
+        cmd: Option<cache::CacheSubCommands>,
+    },
     #[structopt(
         name = "cat",
         no_version,
@@ -101,6 +118,13 @@ pub enum SubCommands {
     )]
     /// Inspect data on the SAFE Network providing only metadata information about the content
     Dog(dog::DogCommands),
+    #[structopt(
+        name = "watch",
+        no_version,
+        global_settings(&[AppSettings::DisableVersion]),
+    )]
+    /// Watch content on the SAFE Network, reporting every new version found
+    Watch(watch::WatchCommands),
     #[structopt(
         name = "files",
         no_version,
@@ -136,6 +160,13 @@ pub enum SubCommands {
     )]
     /// Manage wallets on the SAFE Network
     Wallet(wallet::WalletSubCommands),
+    #[structopt(
+        name = "pay",
+        no_version,
+        global_settings(&[AppSettings::DisableVersion]),
+    )]
+    /// Pay a payment request URL, e.g. one generated with 'safe keys request'
+    Pay(pay::PayCommands),
     #[structopt(
         name = "xorurl",
         no_version,
@@ -162,6 +193,13 @@ pub enum SubCommands {
     )]
     /// Manage Sequences on the Safe Network
     Seq(seq::SeqSubCommands),
+    #[structopt(
+        name = "map",
+        no_version,
+        global_settings(&[AppSettings::DisableVersion]),
+    )]
+    /// Manage Maps on the Safe Network
+    Map(map::MapSubCommands),
     // #[structopt(name = "safe-id")]
     // /// Manage identities on the Safe Network
     // SafeId(safe_id::SafeIdSubCommands),
@@ -184,3 +222,15 @@ pub enum SubCommands {
         cmd: Option<node::NodeSubCommands>,
     },
 }
+
+impl SubCommands {
+    // Only the commands which output a list of flat records can be serialised as CSV
+    pub fn supports_csv_output(&self) -> bool {
+        matches!(
+            self,
+            Self::Keys(keys::KeysSubCommands::History { .. })
+                | Self::Wallet(wallet::WalletSubCommands::History { .. })
+                | Self::Wallet(wallet::WalletSubCommands::Transfer { batch: Some(_), .. })
+        )
+    }
+}
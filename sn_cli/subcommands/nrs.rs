@@ -8,15 +8,63 @@
 // Software.
 
 use super::{
-    helpers::{get_from_arg_or_stdin, notice_dry_run, serialise_output},
+    helpers::{get_from_arg_or_stdin, notice_dry_run, parse_writer, serialise_output},
     OutputFmt,
 };
-use anyhow::Result;
+use crate::operations::safe_net::connect;
+use anyhow::{anyhow, bail, Context, Result};
 use prettytable::{format::FormatBuilder, Table};
-use sn_api::{safeurl::XorUrl, Safe};
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use sn_api::{
+    nrs::{DefaultRdf, NrsMap, PREDICATE_LINK},
+    safeurl::{HomographRisk, SafeUrl, XorUrl},
+    PublicKey, Safe,
+};
+use std::{collections::BTreeMap, fs, path::Path};
 use structopt::StructOpt;
 
+// Supported formats for the NRS zone files
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoneFormat {
+    Toml,
+    Yaml,
+}
+
+impl std::str::FromStr for ZoneFormat {
+    type Err = String;
+    fn from_str(str: &str) -> Result<Self, String> {
+        match str {
+            "toml" => Ok(Self::Toml),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            other => Err(format!(
+                "Zone file format '{}' not supported. Supported values are toml and yaml",
+                other
+            )),
+        }
+    }
+}
+
+// Human-editable representation of an NRS Map, with the link of each
+// sub name and what the top level name defaults to
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct NrsZone {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default)]
+    default: ZoneDefault,
+    #[serde(default)]
+    names: BTreeMap<String, XorUrl>,
+}
+
+// The default can either be an alias to one of the sub names or a link
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ZoneDefault {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sub_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link: Option<XorUrl>,
+}
+
 #[derive(StructOpt, Debug)]
 pub enum NrsSubCommands {
     #[structopt(name = "add")]
@@ -33,6 +81,9 @@ pub enum NrsSubCommands {
         /// If --default is set, the default name is set using a direct link to the final destination that was provided with `--link`, rather than a link to the sub name being added (which is the default behaviour if this flag is not passed)
         #[structopt(long = "direct")]
         direct_link: bool,
+        /// Fail if the NRS Map Container is not currently at this version, rather than merging the change with any other concurrent update
+        #[structopt(long = "expect-version")]
+        expect_version: Option<u64>,
     },
     #[structopt(name = "create")]
     /// Create a new public name
@@ -45,12 +96,48 @@ pub enum NrsSubCommands {
         /// The default name is set using a direct link to the final destination that was provided with `--link`, rather than a link to the sub name being created (which is the default behaviour if this flag is not passed)
         #[structopt(long = "direct")]
         direct_link: bool,
+        /// Create the name even if it mixes characters from different scripts which could make it look like another name
+        #[structopt(long = "allow-confusable")]
+        allow_confusable: bool,
+        /// Grant another user permission to add and remove sub names, given its public key or SafeKey URL. It can be set multiple times
+        #[structopt(long = "writer", number_of_values = 1, parse(try_from_str = parse_writer))]
+        writers: Vec<PublicKey>,
+    },
+    #[structopt(name = "export")]
+    /// Export all the subnames of an NRS name, and their links, in a zone file format
+    Export {
+        /// The NRS name to export
+        name: String,
+        /// The local file to write the zone to. If not provided it's written to stdout
+        #[structopt(short = "f", long = "file")]
+        file: Option<String>,
+        /// Format of the zone: toml (default, unless inferred from the file extension) or yaml
+        #[structopt(long = "format")]
+        format: Option<ZoneFormat>,
+    },
+    #[structopt(name = "apply")]
+    /// Update an NRS name so it contains exactly the subnames and links found in a zone file, publishing all changes as a single new version
+    Apply {
+        /// The NRS name to apply the zone to
+        name: String,
+        /// The local zone file, as generated by 'nrs export'
+        #[structopt(short = "f", long = "file")]
+        file: String,
+        /// Format of the zone file: toml or yaml. If not provided it's inferred from the file extension
+        #[structopt(long = "format")]
+        format: Option<ZoneFormat>,
+        /// Fail if the NRS Map Container is not currently at this version, rather than merging the change with any other concurrent update
+        #[structopt(long = "expect-version")]
+        expect_version: Option<u64>,
     },
     #[structopt(name = "remove")]
     /// Remove a subname from an NRS name
     Remove {
         /// The name to remove
         name: String,
+        /// Fail if the NRS Map Container is not currently at this version, rather than merging the change with any other concurrent update
+        #[structopt(long = "expect-version")]
+        expect_version: Option<u64>,
     },
 }
 
@@ -65,11 +152,30 @@ pub async fn nrs_commander(
             name,
             link,
             direct_link,
+            allow_confusable,
+            writers,
         } => {
             // TODO: Where do we store/reference these? add it to the Root container,
             // sanitize name / spacing etc., validate destination?
             let link = get_from_arg_or_stdin(link, Some("...awaiting link URL from stdin"))?;
 
+            // Protect users from creating names which can be mistaken for another one
+            let name_url =
+                SafeUrl::from_nrsurl(&format!("safe://{}", name.replace("safe://", "")))?;
+            match name_url.homograph_risk() {
+                Some(risk @ HomographRisk::MixedScript { .. }) if !allow_confusable => bail!(
+                    "The name '{}' could be mistaken for a different one since {}. Use '--allow-confusable' if you still want to create it",
+                    name_url.public_name_unicode(),
+                    risk
+                ),
+                Some(risk) => eprintln!(
+                    "WARNING: the name '{}' could be mistaken for a different one since {}",
+                    name_url.public_name_unicode(),
+                    risk
+                ),
+                None => {}
+            }
+
             if dry_run && OutputFmt::Pretty == output_fmt {
                 notice_dry_run();
             }
@@ -78,7 +184,14 @@ pub async fn nrs_commander(
             let default = true;
 
             let (nrs_map_container_xorurl, processed_entries, _nrs_map) = safe
-                .nrs_map_container_create(&name, &link, default, direct_link, dry_run)
+                .nrs_map_container_create_with_writers(
+                    &name,
+                    &link,
+                    default,
+                    direct_link,
+                    dry_run,
+                    &writers,
+                )
                 .await?;
 
             // Now let's just print out a summary
@@ -99,6 +212,7 @@ pub async fn nrs_commander(
             link,
             default,
             direct_link,
+            expect_version,
         } => {
             let link = get_from_arg_or_stdin(link, Some("...awaiting link URL from stdin"))?;
 
@@ -107,7 +221,7 @@ pub async fn nrs_commander(
             }
 
             let (version, xorurl, processed_entries, _nrs_map) = safe
-                .nrs_map_container_add(&name, &link, default, direct_link, dry_run)
+                .nrs_map_container_add(&name, &link, default, direct_link, expect_version, dry_run)
                 .await?;
 
             // Now let's just print out the summary
@@ -120,13 +234,17 @@ pub async fn nrs_commander(
 
             Ok(())
         }
-        NrsSubCommands::Remove { name } => {
+        NrsSubCommands::Remove {
+            name,
+            expect_version,
+        } => {
             if dry_run && OutputFmt::Pretty == output_fmt {
                 notice_dry_run();
             }
 
-            let (version, xorurl, processed_entries, _nrs_map) =
-                safe.nrs_map_container_remove(&name, dry_run).await?;
+            let (version, xorurl, processed_entries, _nrs_map) = safe
+                .nrs_map_container_remove(&name, expect_version, dry_run)
+                .await?;
 
             // Now let's just print out the summary
             print_summary(
@@ -136,11 +254,148 @@ pub async fn nrs_commander(
                 processed_entries,
             );
 
+            Ok(())
+        }
+        NrsSubCommands::Export { name, file, format } => {
+            let format = format
+                .or_else(|| file.as_deref().and_then(zone_format_from_path))
+                .unwrap_or(ZoneFormat::Toml);
+            let url = format!("safe://{}", name.replace("safe://", ""));
+            let (_version, nrs_map) = safe.nrs_map_container_get(&url).await?;
+
+            let zone = nrs_map_to_zone(&name, &nrs_map);
+            let serialised_zone = match format {
+                ZoneFormat::Toml => toml::to_string(&zone)
+                    .map_err(|err| anyhow!("Failed to serialise zone to toml: {}", err))?,
+                ZoneFormat::Yaml => serde_yaml::to_string(&zone)
+                    .map_err(|err| anyhow!("Failed to serialise zone to yaml: {}", err))?,
+            };
+
+            match file {
+                Some(path) => fs::write(&path, serialised_zone)
+                    .with_context(|| format!("Couldn't write zone to file: \"{}\"", path))?,
+                None => print!("{}", serialised_zone),
+            }
+
+            Ok(())
+        }
+        NrsSubCommands::Apply {
+            name,
+            file,
+            format,
+            expect_version,
+        } => {
+            let format = format
+                .or_else(|| zone_format_from_path(&file))
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Couldn't infer the format of zone file \"{}\", please specify it with '--format'",
+                        file
+                    )
+                })?;
+            let content = fs::read_to_string(&file)
+                .with_context(|| format!("Couldn't read zone file: \"{}\"", file))?;
+            let zone: NrsZone = match format {
+                ZoneFormat::Toml => toml::from_str(&content)
+                    .map_err(|err| anyhow!("Failed to parse toml zone file: {}", err))?,
+                ZoneFormat::Yaml => serde_yaml::from_str(&content)
+                    .map_err(|err| anyhow!("Failed to parse yaml zone file: {}", err))?,
+            };
+            let (sub_names, default) = zone_to_nrs_entries(&name, zone)?;
+
+            if dry_run {
+                // We need to read the current NRS Map to be able to generate the plan
+                connect(safe).await?;
+                if OutputFmt::Pretty == output_fmt {
+                    notice_dry_run();
+                }
+            }
+
+            let (version, xorurl, processed_entries, _nrs_map) = safe
+                .nrs_map_container_apply(&name, &sub_names, &default, expect_version, dry_run)
+                .await?;
+
+            let header_msg = if processed_entries.is_empty() {
+                format!("NRS Map is already up to date (version {})", version)
+            } else if dry_run {
+                format!("Plan to update NRS Map (version {})", version)
+            } else {
+                format!("NRS Map updated (version {})", version)
+            };
+            print_summary(output_fmt, &header_msg, xorurl, processed_entries);
+
             Ok(())
         }
     }
 }
 
+fn zone_format_from_path(path: &str) -> Option<ZoneFormat> {
+    Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| ext.parse().ok())
+}
+
+fn nrs_map_to_zone(name: &str, nrs_map: &NrsMap) -> NrsZone {
+    let default = match &nrs_map.default {
+        DefaultRdf::NotSet => ZoneDefault::default(),
+        DefaultRdf::ExistingRdf(sub_name) => ZoneDefault {
+            sub_name: Some(sub_name.clone()),
+            link: None,
+        },
+        DefaultRdf::OtherRdf(def_data) => ZoneDefault {
+            sub_name: None,
+            link: def_data.get(PREDICATE_LINK).cloned(),
+        },
+    };
+
+    NrsZone {
+        name: Some(name.replace("safe://", "")),
+        default,
+        names: nrs_map.get_sub_names_links(),
+    }
+}
+
+fn zone_to_nrs_entries(
+    name: &str,
+    zone: NrsZone,
+) -> Result<(BTreeMap<String, XorUrl>, DefaultRdf)> {
+    if let Some(zone_name) = &zone.name {
+        if zone_name.replace("safe://", "") != name.replace("safe://", "") {
+            bail!(
+                "The zone file is for NRS name \"{}\" but it's being applied to \"{}\"",
+                zone_name,
+                name
+            );
+        }
+    }
+
+    let default = match zone.default {
+        ZoneDefault {
+            sub_name: Some(_),
+            link: Some(_),
+        } => bail!("The default in the zone file can be either a sub name or a link, but not both"),
+        ZoneDefault {
+            sub_name: Some(sub_name),
+            link: None,
+        } => DefaultRdf::ExistingRdf(sub_name),
+        ZoneDefault {
+            sub_name: None,
+            link: Some(link),
+        } => {
+            let mut def_data = BTreeMap::new();
+            def_data.insert(PREDICATE_LINK.to_string(), link);
+            DefaultRdf::OtherRdf(def_data)
+        }
+        ZoneDefault {
+            sub_name: None,
+            link: None,
+        } => DefaultRdf::NotSet,
+    };
+
+    Ok((zone.names, default))
+}
+
 fn print_summary(
     output_fmt: OutputFmt,
     header_msg: &str,
@@ -0,0 +1,89 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    helpers::{get_from_arg_or_stdin, prompt_user, serialise_output},
+    OutputFmt,
+};
+use crate::operations::keystore::resolve_sk_alias;
+use anyhow::{bail, Result};
+use log::debug;
+use sn_api::Safe;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct PayCommands {
+    /// The payment request URL, e.g. one generated with 'safe keys request', otherwise pulled from stdin if not provided
+    request: Option<String>,
+    /// Source Wallet URL, or SafeKey's secret key, to pay from. If not provided, funds from the application's default SafeKey will be used
+    #[structopt(long = "from")]
+    from: Option<String>,
+    /// The number of safecoins expected to be paid, the payment is rejected if it doesn't match the amount requested
+    #[structopt(long = "amount")]
+    amount: Option<String>,
+    /// Make the payment without asking for confirmation
+    #[structopt(short = "y", long = "yes")]
+    yes: bool,
+}
+
+pub async fn pay_commander(cmd: PayCommands, output_fmt: OutputFmt, safe: &mut Safe) -> Result<()> {
+    let url = get_from_arg_or_stdin(
+        cmd.request,
+        Some("...awaiting payment request URL from STDIN stream..."),
+    )?;
+
+    let request = safe.payment_request_parse(&url)?;
+    debug!("Payment request parsed: {:?}", request);
+    if let Some(amount) = &cmd.amount {
+        request.check_amount(amount)?;
+    }
+
+    if !cmd.yes {
+        if !isatty::stdin_isatty() {
+            bail!("Cannot ask for confirmation of the payment as stdin is not a terminal, use '--yes' to make it without confirmation");
+        }
+        println!("Payment request:");
+        println!("  To: {}", request.to);
+        println!("  Amount: {} safecoins", request.amount);
+        if let Some(memo) = &request.memo {
+            println!("  Memo: {}", memo);
+        }
+        if let Some(expires) = &request.expires {
+            println!("  Expires: {}", expires);
+        }
+        let answer = prompt_user("Make the payment? [y/N]: ", "").unwrap_or_default();
+        if !answer.eq_ignore_ascii_case("y") && !answer.eq_ignore_ascii_case("yes") {
+            bail!("Payment cancelled");
+        }
+    }
+
+    let tx_id = match cmd.from {
+        Some(wallet_url) if wallet_url.starts_with("safe://") => {
+            safe.wallet_transfer(&request.amount, &wallet_url, &request.to)
+                .await?
+        }
+        Some(sk) => {
+            let sk = resolve_sk_alias(&sk)?;
+            safe.keys_transfer(&request.amount, Some(&sk), &request.to)
+                .await?
+        }
+        None => {
+            safe.keys_transfer(&request.amount, None, &request.to)
+                .await?
+        }
+    };
+
+    if OutputFmt::Pretty == output_fmt {
+        println!("Success. TX_ID: {}", tx_id);
+    } else {
+        println!("{}", serialise_output(&(request, tx_id), output_fmt));
+    }
+
+    Ok(())
+}
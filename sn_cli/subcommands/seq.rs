@@ -9,12 +9,16 @@
 
 use super::{
     helpers::{
-        get_from_arg_or_stdin, get_from_stdin, hex_to_xorname, parse_stdin_arg, serialise_output,
+        get_from_arg_or_stdin, get_from_stdin, hex_to_xorname, parse_stdin_arg, parse_writer,
+        serialise_output,
     },
     OutputFmt,
 };
-use anyhow::Result;
-use sn_api::Safe;
+use anyhow::{bail, Result};
+use futures::StreamExt;
+use prettytable::Table;
+use serde::Serialize;
+use sn_api::{fetch::SafeData, PublicKey, Safe, WatchOptions};
 use structopt::StructOpt;
 
 // Default type tag to use for the Sequence
@@ -36,6 +40,9 @@ pub enum SeqSubCommands {
         /// Store the Sequence as Private (default is Public)
         #[structopt(short = "p", long = "private")]
         private: bool,
+        /// Grant another user permission to append to the Sequence (and to read it if it's Private), given its public key or SafeKey URL. It can be set multiple times
+        #[structopt(long = "writer", number_of_values = 1, parse(try_from_str = parse_writer))]
+        writers: Vec<PublicKey>,
     },
     #[structopt(name = "append")]
     /// Append an element to an existing Sequence on the network
@@ -46,6 +53,56 @@ pub enum SeqSubCommands {
         /// The target Sequence to append the data to
         target: Option<String>,
     },
+    #[structopt(name = "perms")]
+    /// List the permissions of a Sequence, including FilesContainers and NRS Map Containers. Permissions can only be set when the Sequence is created
+    Perms(SeqPermsSubCommands),
+    #[structopt(name = "log")]
+    /// List the entries of a Sequence, from the oldest to the newest
+    Log {
+        /// The target Sequence to list the entries of
+        target: Option<String>,
+        /// The version of the first entry to list
+        #[structopt(long = "from", default_value = "0")]
+        from: u64,
+        /// The maximum number of entries to list, all entries up to the latest are listed by default
+        #[structopt(long = "limit")]
+        limit: Option<u64>,
+        /// Renders the entries as hex
+        #[structopt(short = "x", long = "hexdump")]
+        hexdump: bool,
+    },
+    #[structopt(name = "tail")]
+    /// Show the latest entries of a Sequence, optionally waiting for new entries to be appended
+    Tail {
+        /// The target Sequence to show the latest entries of
+        target: Option<String>,
+        /// The number of latest entries to show
+        #[structopt(long = "lines", default_value = "10")]
+        lines: u64,
+        /// Keep polling the Sequence and output new entries as they are appended
+        #[structopt(short = "f", long = "follow")]
+        follow: bool,
+        /// Renders the entries as hex
+        #[structopt(short = "x", long = "hexdump")]
+        hexdump: bool,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+pub enum SeqPermsSubCommands {
+    #[structopt(name = "ls")]
+    /// List the owner of a Sequence and the permissions granted to its users
+    Ls {
+        /// The target Sequence, FilesContainer, or NRS-URL of an NRS Map Container
+        target: Option<String>,
+    },
+}
+
+// Entry of a Sequence as output by the 'log' and 'tail' subcommands
+#[derive(Debug, Serialize)]
+struct SequenceEntry {
+    version: u64,
+    data: String,
 }
 
 pub async fn seq_commander(
@@ -59,6 +116,7 @@ pub async fn seq_commander(
             type_tag,
             xorname,
             private,
+            writers,
         } => {
             let tag = type_tag.unwrap_or(DEFAULT_SEQUENCE_TYPE_TAG);
             let xorname = match xorname.as_ref() {
@@ -67,18 +125,14 @@ pub async fn seq_commander(
             };
 
             // If data is '-' then we read arg from STDIN
-            let xorurl = if data == "-" {
-                safe.sequence_create(
-                    &get_from_stdin(Some("...awaiting data that will be stored from STDIN"))?,
-                    xorname,
-                    tag,
-                    private,
-                )
-                .await?
+            let data = if data == "-" {
+                get_from_stdin(Some("...awaiting data that will be stored from STDIN"))?
             } else {
-                safe.sequence_create(data.as_bytes(), xorname, tag, private)
-                    .await?
+                data.as_bytes().to_vec()
             };
+            let xorurl = safe
+                .sequence_create_with_writers(&data, xorname, tag, private, &writers)
+                .await?;
 
             if OutputFmt::Pretty == output_fmt {
                 println!(
@@ -114,5 +168,173 @@ pub async fn seq_commander(
 
             Ok(())
         }
+        SeqSubCommands::Perms(SeqPermsSubCommands::Ls { target }) => {
+            let target_url =
+                get_from_arg_or_stdin(target, Some("...awaiting target URl from STDIN"))?;
+            let perms = safe.sequence_get_permissions(&target_url).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "{} Sequence at \"{}\" owned by: {}",
+                    if perms.private { "Private" } else { "Public" },
+                    target_url,
+                    perms.owner
+                );
+                let mut table = Table::new();
+                table.add_row(row![bFg->"User", bFg->"Read", bFg->"Append"]);
+                for user in perms.users.iter() {
+                    table.add_row(row![user.user, user.read, user.append]);
+                }
+                table.printstd();
+            } else {
+                println!("{}", serialise_output(&perms, output_fmt));
+            }
+
+            Ok(())
+        }
+        SeqSubCommands::Log {
+            target,
+            from,
+            limit,
+            hexdump,
+        } => {
+            let target_url =
+                get_from_arg_or_stdin(target, Some("...awaiting target URl from STDIN"))?;
+            if limit == Some(0) {
+                bail!("The '--limit' must be greater than zero");
+            }
+
+            let to = limit.map(|limit| from.saturating_add(limit));
+            let entries = safe.sequence_get_range(&target_url, from, to).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                if entries.is_empty() {
+                    println!("No entries found in the Sequence from version {}", from);
+                }
+                for (version, data) in entries.iter() {
+                    print_entry(*version, data, hexdump, output_fmt);
+                }
+            } else {
+                let entries: Vec<SequenceEntry> = entries
+                    .iter()
+                    .map(|(version, data)| to_output_entry(*version, data, hexdump))
+                    .collect();
+                println!("{}", serialise_output(&entries, output_fmt));
+            }
+
+            Ok(())
+        }
+        SeqSubCommands::Tail {
+            target,
+            lines,
+            follow,
+            hexdump,
+        } => {
+            let target_url =
+                get_from_arg_or_stdin(target, Some("...awaiting target URl from STDIN"))?;
+
+            if follow {
+                return follow_sequence(safe, &target_url, lines, hexdump, output_fmt).await;
+            }
+
+            let (last_version, _) = safe.sequence_get_latest(&target_url).await?;
+            let from = (last_version + 1).saturating_sub(lines);
+            for (version, data) in safe
+                .sequence_get_range(&target_url, from, Some(last_version + 1))
+                .await?
+            {
+                print_entry(version, &data, hexdump, output_fmt);
+            }
+
+            Ok(())
+        }
+    }
+}
+
+// Output the latest entries of a Sequence, and then the new entries as they are appended.
+// The Sequence is watched for new versions, starting from the current one so no entries
+// are missed between showing the latest ones and polling for new ones.
+async fn follow_sequence(
+    safe: &mut Safe,
+    url: &str,
+    lines: u64,
+    hexdump: bool,
+    output_fmt: OutputFmt,
+) -> Result<()> {
+    let options = WatchOptions {
+        yield_current: true,
+        ..WatchOptions::default()
+    };
+    let mut events = Box::pin(safe.watch_with_options(url, options));
+    let mut last_version = None;
+    let mut last_error = None;
+    while let Some(event) = events.next().await {
+        // The URL is resolved by the watch, e.g. if it's an NRS-URL, thus the entries
+        // are read from the Sequence it was resolved to
+        let (xorurl, latest_version) = match event {
+            Ok(SafeData::PublicSequence {
+                xorurl, version, ..
+            })
+            | Ok(SafeData::PrivateSequence {
+                xorurl, version, ..
+            }) => (xorurl, version),
+            Ok(_) => bail!("The URL doesn't target a Sequence: {}", url),
+            Err(err) => {
+                // The stream only ends after an error if the URL cannot be watched at all,
+                // otherwise we just keep following the Sequence
+                eprintln!("Failed to poll Sequence for new entries: {}", err);
+                last_error = Some(err);
+                continue;
+            }
+        };
+        last_error = None;
+
+        let from = match last_version {
+            Some(version) => version + 1,
+            None => (latest_version + 1).saturating_sub(lines),
+        };
+        let mut seq_url = Safe::parse_url(&xorurl)?;
+        seq_url.set_content_version(None);
+        for (version, data) in safe
+            .sequence_get_range(&seq_url.to_string(), from, Some(latest_version + 1))
+            .await?
+        {
+            print_entry(version, &data, hexdump, output_fmt);
+        }
+        last_version = Some(latest_version);
+    }
+
+    match last_error {
+        Some(err) => Err(err.into()),
+        None => Ok(()),
+    }
+}
+
+fn to_output_entry(version: u64, data: &[u8], hexdump: bool) -> SequenceEntry {
+    let data = if hexdump {
+        hex::encode(data)
+    } else {
+        String::from_utf8_lossy(data).to_string()
+    };
+    SequenceEntry { version, data }
+}
+
+// Output an entry of a Sequence, serialised entries are output one per line,
+// thus JSON is always compact, so they can be processed as a stream
+fn print_entry(version: u64, data: &[u8], hexdump: bool, output_fmt: OutputFmt) {
+    if OutputFmt::Pretty == output_fmt {
+        if hexdump {
+            println!("Version {}:", version);
+            println!("{}", pretty_hex::pretty_hex(&data));
+        } else {
+            println!("[{}] {}", version, String::from_utf8_lossy(data));
+        }
+    } else {
+        let output_fmt = match output_fmt {
+            OutputFmt::Json => OutputFmt::JsonCompact,
+            other => other,
+        };
+        let entry = to_output_entry(version, data, hexdump);
+        println!("{}", serialise_output(&entry, output_fmt));
     }
 }
@@ -8,15 +8,38 @@
 // Software.
 
 use super::{
-    helpers::{get_from_arg_or_stdin, get_secret_key, serialise_output},
-    keys::{create_new_key, print_new_key_output},
+    helpers::{
+        get_from_arg_or_stdin, get_secret_key, read_secret, serialise_csv_records, serialise_output,
+    },
+    keys::{create_new_key, print_new_key_output, print_transfer_history, HistoryPagination},
     OutputFmt,
 };
-use anyhow::Result;
+use crate::operations::{
+    batch_transfer::{
+        default_results_path, journal_entry_id, read_batch_file, BatchFormat, BatchJournal,
+        BatchResult, BatchStatus, BatchTransfer, JournalEntry,
+    },
+    keystore::resolve_sk_alias,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use futures::stream::{self, StreamExt};
 use log::debug;
-use sn_api::{ed_sk_from_hex, sk_to_hex, Keypair, Safe, SecretKey};
+use prettytable::Table;
+use sn_api::{
+    ed_sk_from_hex, parse_coins_amount, sk_to_hex,
+    wallet::{CoinSelection, WalletTransferReport},
+    Keypair, Safe, SecretKey, MNEMONIC_DEFAULT_GAP_LIMIT,
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 use structopt::StructOpt;
 
+// Number of destinations of a batch validated concurrently if not specified
+const BATCH_DEFAULT_VALIDATE_CONCURRENCY: usize = 1;
+
 #[derive(StructOpt, Debug)]
 pub enum WalletSubCommands {
     #[structopt(name = "insert")]
@@ -39,12 +62,44 @@ pub enum WalletSubCommands {
         /// Set the inserted SafeKey as the default one in the target Wallet
         #[structopt(long = "default")]
         default: bool,
+        /// Insert a watch-only balance instead, given the SafeKey's safe://xor-url or its public key. Its balance can be checked, and it can receive transfers, but it cannot be spent from
+        #[structopt(long = "watch-only", conflicts_with_all = &["sk", "keyurl", "default"])]
+        watch_only: Option<String>,
     },
     #[structopt(name = "balance")]
     /// Query a Wallet's total balance
     Balance {
         /// The target Wallet to check the total balance
         target: Option<String>,
+        /// Report the total balance of the Wallet's watch-only balances instead of the spendable ones
+        #[structopt(long = "watch-only")]
+        watch_only: bool,
+    },
+    #[structopt(name = "remove")]
+    /// Remove a spendable balance from a Wallet
+    Remove {
+        /// The target Wallet
+        target: String,
+        /// The friendly name of the spendable balance to remove
+        name: String,
+    },
+    #[structopt(name = "rename")]
+    /// Change the friendly name of a Wallet's spendable balance
+    Rename {
+        /// The target Wallet
+        target: String,
+        /// The current friendly name of the spendable balance
+        name: String,
+        /// The new friendly name for the spendable balance
+        new_name: String,
+    },
+    #[structopt(name = "set-default")]
+    /// Set the default spendable balance of a Wallet
+    SetDefault {
+        /// The target Wallet
+        target: String,
+        /// The friendly name of the spendable balance to set as default
+        name: String,
     },
     #[structopt(name = "create")]
     /// Create a new Wallet
@@ -70,18 +125,65 @@ pub enum WalletSubCommands {
         /// Preload with a balance
         #[structopt(long = "preload")]
         preload: Option<String>,
+        /// Rebuild the Wallet's spendable balances from the SafeKeys derived from a mnemonic phrase, which is prompted for, or read from stdin. If none of them has been used yet, the first one is inserted, preloaded as when generating a new SafeKey
+        #[structopt(
+            long = "mnemonic",
+            conflicts_with_all = &["no-balance", "keyurl", "sk", "name"]
+        )]
+        mnemonic: bool,
+        /// The number of consecutive unused SafeKeys to derive from the mnemonic phrase before stopping the search for used ones
+        #[structopt(long = "gap-limit", requires = "mnemonic")]
+        gap_limit: Option<u32>,
     },
     #[structopt(name = "transfer")]
     /// Transfer safecoins from one Wallet to another, or to a SafeKey
     Transfer {
         /// Number of safecoins to transfer
-        amount: String,
+        #[structopt(required_unless = "batch")]
+        amount: Option<String>,
         /// Source Wallet URL
         #[structopt(long = "from")]
         from: String,
         /// The receiving Wallet/SafeKey URL or public key, otherwise pulled from stdin if not provided
         #[structopt(long = "to")]
         to: Option<String>,
+        /// Make the transfers listed in a CSV file (with 'to', 'amount', and optional 'memo' columns), or a JSON file (with an array of objects with those fields). Transfers already made are recorded in a journal next to the file, so the batch can be safely re-run
+        #[structopt(
+            long = "batch",
+            parse(from_os_str),
+            conflicts_with_all = &["amount", "to", "coin-selection", "from-balances"]
+        )]
+        batch: Option<PathBuf>,
+        /// Maximum number of destinations of the batch to be validated concurrently. The transfers themselves are always made one after the other, since they are all debited from the same spendable balance
+        #[structopt(long = "validate-concurrency", requires = "batch")]
+        validate_concurrency: Option<usize>,
+        /// File where to write the results of the batch, in the same format as the batch file. Defaults to '<batch file name>.results.<csv|json>'
+        #[structopt(long = "results", parse(from_os_str), requires = "batch")]
+        results: Option<PathBuf>,
+        /// Fund the transfer from as many spendable balances of the source Wallet as needed, selecting them with the given strategy
+        #[structopt(long = "coin-selection", possible_values = &["smallest-first", "largest-first"])]
+        coin_selection: Option<CoinSelection>,
+        /// Fund the transfer from the spendable balances of the source Wallet with the given friendly names, in the order given (e.g. 'savings,expenses'). Each name can only be provided once
+        #[structopt(
+            long = "from-balances",
+            use_delimiter = true,
+            conflicts_with = "coin-selection"
+        )]
+        from_balances: Option<Vec<String>>,
+    },
+    #[structopt(name = "history")]
+    /// List the incoming and outgoing transfers of a Wallet's spendable balances
+    History {
+        /// The target Wallet, or one of its spendable balances by providing its friendly name as the URL path
+        target: Option<String>,
+        #[structopt(flatten)]
+        pagination: HistoryPagination,
+    },
+    #[structopt(name = "migrate")]
+    /// Encrypt the secret keys of spendable balances stored in plaintext by older versions
+    Migrate {
+        /// The target Wallet to migrate
+        target: Option<String>,
     },
 }
 
@@ -99,12 +201,37 @@ pub async fn wallet_commander(
             name,
             pay_with,
             secret_key,
+            mnemonic,
+            gap_limit,
         } => {
+            let mnemonic = if mnemonic {
+                Some(read_secret("mnemonic phrase")?)
+            } else {
+                None
+            };
+
             // create wallet
             let wallet_xorurl = safe.wallet_create().await?;
             let mut key_generated_output: (String, Option<Keypair>, String) = Default::default();
+            let mut restored = vec![];
+
+            if let Some(mnemonic) = mnemonic {
+                let gap_limit = gap_limit.unwrap_or(MNEMONIC_DEFAULT_GAP_LIMIT);
+                restored = safe
+                    .wallet_insert_from_mnemonic(&wallet_xorurl, &mnemonic, gap_limit)
+                    .await?;
 
-            if !no_balance {
+                if restored.is_empty() {
+                    // none of the SafeKeys has been used, let's start with the first one
+                    let keypair = safe.keys_derive_from_mnemonic(&mnemonic, 0)?;
+                    let (xorurl, key_pair, amount) =
+                        create_new_key(safe, test_coins, pay_with, preload, Some(keypair)).await?;
+                    let sk = sk_to_hex(key_pair.secret_key()?);
+                    safe.wallet_insert(&wallet_xorurl, Some("mnemonic-0"), true, &sk)
+                        .await?;
+                    key_generated_output = (xorurl, Some(key_pair), amount);
+                }
+            } else if !no_balance {
                 // get or create keypair
                 let sk = match keyurl {
                     Some(linked_key) => {
@@ -117,10 +244,10 @@ pub async fn wallet_commander(
                         sk_hex
                     }
                     None => match secret_key {
-                        Some(sk) => sk,
+                        Some(sk) => resolve_sk_alias(&sk)?,
                         None => {
                             let (xorurl, key_pair, amount) =
-                                create_new_key(safe, test_coins, pay_with, preload).await?;
+                                create_new_key(safe, test_coins, pay_with, preload, None).await?;
                             let sk = key_pair.secret_key()?;
 
                             key_generated_output = (xorurl, Some(key_pair), amount);
@@ -136,6 +263,18 @@ pub async fn wallet_commander(
 
             if OutputFmt::Pretty == output_fmt {
                 println!("Wallet created at: \"{}\"", wallet_xorurl);
+                if !restored.is_empty() {
+                    println!(
+                        "{} spendable balance/s derived from the mnemonic phrase restored:",
+                        restored.len()
+                    );
+                    let mut table = Table::new();
+                    table.add_row(row![bFg->"Index", bFg->"Spendable balance"]);
+                    for (index, name) in restored.iter() {
+                        table.add_row(row![index, name]);
+                    }
+                    table.printstd();
+                }
                 if !key_generated_output.0.is_empty() {
                     print_new_key_output(
                         output_fmt,
@@ -150,6 +289,11 @@ pub async fn wallet_commander(
                     "{}",
                     serialise_output(&(&wallet_xorurl, &key_generated_output.0, pair), output_fmt)
                 );
+            } else if !restored.is_empty() {
+                println!(
+                    "{}",
+                    serialise_output(&(&wallet_xorurl, &restored), output_fmt)
+                );
             } else {
                 println!(
                     "{}",
@@ -159,27 +303,49 @@ pub async fn wallet_commander(
 
             Ok(())
         }
-        WalletSubCommands::Balance { target } => {
+        WalletSubCommands::Balance { target, watch_only } => {
             let target = get_from_arg_or_stdin(
                 target,
                 Some("...awaiting Wallet address/location from STDIN stream..."),
             )?;
 
             debug!("Got target location {:?}", target);
-            let balance = safe.wallet_balance(&target).await?;
+            let total = safe.wallet_balance_with_watch_only(&target).await?;
+
+            // A single balance is either spendable or watch-only, thus only one of the totals
+            // can be other than zero, which is the balance reported for it
+            let safeurl = Safe::parse_url(&target)?;
+            let is_single_balance = !safeurl.path().is_empty();
+            let balance = if watch_only
+                || (is_single_balance && parse_coins_amount(&total.watch_only)?.as_nano() > 0)
+            {
+                &total.watch_only
+            } else {
+                &total.spendable
+            };
 
             if OutputFmt::Pretty == output_fmt {
-                let safeurl = Safe::parse_url(&target)?;
-                if safeurl.path().is_empty() {
+                if watch_only {
                     println!(
-                        "Wallet at \"{}\" has a total balance of {} safecoins",
+                        "Wallet at \"{}\" has a total watch-only balance of {} safecoins",
                         target, balance
                     );
-                } else {
+                } else if is_single_balance {
                     println!(
                         "Wallet's spendable balance at \"{}\" has a balance of {} safecoins",
                         target, balance
                     );
+                } else {
+                    println!(
+                        "Wallet at \"{}\" has a total balance of {} safecoins",
+                        target, balance
+                    );
+                    if parse_coins_amount(&total.watch_only)?.as_nano() > 0 {
+                        println!(
+                            "Its watch-only balances, which cannot be spent, have a total balance of {} safecoins",
+                            total.watch_only
+                        );
+                    }
                 }
             } else {
                 println!("{}", balance);
@@ -187,6 +353,52 @@ pub async fn wallet_commander(
 
             Ok(())
         }
+        WalletSubCommands::Remove { target, name } => {
+            safe.wallet_remove(&target, &name).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Spendable balance '{}' removed from Wallet located at \"{}\"",
+                    name, target
+                );
+            } else {
+                println!("{}", target);
+            }
+
+            Ok(())
+        }
+        WalletSubCommands::Rename {
+            target,
+            name,
+            new_name,
+        } => {
+            safe.wallet_rename(&target, &name, &new_name).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Spendable balance '{}' renamed to '{}' in Wallet located at \"{}\"",
+                    name, new_name, target
+                );
+            } else {
+                println!("{}", target);
+            }
+
+            Ok(())
+        }
+        WalletSubCommands::SetDefault { target, name } => {
+            safe.wallet_set_default(&target, &name).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Spendable balance '{}' set as default in Wallet located at \"{}\"",
+                    name, target
+                );
+            } else {
+                println!("{}", target);
+            }
+
+            Ok(())
+        }
         WalletSubCommands::Insert {
             target,
             keyurl,
@@ -194,11 +406,29 @@ pub async fn wallet_commander(
             default,
             secret_key,
             pay_with,
+            watch_only,
         } => {
             if pay_with.is_some() {
                 println!("The '--pay-with' argument is being ignored for now as it's not supported yet for this command.");
             }
 
+            if let Some(key) = watch_only {
+                let the_name = safe
+                    .wallet_insert_watch_only(&target, name.as_deref(), &key)
+                    .await?;
+
+                if OutputFmt::Pretty == output_fmt {
+                    println!(
+                        "Watch-only balance inserted with name '{}' in Wallet located at \"{}\"",
+                        the_name, target
+                    );
+                } else {
+                    println!("{}", target);
+                }
+
+                return Ok(());
+            }
+
             let sk = match keyurl {
                 Some(linked_key) => {
                     let sk_hex = get_secret_key(&linked_key, secret_key, "the SafeKey to insert")?;
@@ -226,12 +456,49 @@ pub async fn wallet_commander(
 
             Ok(())
         }
-        WalletSubCommands::Transfer { amount, from, to } => {
+        WalletSubCommands::Transfer {
+            amount,
+            from,
+            to,
+            coin_selection,
+            from_balances,
+            batch,
+            validate_concurrency,
+            results,
+        } => {
+            if let Some(batch_path) = batch {
+                let validate_concurrency =
+                    validate_concurrency.unwrap_or(BATCH_DEFAULT_VALIDATE_CONCURRENCY);
+                let results_path = results.unwrap_or_else(|| default_results_path(&batch_path));
+                return batch_transfer(
+                    &from,
+                    &batch_path,
+                    &results_path,
+                    validate_concurrency,
+                    output_fmt,
+                    safe,
+                )
+                .await;
+            }
+
+            // it's required by the args parser unless a batch file is provided
+            let amount = amount.unwrap_or_default();
             let destination = get_from_arg_or_stdin(
                 to,
                 Some("...awaiting destination Wallet/SafeKey URL, or public key, from STDIN stream..."),
             )?;
 
+            let selection = match (coin_selection, from_balances) {
+                (_, Some(names)) => Some(CoinSelection::Explicit(names)),
+                (selection, None) => selection,
+            };
+            if let Some(selection) = selection {
+                let report = safe
+                    .wallet_transfer_with_selection(&amount, &from, &destination, selection)
+                    .await?;
+                return print_transfer_report(&report, output_fmt);
+            }
+
             let tx_id = safe.wallet_transfer(&amount, &from, &destination).await?;
 
             if OutputFmt::Pretty == output_fmt {
@@ -242,5 +509,289 @@ pub async fn wallet_commander(
 
             Ok(())
         }
+        WalletSubCommands::History { target, pagination } => {
+            let target = get_from_arg_or_stdin(
+                target,
+                Some("...awaiting Wallet address/location from STDIN stream..."),
+            )?;
+
+            let history = safe.wallet_history(&target).await?;
+            print_transfer_history(&history, &pagination, output_fmt)
+        }
+        WalletSubCommands::Migrate { target } => {
+            let target = get_from_arg_or_stdin(
+                target,
+                Some("...awaiting Wallet address/location from STDIN stream..."),
+            )?;
+
+            let migrated = safe.wallet_migrate(&target).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                if migrated.is_empty() {
+                    println!(
+                        "No spendable balances with plaintext secret keys found in Wallet at \"{}\"",
+                        target
+                    );
+                } else {
+                    println!(
+                        "Secret keys of the following spendable balances in Wallet at \"{}\" are now encrypted:",
+                        target
+                    );
+                    for name in migrated.iter() {
+                        println!("{}", name);
+                    }
+                }
+            } else {
+                println!("{}", serialise_output(&(target, migrated), output_fmt));
+            }
+
+            Ok(())
+        }
+    }
+}
+
+// Print the consolidated report of a transfer funded from several spendable balances
+fn print_transfer_report(report: &WalletTransferReport, output_fmt: OutputFmt) -> Result<()> {
+    if OutputFmt::Pretty == output_fmt {
+        let mut table = Table::new();
+        table.add_row(row![bFg->"Spendable balance", bFg->"Amount", bFg->"TX_ID"]);
+        for leg in report.legs.iter() {
+            table.add_row(row![
+                leg.balance_name,
+                leg.amount,
+                leg.tx_id.map_or_else(String::default, |id| id.to_string())
+            ]);
+        }
+
+        if report.is_complete() {
+            println!(
+                "Success. Transfer made from {} spendable balance/s:",
+                report.legs.len()
+            );
+            table.printstd();
+        } else {
+            println!("Transfer only partially made from spendable balance/s:");
+            table.printstd();
+            if !report.rollback.is_empty() {
+                println!("Rollback of the transfers made (those without TX_ID need to be reverted by the owner of the destination):");
+                let mut table = Table::new();
+                table.add_row(row![bFg->"Back to spendable balance", bFg->"Amount", bFg->"TX_ID"]);
+                for leg in report.rollback.iter() {
+                    table.add_row(row![
+                        leg.balance_name,
+                        leg.amount,
+                        leg.tx_id.map_or_else(String::default, |id| id.to_string())
+                    ]);
+                }
+                table.printstd();
+            }
+        }
+    } else {
+        println!("{}", serialise_output(report, output_fmt));
+    }
+
+    match &report.error {
+        Some(err) => bail!("{}", err),
+        None => Ok(()),
+    }
+}
+
+// Make the transfers listed in a batch file. All of them are validated before making any
+// transfer, and the outcome of each of them is recorded in the batch's journal, so only
+// those which were not successfully made before are attempted when re-running the batch.
+// The transfers are made sequentially, as they are all debited from the same spendable
+// balance and concurrent debits would race on its transfer actor.
+async fn batch_transfer(
+    from: &str,
+    batch_path: &Path,
+    results_path: &Path,
+    validate_concurrency: usize,
+    output_fmt: OutputFmt,
+    safe: &mut Safe,
+) -> Result<()> {
+    if validate_concurrency == 0 {
+        bail!("The validation concurrency must be greater than zero");
+    }
+
+    let transfers = read_batch_file(batch_path)?
+        .into_iter()
+        .map(|transfer| BatchTransfer {
+            to: normalise_destination(&transfer.to),
+            ..transfer
+        })
+        .collect::<Vec<_>>();
+    let mut journal = BatchJournal::load(batch_path)?;
+
+    // validate all the amounts and destinations before transferring anything
+    let invalid = stream::iter(transfers.iter().enumerate())
+        .map(|(i, transfer)| {
+            let mut safe = safe.clone();
+            async move {
+                let validation = match parse_coins_amount(&transfer.amount) {
+                    Ok(_) => safe.validate_transfer_destination(&transfer.to).await,
+                    Err(err) => Err(err),
+                };
+                validation.map_err(|err| format!("row {}: {}", i + 1, err))
+            }
+        })
+        .buffered(validate_concurrency)
+        .filter_map(|validation| async move { validation.err() })
+        .collect::<Vec<_>>()
+        .await;
+    if !invalid.is_empty() {
+        bail!(
+            "No transfers were made as the batch contains invalid entries:\n{}",
+            invalid.join("\n")
+        );
+    }
+
+    // only those not made in a previous run are to be made now, they are looked up in the
+    // journal by the spendable balance they are debited from rather than by the URL provided
+    let source = safe.wallet_source_xorname(from).await?;
+    let mut results = vec![];
+    let mut to_transfer = vec![];
+    let mut total_nanos: u64 = 0;
+    let mut occurrences = BTreeMap::new();
+    for (i, transfer) in transfers.iter().enumerate() {
+        let row = i + 1;
+        // identical transfers are told apart by the number of times they were listed before
+        let occurrence = occurrences
+            .entry((&transfer.to, &transfer.amount, &transfer.memo))
+            .or_insert(0);
+        let id = journal_entry_id(&source, transfer, *occurrence);
+        *occurrence += 1;
+        let mut result = BatchResult {
+            row,
+            to: transfer.to.clone(),
+            amount: transfer.amount.clone(),
+            memo: transfer.memo.clone(),
+            status: BatchStatus::Skipped,
+            tx_id: None,
+            error: None,
+        };
+        match journal.get(&id) {
+            Some(JournalEntry::Done { tx_id }) => {
+                result.tx_id = Some(*tx_id);
+                results.push(result);
+            }
+            Some(JournalEntry::Pending) => {
+                result.status = BatchStatus::Unknown;
+                result.error = Some(format!("A previous run was interrupted while making this transfer, please check the Wallet's history and remove the entry '{}' from the journal to retry it", id));
+                results.push(result);
+            }
+            Some(JournalEntry::Failed { .. }) | None => {
+                let nanos = parse_coins_amount(&transfer.amount)?.as_nano();
+                total_nanos = total_nanos
+                    .checked_add(nanos)
+                    .ok_or_else(|| anyhow!("The total amount of the batch is too large"))?;
+                to_transfer.push((id, result));
+            }
+        }
     }
+
+    // all the transfers are debited from the same spendable balance
+    let balance = safe.wallet_source_balance(from).await?;
+    let balance_nanos = parse_coins_amount(&balance)?.as_nano();
+    if total_nanos > balance_nanos {
+        bail!(
+            "Not enough balance in Wallet \"{}\" for the batch of transfers, {} safecoins needed but the spendable balance they are made from has {}",
+            from,
+            format_nanos(total_nanos),
+            balance
+        );
+    }
+
+    // make the transfers, recording each state change in the journal
+    for (id, mut result) in to_transfer.into_iter() {
+        journal.record(&id, JournalEntry::Pending)?;
+        match safe.wallet_transfer(&result.amount, from, &result.to).await {
+            Ok(tx_id) => {
+                journal.record(&id, JournalEntry::Done { tx_id })?;
+                result.status = BatchStatus::Done;
+                result.tx_id = Some(tx_id);
+            }
+            Err(err) => {
+                let error = err.to_string();
+                journal.record(
+                    &id,
+                    JournalEntry::Failed {
+                        error: error.clone(),
+                    },
+                )?;
+                result.status = BatchStatus::Failed;
+                result.error = Some(error);
+            }
+        }
+        results.push(result);
+    }
+    results.sort_by_key(|result| result.row);
+
+    write_batch_results(results_path, &results)?;
+
+    let failed = results
+        .iter()
+        .filter(|result| {
+            result.status == BatchStatus::Failed || result.status == BatchStatus::Unknown
+        })
+        .count();
+    match output_fmt {
+        OutputFmt::Pretty => {
+            let mut table = Table::new();
+            table.add_row(
+                row![bFg->"Row", bFg->"To", bFg->"Amount", bFg->"Status", bFg->"TX_ID", bFg->"Error"],
+            );
+            for result in results.iter() {
+                table.add_row(row![
+                    result.row,
+                    result.to,
+                    result.amount,
+                    format!("{:?}", result.status).to_lowercase(),
+                    result
+                        .tx_id
+                        .map_or_else(String::default, |id| id.to_string()),
+                    result.error.as_deref().unwrap_or_default()
+                ]);
+            }
+            table.printstd();
+            println!("Results written to '{}'", results_path.display());
+            println!("Journal kept at '{}'", journal.file_path().display());
+        }
+        OutputFmt::Csv => print!("{}", serialise_csv_records(&results)?),
+        other => println!("{}", serialise_output(&results, other)),
+    }
+    if failed > 0 {
+        bail!(
+            "{} out of {} transfers of the batch were not made, they can be retried by re-running the batch",
+            failed,
+            results.len()
+        );
+    }
+
+    Ok(())
+}
+
+// Destinations can be Wallet/SafeKey URLs, NRS names without the 'safe://' scheme, or public keys
+fn normalise_destination(to: &str) -> String {
+    let is_pk = to.len() >= 64 && to.chars().all(|c| c.is_ascii_hexdigit());
+    if to.starts_with("safe://") || is_pk {
+        to.to_string()
+    } else {
+        format!("safe://{}", to)
+    }
+}
+
+// Format an amount of nano safecoins the same way balances are formatted
+fn format_nanos(nanos: u64) -> String {
+    format!("{}.{:09}", nanos / 1_000_000_000, nanos % 1_000_000_000)
+}
+
+fn write_batch_results(path: &Path, results: &[BatchResult]) -> Result<()> {
+    let content = match BatchFormat::from_path(path) {
+        BatchFormat::Json => serde_json::to_string_pretty(results)
+            .context("Failed to serialise the results of the batch")?,
+        BatchFormat::Csv => serialise_csv_records(results)?,
+    };
+
+    fs::write(path, content)
+        .with_context(|| format!("Failed to write results file '{}'", path.display()))
 }
@@ -0,0 +1,127 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+use super::{
+    helpers::{get_from_arg_or_stdin, serialise_output},
+    OutputFmt,
+};
+use anyhow::{bail, Context, Result};
+use futures::StreamExt;
+use log::debug;
+use sn_api::{fetch::SafeData, safeurl::SafeUrl, Safe, WatchOptions};
+use std::time::Duration;
+use structopt::StructOpt;
+use tokio::process::Command;
+
+// The shell used to run the command provided with '--exec'
+#[cfg(not(target_os = "windows"))]
+const SHELL: (&str, &str) = ("sh", "-c");
+
+#[cfg(target_os = "windows")]
+const SHELL: (&str, &str) = ("cmd", "/C");
+
+#[derive(StructOpt, Debug)]
+pub struct WatchCommands {
+    /// The safe:// location to watch, which must target a FilesContainer, an NRS Map Container, a Wallet, a Sequence or a Map. An NRS-URL is resolved when the watch starts, and the content it links to is watched
+    location: Option<String>,
+    /// Command to run with the system's shell every time a new version is found. The URL being watched and the XOR-URL of the new version are provided in the SAFE_WATCH_URL and SAFE_WATCH_XORURL environment variables
+    #[structopt(long = "exec")]
+    exec: Option<String>,
+    /// Number of seconds to wait between polls right after a new version is found
+    #[structopt(long = "interval", default_value = "1")]
+    interval: u64,
+    /// Maximum number of seconds to wait between polls, the interval is doubled every time no new version is found until it reaches this maximum
+    #[structopt(long = "max-interval", default_value = "30")]
+    max_interval: u64,
+}
+
+pub async fn watch_commander(
+    cmd: WatchCommands,
+    output_fmt: OutputFmt,
+    safe: &mut Safe,
+) -> Result<()> {
+    let url = get_from_arg_or_stdin(cmd.location, None)?;
+    if cmd.interval == 0 {
+        bail!("The '--interval' must be greater than zero");
+    }
+    if cmd.max_interval < cmd.interval {
+        bail!("The '--max-interval' cannot be lower than the '--interval'");
+    }
+    debug!("Running watch for: {:?}", &url);
+
+    let options = WatchOptions {
+        interval: Duration::from_secs(cmd.interval),
+        max_interval: Duration::from_secs(cmd.max_interval),
+        ..WatchOptions::default()
+    };
+    let mut events = Box::pin(safe.watch_with_options(&url, options));
+    let mut last_error = None;
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(content) => {
+                last_error = None;
+                let xorurl = versioned_xorurl(&content)?;
+                if OutputFmt::Pretty == output_fmt {
+                    println!("New version of \"{}\" found: {}", url, xorurl);
+                } else {
+                    println!("{}", serialise_output(&(&url, &xorurl), output_fmt));
+                }
+
+                if let Some(exec) = &cmd.exec {
+                    run_exec(exec, &url, &xorurl).await?;
+                }
+            }
+            Err(err) => {
+                // The stream only ends after an error if the URL cannot be watched at all,
+                // otherwise we just keep watching
+                eprintln!("Failed to watch \"{}\": {}", url, err);
+                last_error = Some(err);
+            }
+        }
+    }
+
+    match last_error {
+        Some(err) => Err(err.into()),
+        None => Ok(()),
+    }
+}
+
+// Run the command provided with '--exec', a failure of the command
+// itself is reported but it doesn't stop the watch
+async fn run_exec(exec: &str, url: &str, xorurl: &str) -> Result<()> {
+    let (shell, shell_arg) = SHELL;
+    let status = Command::new(shell)
+        .args(&[shell_arg, exec])
+        .env("SAFE_WATCH_URL", url)
+        .env("SAFE_WATCH_XORURL", xorurl)
+        .status()
+        .await
+        .with_context(|| format!("Failed to run '{}'", exec))?;
+
+    if !status.success() {
+        eprintln!("Command '{}' failed with {}", exec, status);
+    }
+
+    Ok(())
+}
+
+// XOR-URL of the content found, including its version when it's versioned content
+fn versioned_xorurl(content: &SafeData) -> Result<String> {
+    let version = match content {
+        SafeData::FilesContainer { version, .. }
+        | SafeData::NrsMapContainer { version, .. }
+        | SafeData::PublicSequence { version, .. }
+        | SafeData::PrivateSequence { version, .. } => *version,
+        _ => return Ok(content.xorurl()),
+    };
+
+    let mut safeurl = SafeUrl::from_url(&content.xorurl())?;
+    safeurl.set_content_version(Some(version));
+    Ok(safeurl.to_string())
+}
@@ -8,18 +8,24 @@
 // Software.
 
 use super::{
-    helpers::{gen_processed_files_table, get_from_arg_or_stdin, serialise_output, xorname_to_hex},
+    helpers::{
+        gen_processed_files_table, get_from_arg_or_stdin, hex_to_xorname, serialise_output,
+        xorname_to_hex,
+    },
     OutputFmt,
 };
 use anyhow::{anyhow, Result};
-use sn_api::{safeurl::SafeUrl, PublicKey, Safe, XorName};
+use sn_api::{
+    safeurl::{SafeContentType, SafeDataType, SafeUrl, XorUrlBase},
+    PublicKey, Safe, XorName,
+};
 use structopt::StructOpt;
 
 // Defines subcommands of 'xorurl'
 #[derive(StructOpt, Debug)]
 pub enum XorurlSubCommands {
     #[structopt(name = "decode")]
-    /// Decode a XOR-URL extracting all the information encoded it in
+    /// Decode a XOR-URL or NRS-URL, showing all the information encoded in it
     Decode {
         /// The XOR-URL to decode
         xorurl: Option<String>,
@@ -29,6 +35,37 @@ pub enum XorurlSubCommands {
         /// The Public Key to generate the SafeKey XOR-URL for
         pk: String,
     },
+    #[structopt(name = "convert")]
+    /// Convert a safe:// URL into a XOR-URL encoded with a different base
+    Convert {
+        /// The safe:// URL to convert
+        url: Option<String>,
+        /// The base encoding to convert it to: base32z, base32, base64, base58btc, base36, base16, or base64url
+        #[structopt(long = "to")]
+        to: XorUrlBase,
+    },
+    #[structopt(name = "encode")]
+    /// Build a XOR-URL from each of the pieces of information it encodes
+    Encode {
+        /// The XoR name (in Hex) of the content
+        #[structopt(long = "xorname")]
+        xorname: String,
+        /// The type tag of the content
+        #[structopt(long = "type-tag", default_value = "0")]
+        type_tag: u64,
+        /// The native data type holding the content: SafeKey, PublicBlob, PrivateBlob, PublicSequence, PrivateSequence, SeqMap, or UnseqMap
+        #[structopt(long = "data-type")]
+        data_type: SafeDataType,
+        /// The type of the content: Raw (default), Wallet, FilesContainer, NrsMapContainer, or a media-type (e.g. 'text/html')
+        #[structopt(long = "content-type", default_value = "Raw")]
+        content_type: SafeContentType,
+        /// The path (percent-encoded) to be appended to the XOR-URL
+        #[structopt(long = "path")]
+        path: Option<String>,
+        /// The version of the content to be targeted
+        #[structopt(long = "content-version")]
+        content_version: Option<u64>,
+    },
 }
 
 pub async fn xorurl_commander(
@@ -54,6 +91,7 @@ pub async fn xorurl_commander(
                 println!("Xorname: {}", xorname_to_hex(&safeurl.xorname()));
                 println!("Public Name: {}", public_name);
                 if safeurl.is_nrsurl() {
+                    println!("Public Name (Unicode): {}", safeurl.public_name_unicode());
                     println!("Top Name: {}", safeurl.top_name());
                 }
                 println!("Sub names: {}", safeurl.sub_names());
@@ -89,6 +127,44 @@ pub async fn xorurl_commander(
                 println!("{}", xorurl);
             }
         }
+        Some(XorurlSubCommands::Convert { url, to }) => {
+            let url = get_from_arg_or_stdin(url, Some("...awaiting safe:// URL from stdin"))?;
+            let xorurl = SafeUrl::from_url(&url)?.to_base(to);
+
+            if OutputFmt::Pretty == output_fmt {
+                println!("XOR-URL ({}): {}", to, xorurl);
+            } else {
+                println!("{}", serialise_output(&xorurl, output_fmt));
+            }
+        }
+        Some(XorurlSubCommands::Encode {
+            xorname,
+            type_tag,
+            data_type,
+            content_type,
+            path,
+            content_version,
+        }) => {
+            let xorurl = SafeUrl::encode(
+                hex_to_xorname(&xorname)?,
+                None,
+                type_tag,
+                data_type,
+                content_type,
+                path.as_deref(),
+                None,
+                None,
+                None,
+                content_version,
+                safe.xorurl_base,
+            )?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!("XOR-URL: {}", xorurl);
+            } else {
+                println!("{}", serialise_output(&xorurl, output_fmt));
+            }
+        }
         None => {
             let location =
                 get_from_arg_or_stdin(location, Some("...awaiting location path from stdin"))?;
@@ -0,0 +1,113 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+extern crate sn_cmd_test_utilities;
+
+#[macro_use]
+extern crate duct;
+
+use anyhow::{anyhow, Result};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use sn_api::CacheStats;
+use sn_cmd_test_utilities::{get_random_nrs_string, parse_files_put_or_sync_output, CLI};
+use std::{env, fs, process::Command};
+use walkdir::WalkDir;
+
+const TEST_FILE: &str = "../testdata/test.md";
+const TEST_FILE_CONTENT: &str = "hello tests!";
+
+fn cache_stats() -> Result<CacheStats> {
+    let output = cmd!(env!("CARGO_BIN_EXE_safe"), "cache", "stats", "--json")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    serde_json::from_str(&output).map_err(|e| anyhow!(e.to_string()))
+}
+
+// All the 'cache' subcommands are tested in sequence since they
+// all update the same setting in the CLI config file
+#[test]
+fn calling_safe_cache() -> Result<()> {
+    let cache_dir = env::temp_dir().join(format!("safe-cli-cache-{}", get_random_nrs_string()));
+    let cache_dir_str = cache_dir.display().to_string();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["cache", "enable", &cache_dir_str])
+        .assert()
+        .stdout(predicate::str::contains("On-disk cache enabled"))
+        .success();
+
+    let stats = cache_stats()?;
+    assert_eq!(stats.disk_dir, Some(cache_dir.clone()));
+    assert_eq!(stats.disk_entries, 0);
+
+    // fetching content from the network populates the on-disk cache
+    let content = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "files",
+        "put",
+        TEST_FILE,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (_container_xorurl, map) = parse_files_put_or_sync_output(&content);
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["cat", &map[TEST_FILE].1])
+        .assert()
+        .stdout(predicate::str::contains(TEST_FILE_CONTENT))
+        .success();
+
+    let stats = cache_stats()?;
+    assert!(stats.disk_entries > 0);
+    assert!(stats.disk_size > 0);
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["cache", "clear"])
+        .assert()
+        .stdout(predicate::str::contains("cleared"))
+        .success();
+
+    let stats = cache_stats()?;
+    assert_eq!(stats.disk_dir, Some(cache_dir.clone()));
+    assert_eq!(stats.disk_entries, 0);
+    assert_eq!(stats.disk_size, 0);
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["cache", "disable"])
+        .assert()
+        .stdout(predicate::str::contains("On-disk cache disabled"))
+        .success();
+
+    let stats = cache_stats()?;
+    assert_eq!(stats.disk_dir, None);
+
+    // nothing is cached on disk anymore once disabled
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["cat", &map[TEST_FILE].1])
+        .assert()
+        .stdout(predicate::str::contains(TEST_FILE_CONTENT))
+        .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["cache", "clear"])
+        .assert()
+        .stdout(predicate::str::contains("nothing to clear"))
+        .success();
+
+    let files_cached = WalkDir::new(&cache_dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .count();
+    let _ = fs::remove_dir_all(&cache_dir);
+    assert_eq!(files_cached, 0);
+    Ok(())
+}
@@ -95,6 +95,30 @@ fn calling_safe_cat_subfolders() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn calling_safe_cat_with_base_url() -> Result<()> {
+    let content = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "files",
+        "put",
+        TEST_DATA,
+        "--json",
+        "--recursive",
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let (container_xorurl, _) = parse_files_put_or_sync_output(&content);
+    let base = format!("{}/subfolder/subexists.md", container_xorurl);
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["cat", "../another.md", "--base", &base])
+        .assert()
+        .stdout(predicate::str::contains(ANOTHER_FILE_CONTENT))
+        .success();
+    Ok(())
+}
+
 #[test]
 fn calling_safe_cat_on_relative_file_from_id_fails() -> Result<()> {
     let content = cmd!(
@@ -295,9 +319,15 @@ fn calling_safe_cat_wallet_xorurl() -> Result<()> {
     let key_pair = key_pair.ok_or_else(|| anyhow!("Could not fetch Keypair".to_string()))?;
     let secret_key = key_pair.secret_key().map_err(|e| anyhow!(e.to_string()))?;
 
-    let wallet_cat = cmd!(env!("CARGO_BIN_EXE_safe"), "cat", &wallet_xorurl, "--json")
-        .read()
-        .map_err(|e| anyhow!(e.to_string()))?;
+    let wallet_cat = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "cat",
+        &wallet_xorurl,
+        "--show-sk",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
     let (xorurl, balances) = parse_cat_wallet_output(&wallet_cat);
 
     assert_eq!(wallet_xorurl, xorurl);
@@ -310,6 +340,14 @@ fn calling_safe_cat_wallet_xorurl() -> Result<()> {
     assert_eq!(balances[&key_pk_xor].0, false);
     assert_eq!(balances[&key_pk_xor].1.xorurl, key_pk_xor);
     assert_eq!(balances[&key_pk_xor].1.sk, sk);
+
+    // secret keys are redacted unless '--show-sk' is passed
+    let wallet_cat = cmd!(env!("CARGO_BIN_EXE_safe"), "cat", &wallet_xorurl, "--json")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    let (_, balances) = parse_cat_wallet_output(&wallet_cat);
+    assert_eq!(balances[&key_xorurl].1.sk, "<redacted>");
+    assert_eq!(balances[&key_pk_xor].1.sk, "<redacted>");
     Ok(())
 }
 
@@ -0,0 +1,139 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+extern crate sn_cmd_test_utilities;
+
+#[macro_use]
+extern crate duct;
+
+use anyhow::{anyhow, Result};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use sn_cmd_test_utilities::{get_random_nrs_string, CLI, SAFE_PROTOCOL};
+use std::process::Command;
+
+const PRETTY_KEYS_CREATION_RESPONSE: &str = "New SafeKey created:";
+
+#[test]
+fn calling_safe_config_profile() -> Result<()> {
+    let profile = format!("test-{}", get_random_nrs_string());
+
+    cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "config",
+        "profile",
+        "add",
+        &profile,
+        "--output-format",
+        "json",
+        "--xorurl-base",
+        "base32",
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let list = cmd!(env!("CARGO_BIN_EXE_safe"), "config", "profile", "ls")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    assert!(list.contains(&profile));
+
+    // the output format set in the profile is used when none is provided
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "keys",
+        "create",
+        "--test-coins",
+        "--profile",
+        &profile,
+    ])
+    .assert()
+    .stdout(predicate::str::contains(PRETTY_KEYS_CREATION_RESPONSE).count(0))
+    .stdout(predicate::str::contains(SAFE_PROTOCOL).from_utf8())
+    .success();
+
+    // ...and it can also be selected with the env var
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["keys", "create", "--test-coins"])
+        .env("SAFE_PROFILE", &profile)
+        .assert()
+        .stdout(predicate::str::contains(PRETTY_KEYS_CREATION_RESPONSE).count(0))
+        .success();
+
+    // arguments take precedence over the profile's settings
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "keys",
+        "create",
+        "--test-coins",
+        "--profile",
+        &profile,
+        "--output",
+        "yaml",
+    ])
+    .assert()
+    .stdout(predicate::str::contains(PRETTY_KEYS_CREATION_RESPONSE).count(0))
+    .stdout(predicate::str::starts_with("---").from_utf8())
+    .success();
+
+    cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "config",
+        "profile",
+        "rm",
+        &profile
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let list = cmd!(env!("CARGO_BIN_EXE_safe"), "config", "profile", "ls")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    assert!(!list.contains(&profile));
+    Ok(())
+}
+
+#[test]
+fn calling_safe_config_profile_unknown() -> Result<()> {
+    let profile = format!("test-{}", get_random_nrs_string());
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "keys",
+        "create",
+        "--test-coins",
+        "--profile",
+        &profile,
+    ])
+    .assert()
+    .stderr(predicate::str::contains("No profile with name"))
+    .failure();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["config", "profile", "use", &profile])
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["config", "profile", "add", "invalid/name"])
+        .assert()
+        .failure();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "config",
+        "profile",
+        "add",
+        &profile,
+        "--xorurl-base",
+        "base99",
+    ])
+    .assert()
+    .failure();
+    Ok(())
+}
@@ -13,7 +13,7 @@ extern crate sn_cmd_test_utilities;
 extern crate duct;
 
 use anyhow::{anyhow, Result};
-use sn_api::fetch::SafeData;
+use sn_api::fetch::{ResolutionRule, ResolutionStep, SafeData};
 use sn_cmd_test_utilities::{
     create_preload_and_get_keys, get_random_nrs_string, parse_dog_output,
     parse_files_put_or_sync_output, safeurl_from,
@@ -246,3 +246,101 @@ fn calling_safe_dog_nrs_url_with_subnames() -> Result<()> {
         panic!("Content retrieved was unexpected: {:?}", safe_data_vec);
     }
 }
+
+#[test]
+fn calling_safe_dog_with_trace() -> Result<()> {
+    let content = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "files",
+        "put",
+        TEST_FILE,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (container_xorurl, _files_map) = parse_files_put_or_sync_output(&content);
+
+    let nrsurl = format!("safe://{}", get_random_nrs_string());
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "nrs",
+        "create",
+        &nrsurl,
+        "-l",
+        &container_xorurl,
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let file_url = format!("{}/test.md", nrsurl);
+    let dog_output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "dog",
+        &file_url,
+        "--trace",
+        "--json",
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let (url, safe_data_vec, trace): (String, Vec<SafeData>, Vec<ResolutionStep>) =
+        serde_json::from_str(&dog_output)
+            .map_err(|_| anyhow!("Failed to parse output of `safe dog --trace`"))?;
+    assert_eq!(url, file_url);
+    assert_eq!(safe_data_vec.len(), 3);
+    assert_eq!(trace.len(), 3);
+    assert_eq!(trace[0].url, file_url);
+    assert!(matches!(
+        trace[0].rule,
+        Some(ResolutionRule::NrsDefault { .. })
+    ));
+    match &trace[1].rule {
+        Some(ResolutionRule::FilesContainerPath { path, target, .. }) => {
+            assert_eq!(path, "/test.md");
+            assert_eq!(*target, Some(safe_data_vec[2].xorurl()));
+        }
+        other => panic!("Unexpected resolution rule: {:?}", other),
+    }
+    assert_eq!(trace[2].rule, None);
+    Ok(())
+}
+
+#[test]
+fn calling_safe_dog_with_trace_failing_step() -> Result<()> {
+    let content = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "files",
+        "put",
+        TEST_FILE,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (container_xorurl, _files_map) = parse_files_put_or_sync_output(&content);
+
+    let mut safeurl = safeurl_from(&container_xorurl)?;
+    safeurl.set_path("/missing.md");
+    let missing_url = safeurl.to_string();
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "dog",
+        &missing_url,
+        "--trace",
+        "--json",
+    )
+    .stdout_capture()
+    .unchecked()
+    .run()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert!(!output.status.success());
+
+    let (url, safe_data_vec, trace): (String, Vec<SafeData>, Vec<ResolutionStep>) =
+        serde_json::from_slice(&output.stdout)
+            .map_err(|_| anyhow!("Failed to parse output of `safe dog --trace`"))?;
+    assert_eq!(url, missing_url);
+    assert!(safe_data_vec.is_empty());
+    assert_eq!(trace.len(), 1);
+    assert_eq!(trace[0].rule, None);
+    assert!(trace[0].error.is_some());
+    Ok(())
+}
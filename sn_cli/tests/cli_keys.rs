@@ -15,13 +15,17 @@ extern crate duct;
 use anyhow::{anyhow, Result};
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
+use sn_api::{TransferDirection, TransferHistoryEntry};
 use sn_cmd_test_utilities::{
-    create_preload_and_get_keys, create_wallet_with_balance, get_random_nrs_string, CLI,
-    SAFE_PROTOCOL,
+    create_preload_and_get_keys, create_wallet_with_balance, get_random_nrs_string,
+    parse_keys_create_output, CLI, SAFE_PROTOCOL,
 };
-use std::process::Command;
+use std::{fs, process::Command};
 
 const PRETTY_KEYS_CREATION_RESPONSE: &str = "New SafeKey created:";
+const KEYSTORE_PASSWORD_ENV_VAR: &str = "SAFE_KEYSTORE_PASSWORD";
+const KEYSTORE_PATH_ENV_VAR: &str = "SAFE_KEYSTORE_PATH";
+const TEST_KEYSTORE_PASSWORD: &str = "test keystore password";
 
 #[test]
 fn calling_safe_keys_create_pretty() -> Result<()> {
@@ -160,6 +164,537 @@ fn calling_safe_keys_transfer() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn calling_safe_keys_history() -> Result<()> {
+    let (_, sk1) = create_preload_and_get_keys("10")?;
+    let (safekey2_xorurl, _) = create_preload_and_get_keys("0")?;
+
+    let tx_ids = ["1.1", "2.2", "3.3"]
+        .iter()
+        .map(|amount| {
+            cmd!(
+                env!("CARGO_BIN_EXE_safe"),
+                "keys",
+                "transfer",
+                amount,
+                "--from",
+                &sk1,
+                "--to",
+                &safekey2_xorurl,
+                "--json"
+            )
+            .read()
+            .map_err(|e| anyhow!(e.to_string()))
+        })
+        .collect::<Result<Vec<String>>>()?;
+
+    let history = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "history",
+        "--sk",
+        &sk1,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let history: Vec<TransferHistoryEntry> = serde_json::from_str(&history)
+        .map_err(|_| anyhow!("Failed to parse output of `safe keys history`"))?;
+    let outgoing: Vec<(&str, &str)> = history
+        .iter()
+        .filter(|entry| entry.direction == TransferDirection::Outgoing)
+        .map(|entry| (entry.amount.as_str(), entry.tx_id.as_str()))
+        .collect();
+    assert_eq!(
+        outgoing,
+        vec![
+            ("1.100000000", tx_ids[0].as_str()),
+            ("2.200000000", tx_ids[1].as_str()),
+            ("3.300000000", tx_ids[2].as_str())
+        ]
+    );
+
+    // second page with pages of 2 transfers, in CSV format
+    let page = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "history",
+        "--sk",
+        &sk1,
+        "--page",
+        "2",
+        "--page-size",
+        "2",
+        "--output",
+        "csv"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let mut lines = page.lines();
+    assert_eq!(
+        lines.next(),
+        Some("balance_name,direction,amount,counterparty,tx_id")
+    );
+    assert_eq!(lines.count(), std::cmp::min(2, history.len() - 2));
+    Ok(())
+}
+
+#[test]
+fn calling_safe_keys_create_csv_output() -> Result<()> {
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["keys", "create", "--test-coins", "--output", "csv"])
+        .assert()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains(
+            "The 'csv' output format is only supported by",
+        ))
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn calling_safe_keys_derive_and_create_from_mnemonic() -> Result<()> {
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "derive",
+        "--count",
+        "2",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (mnemonic, derived_keys): (Option<String>, Vec<serde_json::Value>) =
+        serde_json::from_str(&output)
+            .map_err(|_| anyhow!("Failed to parse output of `safe keys derive`"))?;
+    let mnemonic = mnemonic.ok_or_else(|| anyhow!("No mnemonic phrase was generated"))?;
+    assert_eq!(mnemonic.split(' ').count(), 24);
+    assert_eq!(derived_keys.len(), 2);
+    assert_eq!(derived_keys[1]["index"], 1);
+
+    // deriving from the same mnemonic phrase gives the same key pairs, and no new phrase
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "derive",
+        "--mnemonic",
+        "--index",
+        "1",
+        "--json"
+    )
+    .stdin_bytes(mnemonic.as_str())
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (no_mnemonic, derived_again): (Option<String>, Vec<serde_json::Value>) =
+        serde_json::from_str(&output)
+            .map_err(|_| anyhow!("Failed to parse output of `safe keys derive`"))?;
+    assert_eq!(no_mnemonic, None);
+    assert_eq!(derived_again, vec![derived_keys[1].clone()]);
+
+    // create the SafeKey with index 1 on the network
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "create",
+        "--test-coins",
+        "--preload",
+        "5",
+        "--from-mnemonic",
+        "--index",
+        "1",
+        "--json"
+    )
+    .stdin_bytes(mnemonic.as_str())
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (xorurl, (pk, sk)) = parse_keys_create_output(&output);
+    assert_eq!(derived_keys[1]["xorurl"], xorurl);
+    assert_eq!(derived_keys[1]["public_key"], pk);
+    assert_eq!(derived_keys[1]["secret_key"], sk);
+
+    let balance = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "balance",
+        "--keyurl",
+        &xorurl,
+        "--sk",
+        &sk,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(balance, "5.000000000");
+    Ok(())
+}
+
+#[test]
+fn calling_safe_keys_sign_and_verify() -> Result<()> {
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "derive",
+        "--count",
+        "2",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (_, derived_keys): (Option<String>, Vec<serde_json::Value>) = serde_json::from_str(&output)
+        .map_err(|_| anyhow!("Failed to parse output of `safe keys derive`"))?;
+    let sk = derived_keys[0]["secret_key"]
+        .as_str()
+        .ok_or_else(|| anyhow!("No secret key derived"))?;
+    let xorurl = derived_keys[0]["xorurl"]
+        .as_str()
+        .ok_or_else(|| anyhow!("No SafeKey URL derived"))?;
+    let other_xorurl = derived_keys[1]["xorurl"]
+        .as_str()
+        .ok_or_else(|| anyhow!("No SafeKey URL derived"))?;
+
+    let test_dir = std::env::temp_dir().join(format!("sign-{}", get_random_nrs_string()));
+    fs::create_dir_all(&test_dir)?;
+    let file = test_dir.join("artifact.tar.gz");
+    let sig_file = test_dir.join("artifact.tar.gz.sig");
+    fs::write(&file, "release artifact")?;
+
+    let signature = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "sign",
+        &file,
+        "--sk",
+        sk,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let parsed: serde_json::Value = serde_json::from_str(&signature)
+        .map_err(|_| anyhow!("Failed to parse output of `safe keys sign`"))?;
+    assert_eq!(parsed["xorurl"], xorurl);
+    assert_eq!(parsed["public_key"], derived_keys[0]["public_key"]);
+    fs::write(&sig_file, signature)?;
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["keys", "verify"])
+        .arg(&file)
+        .arg(&sig_file)
+        .args(&vec!["--signer", xorurl])
+        .assert()
+        .stdout(predicate::str::contains("Signature is valid"))
+        .success();
+
+    // signed by someone other than the expected signer
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["keys", "verify"])
+        .arg(&file)
+        .arg(&sig_file)
+        .args(&vec!["--signer", other_xorurl])
+        .assert()
+        .stderr(predicate::str::contains("rather than the expected signer"))
+        .failure();
+
+    // the expected signer must be provided
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["keys", "verify"])
+        .arg(&file)
+        .arg(&sig_file)
+        .assert()
+        .failure();
+
+    // a tampered file
+    fs::write(&file, "tampered artifact")?;
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["keys", "verify"])
+        .arg(&file)
+        .arg(&sig_file)
+        .args(&vec!["--signer", xorurl])
+        .assert()
+        .stderr(predicate::str::contains("InvalidSignature"))
+        .failure();
+
+    fs::remove_dir_all(&test_dir)?;
+    Ok(())
+}
+
+#[test]
+fn calling_safe_keys_export_and_import() -> Result<()> {
+    let output = cmd!(env!("CARGO_BIN_EXE_safe"), "keys", "derive", "--json")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    let (_, derived_keys): (Option<String>, Vec<serde_json::Value>) = serde_json::from_str(&output)
+        .map_err(|_| anyhow!("Failed to parse output of `safe keys derive`"))?;
+    let sk = derived_keys[0]["secret_key"]
+        .as_str()
+        .ok_or_else(|| anyhow!("No secret key derived"))?;
+
+    let test_dir = std::env::temp_dir().join(format!("export-{}", get_random_nrs_string()));
+    fs::create_dir_all(&test_dir)?;
+
+    for format in &["pem", "pkcs8", "openssh", "jwk"] {
+        let key_file = test_dir.join(format!("key.{}", format));
+        let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+        cmd.args(&vec![
+            "keys", "export", "--format", format, "--sk", sk, "--out",
+        ])
+        .arg(&key_file)
+        .assert()
+        .success();
+
+        let output = cmd!(
+            env!("CARGO_BIN_EXE_safe"),
+            "keys",
+            "import",
+            &key_file,
+            "--show-sk",
+            "--json"
+        )
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+        let (xorurl, pk, imported_sk): (String, String, Option<String>) =
+            serde_json::from_str(&output)
+                .map_err(|_| anyhow!("Failed to parse output of `safe keys import`"))?;
+        assert_eq!(xorurl, derived_keys[0]["xorurl"]);
+        assert_eq!(pk, derived_keys[0]["public_key"]);
+        assert_eq!(imported_sk.as_deref(), Some(sk));
+    }
+
+    // password protection is only supported by the PKCS#8 formats
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "keys",
+        "export",
+        "--format",
+        "jwk",
+        "--sk",
+        sk,
+        "--password",
+    ])
+    .assert()
+    .stderr(predicate::str::contains(
+        "only supported by the 'pem' and 'pkcs8'",
+    ))
+    .failure();
+
+    fs::remove_dir_all(&test_dir)?;
+    Ok(())
+}
+
+#[test]
+fn calling_safe_keys_store() -> Result<()> {
+    let (safekey_xorurl, sk) = create_preload_and_get_keys("10")?;
+    let alias = format!("test-{}", get_random_nrs_string());
+    let test_dir = std::env::temp_dir().join(format!("keystore-{}", get_random_nrs_string()));
+    let keystore_path = test_dir.join("keystore.json");
+
+    cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "store",
+        "add",
+        &alias,
+        "--json"
+    )
+    .stdin_bytes(sk.as_str())
+    .env(KEYSTORE_PATH_ENV_VAR, &keystore_path)
+    .env(KEYSTORE_PASSWORD_ENV_VAR, TEST_KEYSTORE_PASSWORD)
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    // the list doesn't require the password
+    let list = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "store",
+        "list",
+        "--json"
+    )
+    .env(KEYSTORE_PATH_ENV_VAR, &keystore_path)
+    .env_remove(KEYSTORE_PASSWORD_ENV_VAR)
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let entries: Vec<(String, String, String)> = serde_json::from_str(&list)
+        .map_err(|_| anyhow!("Failed to parse output of `safe keys store list`"))?;
+    let entry = entries
+        .iter()
+        .find(|(entry_alias, _, _)| *entry_alias == alias)
+        .ok_or_else(|| anyhow!("The key stored was not listed"))?;
+    assert_eq!(entry.1, safekey_xorurl);
+
+    let exported = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "store",
+        "export",
+        &alias,
+        "--json"
+    )
+    .env(KEYSTORE_PATH_ENV_VAR, &keystore_path)
+    .env(KEYSTORE_PASSWORD_ENV_VAR, TEST_KEYSTORE_PASSWORD)
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(exported, format!("\"{}\"", sk));
+
+    // the secret key can be referenced by its alias
+    let balance = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "balance",
+        "--keyurl",
+        &safekey_xorurl,
+        "--sk",
+        format!("@{}", alias),
+        "--json"
+    )
+    .env(KEYSTORE_PATH_ENV_VAR, &keystore_path)
+    .env(KEYSTORE_PASSWORD_ENV_VAR, TEST_KEYSTORE_PASSWORD)
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(balance, "10.000000000");
+
+    // a wrong password cannot decrypt it
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["keys", "store", "export", &alias])
+        .env(KEYSTORE_PATH_ENV_VAR, &keystore_path)
+        .env(KEYSTORE_PASSWORD_ENV_VAR, "wrong password")
+        .assert()
+        .stderr(predicate::str::contains("Wrong password"))
+        .failure();
+
+    cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "store",
+        "remove",
+        &alias
+    )
+    .env(KEYSTORE_PATH_ENV_VAR, &keystore_path)
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["keys", "store", "export", &alias])
+        .env(KEYSTORE_PATH_ENV_VAR, &keystore_path)
+        .env(KEYSTORE_PASSWORD_ENV_VAR, TEST_KEYSTORE_PASSWORD)
+        .assert()
+        .stderr(predicate::str::contains(format!(
+            "No key with alias '{}' was found in the keystore",
+            alias
+        )))
+        .failure();
+
+    // the keystore was written in the test folder rather than the default location
+    assert!(keystore_path.exists());
+    fs::remove_dir_all(&test_dir)?;
+    Ok(())
+}
+
+#[test]
+fn calling_safe_keys_request_and_pay() -> Result<()> {
+    let (payer_xorurl, payer_sk) = create_preload_and_get_keys("10")?;
+    let (payee_xorurl, payee_sk) = create_preload_and_get_keys("0")?;
+
+    let request = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "request",
+        "--amount",
+        "1.5",
+        "--to",
+        &payee_xorurl,
+        "--memo",
+        "invoice-42",
+        "--expires-in",
+        "1h",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let request_url: String = serde_json::from_str(&request)
+        .map_err(|_| anyhow!("Failed to parse output of `safe keys request`"))?;
+    assert!(request_url.starts_with(&payee_xorurl));
+
+    // a validity time with an unknown unit is rejected
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "keys",
+        "request",
+        "--amount",
+        "1.5",
+        "--to",
+        &payee_xorurl,
+        "--expires-in",
+        "1é",
+    ])
+    .assert()
+    .stderr(predicate::str::contains("Invalid validity time"))
+    .failure();
+
+    // an amount which doesn't match the one requested is rejected
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "pay",
+        &request_url,
+        "--from",
+        &payer_sk,
+        "--amount",
+        "2",
+        "--yes",
+    ])
+    .assert()
+    .stderr(predicate::str::contains("doesn't match"))
+    .failure();
+
+    // ...and so is an expired request
+    let expired_url = format!("{}?amount=1&expires=2020-01-01T00:00:00Z", payee_xorurl);
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["pay", &expired_url, "--from", &payer_sk, "--yes"])
+        .assert()
+        .stderr(predicate::str::contains("expired"))
+        .failure();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "pay",
+        &request_url,
+        "--from",
+        &payer_sk,
+        "--amount",
+        "1.5",
+        "--yes",
+    ])
+    .assert()
+    .stdout(predicate::str::contains("Success. TX_ID:"))
+    .success();
+
+    let payee_has = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "balance",
+        "--sk",
+        &payee_sk,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(payee_has, "1.500000000");
+
+    let payer_has = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "balance",
+        "--keyurl",
+        &payer_xorurl,
+        "--sk",
+        &payer_sk,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(payer_has, "8.500000000" /* 10 - 1.5 */);
+    Ok(())
+}
+
 #[test]
 fn calling_safe_keys_transfer_to_wallet_xorurl() -> Result<()> {
     let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
@@ -0,0 +1,184 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+extern crate sn_cmd_test_utilities;
+
+#[macro_use]
+extern crate duct;
+
+use anyhow::{anyhow, Result};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use sn_cmd_test_utilities::CLI;
+use std::process::Command;
+
+fn create_map() -> Result<String> {
+    let map_create = cmd!(env!("CARGO_BIN_EXE_safe"), "map", "create", "--json")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    serde_json::from_str(&map_create)
+        .map_err(|_| anyhow!("Failed to parse output of `safe map create`"))
+}
+
+#[test]
+fn calling_safe_map_insert_get_and_ls() -> Result<()> {
+    let map_url = create_map()?;
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["map", "insert", &map_url, "name", "Alice"])
+        .assert()
+        .stdout(predicate::str::contains(
+            "Entry 'name' inserted into the Map",
+        ))
+        .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "map",
+        "insert",
+        &map_url,
+        "{\"id\":1}",
+        "{\"age\": 30}",
+        "--key-encoding",
+        "json",
+        "--value-encoding",
+        "json",
+    ])
+    .assert()
+    .success();
+
+    // inserting an existing key fails
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["map", "insert", &map_url, "name", "Bob"])
+        .assert()
+        .failure();
+
+    let map_get = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "map",
+        "get",
+        &map_url,
+        "name",
+        "--value-encoding",
+        "hex",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let entry: serde_json::Value = serde_json::from_str(&map_get)
+        .map_err(|_| anyhow!("Failed to parse output of `safe map get`"))?;
+    assert_eq!(entry["key"], "name");
+    assert_eq!(entry["value"], hex::encode("Alice"));
+    assert_eq!(entry["version"], 0);
+
+    let map_ls = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "map",
+        "ls",
+        &map_url,
+        "--value-encoding",
+        "json",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&map_ls)
+        .map_err(|_| anyhow!("Failed to parse output of `safe map ls`"))?;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["key"], "name");
+    // values which are not valid JSON are rendered as text
+    assert_eq!(entries[0]["value"], "Alice");
+    assert_eq!(entries[1]["key"], "{\"id\":1}");
+    assert_eq!(entries[1]["value"], "{\"age\":30}");
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["cat", &map_url])
+        .assert()
+        .stdout(predicate::str::contains("Alice"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["dog", &map_url])
+        .assert()
+        .stdout(predicate::str::contains("= Map ="))
+        .stdout(predicate::str::contains("Native data type: SeqMap"))
+        .success();
+    Ok(())
+}
+
+#[test]
+fn calling_safe_map_update_and_delete() -> Result<()> {
+    let map_url = create_map()?;
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["map", "insert", &map_url, "counter", "0"])
+        .assert()
+        .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["map", "update", &map_url, "counter", "1"])
+        .assert()
+        .stdout(predicate::str::contains("updated to version 1"))
+        .success();
+
+    // an update with a stale version is rejected
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "map",
+        "update",
+        &map_url,
+        "counter",
+        "2",
+        "--version",
+        "0",
+    ])
+    .assert()
+    .failure();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "map",
+        "update",
+        &map_url,
+        "counter",
+        "2",
+        "--version",
+        "1",
+    ])
+    .assert()
+    .stdout(predicate::str::contains("updated to version 2"))
+    .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["map", "get", &map_url, "counter"])
+        .assert()
+        .stdout(predicate::str::contains("[2] 2"))
+        .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "map",
+        "delete",
+        &map_url,
+        "counter",
+        "--version",
+        "2",
+    ])
+    .assert()
+    .stdout(predicate::str::contains(
+        "Entry 'counter' deleted from the Map",
+    ))
+    .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["map", "get", &map_url, "counter"])
+        .assert()
+        .failure();
+    Ok(())
+}
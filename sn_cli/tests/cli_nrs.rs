@@ -531,3 +531,117 @@ fn calling_safe_nrs_put_and_add_new_subnames_remove_one_and_so_fail_to_retrieve(
         .failure();
     Ok(())
 }
+
+#[test]
+fn calling_safe_nrs_add_with_stale_expected_version_fails() -> Result<()> {
+    let test_name = format!("safe://{}", get_random_nrs_string());
+    let fake_target = gen_fake_target()?;
+
+    let _nrs_creation = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "nrs",
+        "create",
+        &test_name,
+        "-l",
+        &fake_target,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "nrs",
+        "add",
+        &format!("a.{}", test_name.replace("safe://", "")),
+        "-l",
+        &fake_target,
+        "--expect-version",
+        "3",
+    ])
+    .assert()
+    .stderr(predicate::str::contains(
+        "NRS Map Container is at version 0 but version 3 was expected",
+    ))
+    .failure();
+    Ok(())
+}
+
+#[test]
+fn calling_safe_nrs_create_with_mixed_script_name_fails() -> Result<()> {
+    // the first 'a' is a Cyrillic character
+    let test_name = format!("p\u{0430}ypal{}", get_random_nrs_string());
+    let fake_target = gen_fake_target()?;
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["nrs", "create", &test_name, "-l", &fake_target])
+        .assert()
+        .stderr(predicate::str::contains(
+            "mixes characters from different scripts (Cyrillic, Latin)",
+        ))
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn calling_safe_nrs_export_and_apply() -> Result<()> {
+    let test_name = get_random_nrs_string();
+    let fake_target = gen_fake_target()?;
+
+    let _nrs_creation = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "nrs",
+        "create",
+        format!("a.{}", test_name),
+        "-l",
+        &fake_target,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let zone = cmd!(env!("CARGO_BIN_EXE_safe"), "nrs", "export", &test_name)
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    assert!(zone.contains(&format!("name = \"{}\"", test_name)));
+    assert!(zone.contains("sub_name = \"a\""));
+    assert!(zone.contains(&format!("a = \"{}\"", fake_target)));
+
+    // let's add a new sub name to the zone and apply it
+    let zone_file = format!("./{}.toml", test_name);
+    std::fs::write(&zone_file, format!("{}b = \"{}\"\n", zone, fake_target))?;
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "nrs",
+        "apply",
+        &test_name,
+        "-f",
+        &zone_file,
+        "--dry-run",
+    ])
+    .assert()
+    .stdout(predicate::str::contains(
+        "Plan to update NRS Map (version 1)",
+    ))
+    .stdout(predicate::str::contains(format!("b.{}", test_name)))
+    .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["nrs", "apply", &test_name, "-f", &zone_file])
+        .assert()
+        .stdout(predicate::str::contains("NRS Map updated (version 1)"))
+        .stdout(predicate::str::contains("+").count(1))
+        .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["nrs", "apply", &test_name, "-f", &zone_file])
+        .assert()
+        .stdout(predicate::str::contains(
+            "NRS Map is already up to date (version 1)",
+        ))
+        .success();
+
+    std::fs::remove_file(&zone_file)?;
+    Ok(())
+}
@@ -16,10 +16,14 @@ use anyhow::{anyhow, Result};
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 use sn_cmd_test_utilities::{
-    get_random_nrs_string, parse_cat_seq_output, parse_seq_store_output, safeurl_from, CLI,
-    SAFE_PROTOCOL,
+    get_random_nrs_string, parse_cat_seq_output, parse_files_put_or_sync_output,
+    parse_nrs_create_output, parse_seq_store_output, safeurl_from, CLI, SAFE_PROTOCOL,
+};
+use std::{
+    env,
+    io::{BufRead, BufReader},
+    process::Command,
 };
-use std::{env, process::Command};
 
 const PRETTY_FILES_CREATION_RESPONSE: &str = "Public Sequence stored at: ";
 const PRETTY_FILES_PRIVATE_CREATION_RESPONSE: &str = "Private Sequence stored at: ";
@@ -236,3 +240,238 @@ fn calling_seq_store_and_fetch_with_nrsurl() -> Result<()> {
     assert_eq!(data, content.as_bytes());
     Ok(())
 }
+
+#[test]
+fn calling_safe_seq_log_and_tail() -> Result<()> {
+    let seq_store = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "store",
+        "event 0",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let seq_url = parse_seq_store_output(&seq_store);
+
+    for i in 1..4 {
+        let _ = cmd!(
+            env!("CARGO_BIN_EXE_safe"),
+            "seq",
+            "append",
+            format!("event {}", i),
+            &seq_url,
+            "--json"
+        )
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    }
+
+    let seq_log = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "log",
+        &seq_url,
+        "--from",
+        "1",
+        "--limit",
+        "2",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let entries: Vec<serde_json::Value> = serde_json::from_str(&seq_log)
+        .map_err(|_| anyhow!("Failed to parse output of `safe seq log`"))?;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["version"], 1);
+    assert_eq!(entries[0]["data"], "event 1");
+    assert_eq!(entries[1]["version"], 2);
+    assert_eq!(entries[1]["data"], "event 2");
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["seq", "tail", &seq_url, "--lines", "2"])
+        .assert()
+        .stdout(predicate::str::contains("[2] event 2"))
+        .stdout(predicate::str::contains("[3] event 3"))
+        .stdout(predicate::str::contains("event 1").not())
+        .success();
+
+    // entries are output one per line even with JSON, so they can be streamed
+    let seq_tail = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "tail",
+        &seq_url,
+        "--lines",
+        "2",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let entries = seq_tail
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<serde_json::Value>(line)
+                .map_err(|_| anyhow!("Failed to parse output of `safe seq tail`"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["data"], "event 2");
+    assert_eq!(entries[1]["data"], "event 3");
+    Ok(())
+}
+
+#[test]
+fn calling_safe_seq_tail_follow_with_nrsurl() -> Result<()> {
+    let seq_store = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "store",
+        "event 0",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let seq_url = parse_seq_store_output(&seq_store);
+
+    let mut safeurl = safeurl_from(&seq_url)?;
+    safeurl.set_content_version(Some(0));
+    let nrsurl = format!("safe://{}", get_random_nrs_string());
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "nrs",
+        "create",
+        &nrsurl,
+        "-l",
+        &safeurl.to_string(),
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    // the entries appended to the Sequence the NRS name links to are followed
+    let tail = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "tail",
+        &nrsurl,
+        "--follow"
+    )
+    .reader()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let mut lines = BufReader::new(&tail).lines();
+    let mut next_line = || -> Result<String> {
+        lines
+            .next()
+            .ok_or_else(|| anyhow!("`safe seq tail --follow` exited unexpectedly"))?
+            .map_err(|e| anyhow!(e.to_string()))
+    };
+    assert_eq!(next_line()?, "[0] event 0");
+
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "append",
+        "event 1",
+        &seq_url,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(next_line()?, "[1] event 1");
+
+    tail.kill().map_err(|e| anyhow!(e.to_string()))?;
+    Ok(())
+}
+
+#[test]
+fn calling_safe_seq_store_with_writer_and_perms_ls() -> Result<()> {
+    let writer = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    let seq_store = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "store",
+        "shared log",
+        "--writer",
+        writer,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let seq_url = parse_seq_store_output(&seq_store);
+
+    let perms_ls = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "perms",
+        "ls",
+        &seq_url,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let perms: serde_json::Value = serde_json::from_str(&perms_ls)
+        .map_err(|_| anyhow!("Failed to parse output of `safe seq perms ls`"))?;
+    assert_eq!(perms["private"], false);
+    let users = perms["users"]
+        .as_array()
+        .ok_or_else(|| anyhow!("No users found in permissions"))?;
+    assert_eq!(users.len(), 2);
+    assert!(users
+        .iter()
+        .any(|user| user["user"] == writer && user["append"] == true));
+    Ok(())
+}
+
+#[test]
+fn calling_safe_files_put_and_nrs_create_with_writer() -> Result<()> {
+    let writer = "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    let files_put = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "files",
+        "put",
+        "../testdata/test.md",
+        "--writer",
+        writer,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (container_xorurl, _) = parse_files_put_or_sync_output(&files_put);
+
+    let nrs_create = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "nrs",
+        "create",
+        &get_random_nrs_string(),
+        "-l",
+        &format!("{}?v=0", container_xorurl),
+        "--writer",
+        writer,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (nrs_map_xorurl, _) = parse_nrs_create_output(&nrs_create);
+
+    for url in &[container_xorurl, nrs_map_xorurl] {
+        let perms_ls = cmd!(
+            env!("CARGO_BIN_EXE_safe"),
+            "seq",
+            "perms",
+            "ls",
+            url,
+            "--json"
+        )
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+        let perms: serde_json::Value = serde_json::from_str(&perms_ls)
+            .map_err(|_| anyhow!("Failed to parse output of `safe seq perms ls`"))?;
+        let users = perms["users"]
+            .as_array()
+            .ok_or_else(|| anyhow!("No users found in permissions"))?;
+        assert!(users
+            .iter()
+            .any(|user| user["user"] == writer && user["append"] == true));
+    }
+    Ok(())
+}
@@ -15,11 +15,12 @@ extern crate duct;
 use anyhow::{anyhow, Result};
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
+use sn_api::wallet::WalletTransferReport;
 use sn_cmd_test_utilities::{
-    create_preload_and_get_keys, create_wallet_with_balance, get_random_nrs_string, CLI,
-    SAFE_PROTOCOL,
+    create_preload_and_get_keys, create_wallet_with_balance, get_random_nrs_string,
+    parse_cat_wallet_output, parse_wallet_create_output, CLI, SAFE_PROTOCOL,
 };
-use std::process::Command;
+use std::{fs, process::Command};
 
 const PRETTY_WALLET_CREATION_RESPONSE: &str = "Wallet created at";
 const UNMATCHED_SK_XORURL: &str =
@@ -301,6 +302,165 @@ fn calling_safe_wallet_insert() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn calling_safe_wallet_migrate() -> Result<()> {
+    let (wallet_xor, _pk, _sk) = create_wallet_with_balance("5", None)?;
+
+    // spendable balances inserted by this version are already encrypted
+    let migrated = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "migrate",
+        &wallet_xor,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (target, migrated): (String, Vec<String>) = serde_json::from_str(&migrated)
+        .map_err(|_| anyhow!("Failed to parse output of `safe wallet migrate`"))?;
+    assert_eq!(target, wallet_xor);
+    assert!(migrated.is_empty());
+
+    // and the Wallet can still be spent from
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["wallet", "balance", &wallet_xor, "--json"])
+        .assert()
+        .stdout("5.000000000\n")
+        .success();
+    Ok(())
+}
+
+#[test]
+fn calling_safe_wallet_watch_only_insert_and_balance() -> Result<()> {
+    let (wallet_xor, _pk, _sk) = create_wallet_with_balance("20", Some("spendable"))?;
+    let (key_pk_xor, _sk) = create_preload_and_get_keys("7")?;
+
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "insert",
+        &wallet_xor,
+        "--watch-only",
+        &key_pk_xor,
+        "--name",
+        "cold",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    // the watch-only balance isn't part of the spendable total
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["wallet", "balance", &wallet_xor, "--json"])
+        .assert()
+        .stdout("20.000000000\n")
+        .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "wallet",
+        "balance",
+        &wallet_xor,
+        "--watch-only",
+        "--json",
+    ])
+    .assert()
+    .stdout("7.000000000\n")
+    .success();
+
+    // its balance is also reported on its own, in any of the output formats
+    let cold_balance = format!("{}/cold", wallet_xor);
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["wallet", "balance", &cold_balance, "--json"])
+        .assert()
+        .stdout("7.000000000\n")
+        .success();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["wallet", "balance", &cold_balance])
+        .assert()
+        .stdout(predicate::str::contains("7.000000000 safecoins"))
+        .success();
+
+    // and it cannot be set as the default
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["wallet", "set-default", &wallet_xor, "cold"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn calling_safe_wallet_rename_set_default_and_remove() -> Result<()> {
+    let (wallet_xor, _pk, _sk) = create_wallet_with_balance("5", Some("first"))?;
+    let (key_pk_xor, sk) = create_preload_and_get_keys("3")?;
+
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "insert",
+        &wallet_xor,
+        "--keyurl",
+        &key_pk_xor,
+        "--sk",
+        &sk,
+        "--name",
+        "second",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "rename",
+        &wallet_xor,
+        "second",
+        "savings",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "set-default",
+        &wallet_xor,
+        "savings",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let wallet_cat = cmd!(env!("CARGO_BIN_EXE_safe"), "cat", &wallet_xor, "--json")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    let (_, balances) = parse_cat_wallet_output(&wallet_cat);
+    assert!(balances["savings"].0);
+    assert!(!balances["first"].0);
+    assert!(!balances.contains_key("second"));
+
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "remove",
+        &wallet_xor,
+        "first",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["wallet", "balance", &wallet_xor, "--json"])
+        .assert()
+        .stdout("3.000000000\n")
+        .success();
+    Ok(())
+}
+
 #[test]
 fn calling_safe_wallet_create_no_source() -> Result<()> {
     let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
@@ -372,6 +532,65 @@ fn calling_safe_wallet_create_w_premade_keys_has_balance() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn calling_safe_wallet_create_with_mnemonic() -> Result<()> {
+    let output = cmd!(env!("CARGO_BIN_EXE_safe"), "keys", "derive", "--json")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    let (mnemonic, _): (Option<String>, serde_json::Value) =
+        serde_json::from_str(&output).map_err(|e| anyhow!(e.to_string()))?;
+    let mnemonic = mnemonic.ok_or_else(|| anyhow!("No mnemonic phrase was generated"))?;
+
+    // none of the SafeKeys derived from the mnemonic have been used yet
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "create",
+        "--mnemonic",
+        "--test-coins",
+        "--preload",
+        "7",
+        "--json"
+    )
+    .stdin_bytes(mnemonic.as_str())
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (_, safekey_xorurl, _) = parse_wallet_create_output(&output);
+
+    // the spendable balance can be restored in a new Wallet from the mnemonic alone
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "create",
+        "--mnemonic",
+        "--json"
+    )
+    .stdin_bytes(mnemonic.as_str())
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let (wallet_xorurl, restored): (String, Vec<(u32, String)>) =
+        serde_json::from_str(&output).map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(restored, vec![(0, "mnemonic-0".to_string())]);
+
+    let balance = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "balance",
+        &format!("{}/mnemonic-0", wallet_xorurl),
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!("7.000000000", balance);
+
+    let output = cmd!(env!("CARGO_BIN_EXE_safe"), "cat", &wallet_xorurl, "--json")
+        .read()
+        .map_err(|e| anyhow!(e.to_string()))?;
+    let (_, balances) = parse_cat_wallet_output(&output);
+    assert_eq!(balances["mnemonic-0"].1.xorurl, safekey_xorurl);
+    Ok(())
+}
+
 #[test]
 fn calling_safe_wallet_create_w_sk_only() -> Result<()> {
     let (_xorurl, sk) = create_preload_and_get_keys("333")?;
@@ -525,6 +744,292 @@ fn calling_safe_wallet_transfer_to_key_xorurl() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn calling_safe_wallet_transfer_with_coin_selection() -> Result<()> {
+    let (wallet_from, _pk, _sk) = create_wallet_with_balance("5.000000001", None)?; // we need 1 nano to pay for the costs of creation
+    let (second_key_xorurl, second_sk) = create_preload_and_get_keys("7")?;
+    let (key_xorurl, key_sk) = create_preload_and_get_keys("0.0")?;
+
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "insert",
+        &wallet_from,
+        "--keyurl",
+        &second_key_xorurl,
+        "--sk",
+        &second_sk,
+        "--name",
+        "second-balance",
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    // neither spendable balance can fund the transfer on its own
+    let transfer = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "transfer",
+        "10",
+        "--from",
+        &wallet_from,
+        "--to",
+        &key_xorurl,
+        "--coin-selection",
+        "largest-first",
+        "--json",
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let report: WalletTransferReport = serde_json::from_str(&transfer)
+        .map_err(|_| anyhow!("Failed to parse output of `safe wallet transfer`"))?;
+    assert!(report.is_complete());
+    let legs: Vec<(&str, &str)> = report
+        .legs
+        .iter()
+        .map(|leg| (leg.balance_name.as_str(), leg.amount.as_str()))
+        .collect();
+    assert_eq!(
+        legs,
+        vec![
+            ("second-balance", "7.000000000"),
+            ("default-balance", "3.000000000")
+        ]
+    );
+
+    let key_has = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "balance",
+        "--sk",
+        &key_sk,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(key_has, "10.000000000");
+
+    // explicitly choosing a spendable balance which doesn't have enough
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "wallet",
+        "transfer",
+        "2.5",
+        "--from",
+        &wallet_from,
+        "--to",
+        &key_xorurl,
+        "--from-balances",
+        "second-balance,default-balance",
+    ])
+    .assert()
+    .stderr(predicate::str::contains("Not enough balance"))
+    .failure();
+    Ok(())
+}
+
+#[test]
+fn calling_safe_wallet_transfer_batch() -> Result<()> {
+    let (wallet_from, _pk, _sk) = create_wallet_with_balance("20.000000001", None)?; // we need 1 nano to pay for the costs of creation
+    let (key1_xorurl, key1_sk) = create_preload_and_get_keys("0.0")?;
+    let (key2_xorurl, key2_sk) = create_preload_and_get_keys("0.0")?;
+
+    let batch_dir = std::env::temp_dir().join(format!("batch-{}", get_random_nrs_string()));
+    fs::create_dir_all(&batch_dir)?;
+    let batch_file = batch_dir.join("payouts.csv");
+    fs::write(
+        &batch_file,
+        format!(
+            "to,amount,memo\n{},1.5,first payout\n{},2.25,\n",
+            key1_xorurl, key2_xorurl
+        ),
+    )?;
+    let batch_path = batch_file.display().to_string();
+
+    // an invalid destination makes the whole batch to be rejected
+    let invalid_file = batch_dir.join("invalid.csv");
+    fs::write(
+        &invalid_file,
+        format!("to,amount\n{},1\nnot-a-destination,1\n", key1_xorurl),
+    )?;
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "wallet",
+        "transfer",
+        "--from",
+        &wallet_from,
+        "--batch",
+        &invalid_file.display().to_string(),
+    ])
+    .assert()
+    .stderr(predicate::str::contains("row 2"))
+    .failure();
+
+    // ...and so does a total greater than the balance
+    let too_much_file = batch_dir.join("too-much.csv");
+    fs::write(
+        &too_much_file,
+        format!("to,amount\n{},15\n{},15\n", key1_xorurl, key2_xorurl),
+    )?;
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "wallet",
+        "transfer",
+        "--from",
+        &wallet_from,
+        "--batch",
+        &too_much_file.display().to_string(),
+    ])
+    .assert()
+    .stderr(predicate::str::contains("Not enough balance"))
+    .failure();
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec![
+        "wallet",
+        "transfer",
+        "--from",
+        &wallet_from,
+        "--batch",
+        &batch_path,
+    ])
+    .assert()
+    .stdout(predicate::str::contains("done").count(2))
+    .success();
+
+    let results = fs::read_to_string(batch_dir.join("payouts.results.csv"))?;
+    assert!(results.contains("first payout"));
+
+    // re-running the batch doesn't make the transfers again
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "transfer",
+        "--from",
+        &wallet_from,
+        "--batch",
+        &batch_path,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let results: Vec<serde_json::Value> = serde_json::from_str(&output)
+        .map_err(|_| anyhow!("Failed to parse output of `safe wallet transfer --batch`"))?;
+    assert_eq!(results.len(), 2);
+    assert!(results
+        .iter()
+        .all(|result| result["status"] == "skipped" && result["tx_id"].is_u64()));
+
+    // ...not even when the source Wallet is referenced with an NRS-URL this time
+    let wallet_nrsurl = format!("safe://{}", get_random_nrs_string());
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "nrs",
+        "create",
+        &wallet_nrsurl,
+        "-l",
+        &wallet_from,
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "transfer",
+        "--from",
+        &wallet_nrsurl,
+        "--batch",
+        &batch_path,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let results: Vec<serde_json::Value> = serde_json::from_str(&output)
+        .map_err(|_| anyhow!("Failed to parse output of `safe wallet transfer --batch`"))?;
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(|result| result["status"] == "skipped"));
+
+    // rows are matched by their content, thus reordering them and inserting a new one
+    // only makes the transfer of the new row
+    let (key3_xorurl, key3_sk) = create_preload_and_get_keys("0.0")?;
+    fs::write(
+        &batch_file,
+        format!(
+            "to,amount,memo\n{},0.75,new payout\n{},2.25,\n{},1.5,first payout\n",
+            key3_xorurl, key2_xorurl, key1_xorurl
+        ),
+    )?;
+    let output = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "transfer",
+        "--from",
+        &wallet_from,
+        "--batch",
+        &batch_path,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let results: Vec<serde_json::Value> = serde_json::from_str(&output)
+        .map_err(|_| anyhow!("Failed to parse output of `safe wallet transfer --batch`"))?;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["status"], "done");
+    assert_eq!(results[1]["status"], "skipped");
+    assert_eq!(results[2]["status"], "skipped");
+
+    let key3_has = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "balance",
+        "--sk",
+        &key3_sk,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(key3_has, "0.750000000");
+
+    let key1_has = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "balance",
+        "--sk",
+        &key1_sk,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(key1_has, "1.500000000");
+
+    let key2_has = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "keys",
+        "balance",
+        "--sk",
+        &key2_sk,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(key2_has, "2.250000000");
+
+    let from_has = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "wallet",
+        "balance",
+        &wallet_from,
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    assert_eq!(from_has, "15.500000000" /* 20 - 1.5 - 2.25 - 0.75 */);
+
+    fs::remove_dir_all(&batch_dir)?;
+    Ok(())
+}
+
 #[test]
 fn calling_safe_wallet_transfer_to_key_nrsurl() -> Result<()> {
     let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
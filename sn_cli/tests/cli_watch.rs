@@ -0,0 +1,101 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under the MIT license <LICENSE-MIT
+// http://opensource.org/licenses/MIT> or the Modified BSD license <LICENSE-BSD
+// https://opensource.org/licenses/BSD-3-Clause>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms. Please review the Licences for the
+// specific language governing permissions and limitations relating to use of the SAFE Network
+// Software.
+
+extern crate sn_cmd_test_utilities;
+
+#[macro_use]
+extern crate duct;
+
+use anyhow::{anyhow, Result};
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+use sn_cmd_test_utilities::{parse_seq_store_output, CLI};
+use std::{
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+#[test]
+fn calling_safe_watch_with_version() -> Result<()> {
+    let seq_store = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "store",
+        "first item",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let seq_url = parse_seq_store_output(&seq_store);
+
+    let mut cmd = Command::cargo_bin(CLI).map_err(|e| anyhow!(e.to_string()))?;
+    cmd.args(&vec!["watch", &format!("{}?v=0", seq_url)])
+        .assert()
+        .stderr(predicate::str::contains("cannot contain a version"))
+        .failure();
+    Ok(())
+}
+
+#[test]
+fn calling_safe_watch_with_exec() -> Result<()> {
+    let seq_store = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "store",
+        "first item",
+        "--json"
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let seq_url = parse_seq_store_output(&seq_store);
+
+    let mut watch = Command::cargo_bin(CLI)
+        .map_err(|e| anyhow!(e.to_string()))?
+        .args(&[
+            "watch",
+            &seq_url,
+            "--exec",
+            "echo \"exec: $SAFE_WATCH_XORURL\"",
+        ])
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    // let the watch find the current version before appending the new one
+    thread::sleep(Duration::from_secs(5));
+    let _ = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "seq",
+        "append",
+        "second item",
+        &seq_url
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+
+    let stdout = watch
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow!("Failed to read output of `safe watch`"))?;
+    let mut lines = BufReader::new(stdout).lines();
+    let new_version = lines.next().transpose()?;
+    let exec_output = lines.next().transpose()?;
+    watch.kill()?;
+
+    assert_eq!(
+        new_version,
+        Some(format!(
+            "New version of \"{}\" found: {}?v=1",
+            seq_url, seq_url
+        ))
+    );
+    assert_eq!(exec_output, Some(format!("exec: {}?v=1", seq_url)));
+    Ok(())
+}
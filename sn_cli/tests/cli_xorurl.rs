@@ -15,7 +15,7 @@ extern crate duct;
 use anyhow::{anyhow, Result};
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
-use sn_api::safeurl::SafeUrl;
+use sn_api::safeurl::{SafeContentType, SafeDataType, SafeUrl};
 use sn_cmd_test_utilities::{parse_xorurl_output, safeurl_from, CLI, SAFE_PROTOCOL};
 use std::process::Command;
 
@@ -72,3 +72,47 @@ fn calling_safe_xorurl_decode() -> Result<()> {
     assert_eq!(safeurl, decoded_obj);
     Ok(())
 }
+
+#[test]
+fn calling_safe_xorurl_encode_and_convert() -> Result<()> {
+    let xorname_hex = "3132333435363738393031323334353637383930313233343536373839303132";
+    let xorurl = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "xorurl",
+        "encode",
+        "--xorname",
+        xorname_hex,
+        "--type-tag",
+        "1100",
+        "--data-type",
+        "PublicSequence",
+        "--content-type",
+        "FilesContainer",
+        "--json",
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let xorurl: String = serde_json::from_str(&xorurl)?;
+
+    let safeurl = safeurl_from(&xorurl)?;
+    assert_eq!(safeurl.type_tag(), 1100);
+    assert_eq!(safeurl.data_type(), SafeDataType::PublicSequence);
+    assert_eq!(safeurl.content_type(), SafeContentType::FilesContainer);
+
+    let converted = cmd!(
+        env!("CARGO_BIN_EXE_safe"),
+        "xorurl",
+        "convert",
+        &xorurl,
+        "--to",
+        "base58btc",
+        "--json",
+    )
+    .read()
+    .map_err(|e| anyhow!(e.to_string()))?;
+    let converted: String = serde_json::from_str(&converted)?;
+
+    assert!(converted.starts_with("safe://z"));
+    assert_eq!(safeurl_from(&converted)?, safeurl);
+    Ok(())
+}